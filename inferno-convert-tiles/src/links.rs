@@ -0,0 +1,75 @@
+//! The second pass of a conversion, which reads the converted tiles back from the output and
+//! checks the links between them. Only a tile and the tiles its edges lead into are in memory at
+//! once.
+//!
+//! Broken links are logged rather than failing the conversion, since edges in an extract often
+//! lead into tiles outside it.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use inferno_tiles::inferno::{
+    archive::{tile_archive_ids, tile_archive_path, MappedTile},
+    integrity::{check_tile_links, linked_tile_ids},
+    pack::TilePack,
+    tile_ref::AsTileRef,
+};
+use tracing::warn;
+
+/// Checks the links between the tiles in a pack written by this conversion, returning the number
+/// of broken links.
+pub fn check_pack_links(path: &Path) -> Result<usize, anyhow::Error> {
+    // Safety: The pack was written by this process, and nothing else writes to it.
+    let pack = unsafe { TilePack::open_trusted(path)? };
+    let mut broken = 0;
+    for tile_id in pack.tile_ids() {
+        let tile = pack.tile(&tile_id).expect("Tile is in the pack");
+        broken += report(check_tile_links(&tile, |tile_id| pack.tile(tile_id)));
+    }
+    Ok(broken)
+}
+
+/// Checks the links between the tile archives written to `dir` by this conversion, returning the
+/// number of broken links.
+pub fn check_archive_links(dir: &Path) -> Result<usize, anyhow::Error> {
+    let tile_ids = tile_archive_ids(dir)?;
+    let available: BTreeSet<_> = tile_ids.iter().copied().collect();
+    // Tiles are checked in ID order, so neighboring tiles are mostly checked one after another
+    // and the tiles they lead into are kept mapped between them.
+    let mut mapped = HashMap::new();
+    let mut broken = 0;
+    for tile_id in &tile_ids {
+        if !mapped.contains_key(tile_id) {
+            mapped.insert(*tile_id, map_archive(&tile_archive_path(dir, tile_id))?);
+        }
+        let mut needed = linked_tile_ids(&mapped[tile_id].tile_ref());
+        needed.retain(|tile_id| available.contains(tile_id));
+        needed.insert(*tile_id);
+        mapped.retain(|tile_id, _| needed.contains(tile_id));
+        for tile_id in &needed {
+            if !mapped.contains_key(tile_id) {
+                mapped.insert(*tile_id, map_archive(&tile_archive_path(dir, tile_id))?);
+            }
+        }
+
+        let tile = mapped[tile_id].tile_ref();
+        broken += report(check_tile_links(&tile, |tile_id| {
+            mapped.get(tile_id).map(|tile| tile.tile_ref())
+        }));
+    }
+    Ok(broken)
+}
+
+fn map_archive(path: &Path) -> Result<MappedTile, anyhow::Error> {
+    // Safety: The archive was written by this process, and nothing else writes to it.
+    unsafe { MappedTile::open(path) }
+}
+
+fn report(problems: Vec<String>) -> usize {
+    for problem in &problems {
+        warn!("{}", problem);
+    }
+    problems.len()
+}
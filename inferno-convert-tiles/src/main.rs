@@ -1,15 +1,42 @@
-use std::{fs, io::Read};
+mod links;
+mod pipeline;
+
+use std::{fs, io::Read, path::Path, thread, time::SystemTime};
 
 use clap::Parser;
-use inferno_tiles::inferno::{graph::InfernoTileGraph, InfernoTile};
+use inferno_tiles::inferno::{
+    archive::write_tile_archive,
+    pack::{verify_tile_pack, TilePackWriter},
+    tarball::ValhallaTarball,
+    tile_dir::tile_files_in_dir,
+    InfernoTile,
+};
+use links::{check_archive_links, check_pack_links};
+use pipeline::{convert_tiles, RawTile, TileSender};
 use tracing::{debug, info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 #[derive(Debug, Parser)]
 struct Args {
-    /// Valhalla tile tarball to convert.
+    /// Valhalla tile tarball, or directory of `.gph` or `.gph.gz` tiles, to convert.
     #[clap(short, long)]
     input: String,
+    /// Directory to write converted tiles to, one archive per tile. See
+    /// `inferno_tiles::inferno::archive` for the layout.
+    #[clap(short, long)]
+    output: Option<String>,
+    /// Single file tile pack to write converted tiles to. See `inferno_tiles::inferno::pack` for
+    /// the format.
+    #[clap(short, long)]
+    pack: Option<String>,
+    /// Verify the tile pack given by `--input`, reporting every corrupt tile, instead of
+    /// converting tiles.
+    #[clap(long)]
+    verify: bool,
+    /// Number of threads to convert tiles on. Defaults to the number of available CPUs. The
+    /// output is the same whatever the number of threads.
+    #[clap(short, long)]
+    jobs: Option<usize>,
     /// Log level.
     #[clap(short, long, default_value_t = Level::INFO)]
     log_level: Level,
@@ -18,13 +45,140 @@ struct Args {
 fn main() -> Result<(), anyhow::Error> {
     let args = Args::parse();
     let subscriber = FmtSubscriber::builder()
-        .with_max_level(args.log_level.clone())
+        .with_max_level(args.log_level)
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
-    let mut archive = tar::Archive::new(fs::File::open(&args.input)?);
-    let mut tiles = Vec::new();
+    let input = Path::new(&args.input);
+    if args.verify {
+        return verify_pack(input);
+    }
+    let jobs = args.jobs.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(|jobs| jobs.get())
+            .unwrap_or(1)
+    });
+    info!("Converting tiles on {} threads", jobs);
+    let output = args.output.as_deref().map(Path::new);
+    let pack = args.pack.as_deref().map(Path::new);
+    let mut pack_writer = pack
+        .map(|pack| TilePackWriter::create(pack, SystemTime::now()))
+        .transpose()?;
+    // Each tile is written as soon as it's converted, so only the tiles being converted are in
+    // memory at once.
+    let write = |tile: InfernoTile| {
+        if let Some(output) = output {
+            write_tile_archive(output, &tile)?;
+        }
+        if let Some(pack_writer) = &mut pack_writer {
+            pack_writer.add_tile(&tile)?;
+        }
+        Ok(())
+    };
+    let converted = if input.is_dir() {
+        convert_tiles(jobs, |sender| read_dir(input, sender), write)?
+    } else {
+        match ValhallaTarball::open(input) {
+            Ok(tarball) => convert_tiles(jobs, |sender| read_indexed(tarball, sender), write)?,
+            Err(err) => {
+                warn!(
+                    "Unable to use the tarball's index, scanning it instead: {}",
+                    err
+                );
+                convert_tiles(jobs, |sender| read_scanned(input, sender), write)?
+            }
+        }
+    };
+    info!("Converted {} tiles", converted);
+    if let Some(output) = output {
+        info!("Wrote {} tiles to {:?}", converted, output);
+    }
+    if let (Some(pack), Some(pack_writer)) = (pack, pack_writer) {
+        let header = pack_writer.finish()?;
+        info!("Wrote a pack of {} tiles to {:?}", header.tile_count, pack);
+    }
+
+    info!("Checking links between tiles...");
+    let broken = if let Some(pack) = pack {
+        check_pack_links(pack)?
+    } else if let Some(output) = output {
+        check_archive_links(output)?
+    } else {
+        warn!("Not checking links between tiles, since no tiles were written");
+        return Ok(());
+    };
+    if broken == 0 {
+        info!("All links between tiles are valid!");
+    } else {
+        warn!("Found {} broken links between tiles", broken);
+    }
+
+    Ok(())
+}
+
+/// Validates every tile in a tile pack.
+fn verify_pack(path: &Path) -> Result<(), anyhow::Error> {
+    info!("Verifying {:?}...", path);
+    let corrupt = verify_tile_pack(path)?;
+    if !corrupt.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Found {} corrupt tiles in {:?}",
+            corrupt.len(),
+            path
+        ));
+    }
+    info!("All tiles in {:?} are valid", path);
+    Ok(())
+}
+
+/// Reads every tile in a Valhalla tile directory.
+fn read_dir(dir: &Path, sender: &mut TileSender) -> Result<(), anyhow::Error> {
+    let paths = tile_files_in_dir(dir)?;
+    info!("Converting {} tiles from {:?}", paths.len(), dir);
+    for path in paths {
+        match fs::read(&path) {
+            Ok(bytes) => sender.send(RawTile {
+                name: format!("{:?}", path),
+                path: Some(path),
+                bytes,
+            })?,
+            Err(err) => {
+                warn!("Failed to read {:?}: {}", path, err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads every tile listed in the tarball's index.bin.
+fn read_indexed(
+    mut tarball: ValhallaTarball,
+    sender: &mut TileSender,
+) -> Result<(), anyhow::Error> {
+    let tile_ids: Vec<_> = tarball.tile_ids().collect();
+    info!("Converting {} tiles from index", tile_ids.len());
+    for tile_id in tile_ids {
+        match tarball.read_tile_bytes(&tile_id) {
+            Ok(Some(bytes)) => sender.send(RawTile {
+                name: format!("tile {}", tile_id),
+                path: None,
+                bytes,
+            })?,
+            Ok(None) => {
+                warn!("Missing tile {} in tarball", tile_id);
+            }
+            Err(err) => {
+                warn!("Failed to read tile {}: {}", tile_id, err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads every tile in a tarball from start to end, for tarballs without an index.bin.
+fn read_scanned(input: &Path, sender: &mut TileSender) -> Result<(), anyhow::Error> {
+    let mut archive = tar::Archive::new(fs::File::open(input)?);
     for entry in archive.entries()? {
         let mut entry = entry?;
         let path = if let Some(path) = entry.path()?.to_str() {
@@ -37,23 +191,13 @@ fn main() -> Result<(), anyhow::Error> {
             debug!("Skipping conversion of index.bin");
             continue;
         }
-        info!(r#"Converting "{}"..."#, path);
         let mut bytes = Vec::new();
         entry.read_to_end(&mut bytes)?;
-        match InfernoTile::from_valhalla(&bytes) {
-            Ok(tile) => {
-                info!(r#"Successfully converted "{}"!"#, path);
-                tiles.push(tile);
-            }
-            Err(err) => {
-                warn!(r#"Failed to convert "{}": {}"#, path, err);
-            }
-        }
+        sender.send(RawTile {
+            name: format!(r#""{}""#, path),
+            path: None,
+            bytes,
+        })?;
     }
-
-    info!("Testing tile loading...");
-    InfernoTileGraph::new(&tiles);
-    info!("All tiles loaded successfully!");
-
     Ok(())
 }
@@ -0,0 +1,276 @@
+//! Converts tiles on a pool of worker threads. A reader thread reads raw tiles from the input one
+//! at a time and feeds them to the workers, and converted tiles are handed back in the order they
+//! were read, so the output doesn't depend on the number of workers. The reader is held to a few
+//! tiles ahead of the last tile handed back, so only those tiles are in memory at once.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Condvar, Mutex,
+    },
+    thread,
+};
+
+use inferno_tiles::inferno::{tile_dir::convert_tile_file, InfernoTile};
+use tracing::{info, warn};
+
+/// A tile read from the input, waiting to be converted.
+pub struct RawTile {
+    /// Describes where the tile was read from, for logging.
+    pub name: String,
+    /// The tile file the tile was read from, for tiles read from a directory, whose tile ID is
+    /// checked against the tile's header.
+    pub path: Option<PathBuf>,
+    pub bytes: Vec<u8>,
+}
+
+impl RawTile {
+    fn convert(self) -> Option<InfernoTile> {
+        info!("Converting {}...", self.name);
+        let tile = match &self.path {
+            Some(path) => convert_tile_file(path, self.bytes),
+            None => InfernoTile::from_valhalla(&self.bytes),
+        };
+        match tile {
+            Ok(tile) => {
+                info!("Successfully converted {}!", self.name);
+                Some(tile)
+            }
+            Err(err) => {
+                warn!("Failed to convert {}: {}", self.name, err);
+                None
+            }
+        }
+    }
+}
+
+/// Tracks the next tile to hand back, which the reader may only get `limit` tiles ahead of.
+struct ReadAhead {
+    /// The index of the next tile to hand back, or `None` once tiles are no longer handed back.
+    next_written: Mutex<Option<usize>>,
+    advanced: Condvar,
+    limit: usize,
+}
+
+impl ReadAhead {
+    /// Waits until the tile at `index` may be read, returning false if conversion has stopped.
+    fn wait_for(&self, index: usize) -> bool {
+        let mut next_written = self.next_written.lock().expect("Tile writer panicked");
+        loop {
+            match *next_written {
+                None => return false,
+                Some(next_written) if index < next_written + self.limit => return true,
+                Some(_) => {
+                    next_written = self
+                        .advanced
+                        .wait(next_written)
+                        .expect("Tile writer panicked")
+                }
+            }
+        }
+    }
+
+    fn set(&self, next_written: Option<usize>) {
+        *self.next_written.lock().expect("Tile reader panicked") = next_written;
+        self.advanced.notify_all();
+    }
+}
+
+/// Sends raw tiles from the reader thread to the workers, numbering them in the order they're
+/// read.
+pub struct TileSender<'a> {
+    sender: SyncSender<(usize, RawTile)>,
+    read_ahead: &'a ReadAhead,
+    next: usize,
+}
+
+impl TileSender<'_> {
+    /// Queues a tile for conversion, blocking while the reader is too far ahead. Fails if the
+    /// conversion has been abandoned.
+    pub fn send(&mut self, tile: RawTile) -> Result<(), anyhow::Error> {
+        let stopped = || anyhow::anyhow!("Tile conversion stopped before the input was read");
+        if !self.read_ahead.wait_for(self.next) {
+            return Err(stopped());
+        }
+        self.sender.send((self.next, tile)).map_err(|_| stopped())?;
+        self.next += 1;
+        Ok(())
+    }
+}
+
+/// Converts the tiles that `read` sends on `jobs` worker threads, passing each converted tile to
+/// `write` in the order `read` sent it. Tiles that fail to convert are logged and skipped.
+/// Returns the number of tiles written.
+pub fn convert_tiles(
+    jobs: usize,
+    read: impl FnOnce(&mut TileSender) -> Result<(), anyhow::Error> + Send,
+    write: impl FnMut(InfernoTile) -> Result<(), anyhow::Error>,
+) -> Result<usize, anyhow::Error> {
+    let jobs = jobs.max(1);
+    let read_ahead = ReadAhead {
+        next_written: Mutex::new(Some(0)),
+        advanced: Condvar::new(),
+        limit: jobs * 4,
+    };
+    // The channels hold as many tiles as the reader can get ahead, so sending never blocks and
+    // stopping `read_ahead` is enough to stop the reader.
+    let (raw_sender, raw_receiver) = mpsc::sync_channel(read_ahead.limit);
+    let (converted_sender, converted_receiver) = mpsc::sync_channel(read_ahead.limit);
+    let raw_receiver = Mutex::new(raw_receiver);
+    thread::scope(|scope| {
+        let read_ahead = &read_ahead;
+        let reader = scope.spawn(move || {
+            read(&mut TileSender {
+                sender: raw_sender,
+                read_ahead,
+                next: 0,
+            })
+        });
+        for _ in 0..jobs {
+            let converted_sender = converted_sender.clone();
+            let raw_receiver = &raw_receiver;
+            scope.spawn(move || loop {
+                let Ok((index, raw)) = raw_receiver
+                    .lock()
+                    .expect("Tile conversion thread panicked")
+                    .recv()
+                else {
+                    break;
+                };
+                if converted_sender.send((index, raw.convert())).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(converted_sender);
+
+        // If writing fails, stop the reader, and drop the receiver so the workers stop too.
+        let written = write_in_order(converted_receiver, read_ahead, write);
+        read_ahead.set(None);
+        let read = reader.join().expect("Tile reader thread panicked");
+        let written = written?;
+        read?;
+        Ok(written)
+    })
+}
+
+/// Writes converted tiles in the order they were read, holding back any that finish before the
+/// tiles read ahead of them.
+fn write_in_order(
+    receiver: Receiver<(usize, Option<InfernoTile>)>,
+    read_ahead: &ReadAhead,
+    mut write: impl FnMut(InfernoTile) -> Result<(), anyhow::Error>,
+) -> Result<usize, anyhow::Error> {
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    let mut written = 0;
+    for (index, tile) in receiver {
+        pending.insert(index, tile);
+        while let Some(tile) = pending.remove(&next) {
+            next += 1;
+            if let Some(tile) = tile {
+                write(tile)?;
+                written += 1;
+            }
+        }
+        read_ahead.set(Some(next));
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use inferno_tiles::{
+        geomath::LatLng,
+        inferno::builder::{EdgeAttributes, GraphBuilder},
+    };
+
+    use super::*;
+
+    /// Valhalla tiles in a row of local tiles, the first few much larger than the rest so they
+    /// take longer to convert.
+    fn valhalla_tiles() -> Vec<Vec<u8>> {
+        let mut builder = GraphBuilder::new();
+        for tile in 0..12 {
+            let node_count = if tile < 3 { 2000 } else { 2 + tile };
+            let mut previous = None;
+            for node in 0..node_count {
+                let position = LatLng::new(47.6 + node as f64 * 1e-5, -122.4 + tile as f64 * 0.25);
+                let node = builder.add_node(position);
+                if let Some(previous) = previous {
+                    builder.add_edge(previous, node, EdgeAttributes::default());
+                }
+                previous = Some(node);
+            }
+        }
+        builder.build().unwrap().valhalla_tiles().unwrap()
+    }
+
+    fn convert(jobs: usize, tiles: &[Vec<u8>]) -> Vec<InfernoTile> {
+        let mut written = Vec::new();
+        let count = convert_tiles(
+            jobs,
+            |sender| {
+                for (index, bytes) in tiles.iter().enumerate() {
+                    sender.send(RawTile {
+                        name: format!("tile {}", index),
+                        path: None,
+                        bytes: bytes.clone(),
+                    })?;
+                }
+                Ok(())
+            },
+            |tile| {
+                written.push(tile);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(count, written.len());
+        written
+    }
+
+    #[test]
+    fn output_does_not_depend_on_jobs() {
+        let mut tiles = valhalla_tiles();
+        // Read the small tiles between the large ones, and include one that fails to convert.
+        tiles.swap(1, 7);
+        tiles.insert(5, vec![0; 16]);
+        let expected: Vec<_> = tiles
+            .iter()
+            .filter_map(|bytes| InfernoTile::from_valhalla(bytes).ok())
+            .collect();
+        assert_eq!(expected.len(), 12);
+
+        assert_eq!(convert(1, &tiles), expected);
+        for _ in 0..4 {
+            assert_eq!(convert(8, &tiles), expected);
+        }
+    }
+
+    #[test]
+    fn stops_reading_when_writing_fails() {
+        let tiles = valhalla_tiles();
+        let mut sent = 0;
+        let result = convert_tiles(
+            2,
+            |sender| {
+                for bytes in &tiles {
+                    sender.send(RawTile {
+                        name: "tile".to_string(),
+                        path: None,
+                        bytes: bytes.clone(),
+                    })?;
+                    sent += 1;
+                }
+                Ok(())
+            },
+            |_tile| Err(anyhow::anyhow!("Disk full")),
+        );
+        assert_eq!(result.unwrap_err().to_string(), "Disk full");
+        // The reader stops once it's the read ahead limit past the first tile.
+        assert!(sent <= 2 * 4, "Read {} tiles", sent);
+    }
+}
@@ -1,6 +1,21 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, KnownLayout};
+
 pub static EARTH_RADIUS_APPROX: f64 = 6_371_000f64;
 
-#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub struct LatLng(f64, f64);
 
 impl LatLng {
@@ -39,3 +54,13 @@ pub fn cartesian_to_lat_lng(coords: [f64; 3]) -> LatLng {
     let lng = lng.to_degrees();
     LatLng::new(lat, lng)
 }
+
+/// Great-circle distance between two points in meters, using the haversine formula.
+pub fn distance_meters(a: &LatLng, b: &LatLng) -> f64 {
+    let lat1 = a.0.to_radians();
+    let lat2 = b.0.to_radians();
+    let dlat = lat2 - lat1;
+    let dlng = (b.1 - a.1).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_APPROX * h.sqrt().min(1.0).asin()
+}
@@ -0,0 +1,47 @@
+use crate::valhalla::admin::ValhallaAdmin;
+
+use super::text_list::TextListRef;
+
+/// An administrative area (country and state/province), resolved from the tile's text list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Admin<'a> {
+    country_name: &'a str,
+    state_name: &'a str,
+    country_iso: &'a str,
+    state_iso: &'a str,
+}
+
+impl<'a> Admin<'a> {
+    pub(crate) fn new(admin: &'a ValhallaAdmin, text_list: TextListRef<'a>) -> Admin<'a> {
+        Admin {
+            country_name: text_list.get(admin.country_offset as usize).unwrap_or(""),
+            state_name: text_list.get(admin.state_offset as usize).unwrap_or(""),
+            country_iso: iso_code(&admin.country_iso),
+            state_iso: iso_code(&admin.state_iso),
+        }
+    }
+
+    pub fn country_name(&self) -> &'a str {
+        self.country_name
+    }
+
+    pub fn state_name(&self) -> &'a str {
+        self.state_name
+    }
+
+    /// ISO 3166-1 alpha-2 country code, or an empty string if unknown.
+    pub fn country_iso(&self) -> &'a str {
+        self.country_iso
+    }
+
+    /// ISO 3166-2 subdivision code (without the country prefix), or an empty string if unknown.
+    pub fn state_iso(&self) -> &'a str {
+        self.state_iso
+    }
+}
+
+/// ISO codes are stored in fixed-size arrays, NUL-padded when shorter than the array.
+fn iso_code(bytes: &[u8]) -> &str {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).unwrap_or("")
+}
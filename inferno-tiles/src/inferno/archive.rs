@@ -0,0 +1,371 @@
+//! Converted tiles on disk.
+//!
+//! Tiles are written one file per tile, at `<dir>/<level>/<nnn>/<nnn>[/<nnn>].inferno`, the same
+//! layout Valhalla uses for its tile directories (see [`tile_path`]). Each file is a 16 byte
+//! header followed by the rkyv archive of an [`InfernoTile`]:
+//!
+//! | Offset | Size | Contents                                              |
+//! |--------|------|-------------------------------------------------------|
+//! | 0      | 8    | Magic bytes `INFERNO\0`                               |
+//! | 8      | 4    | Format version, little endian                         |
+//! | 12     | 4    | CRC-32 of the archive, little endian                  |
+//! | 16     | rest | rkyv archive of the tile, with its root at the end    |
+//!
+//! The header is 16 bytes so the archive stays aligned when the whole file is read into an
+//! aligned buffer or memory mapped. A mapped archive can be queried in place through
+//! [`MappedTile`], without deserializing it. Archives are validated when they're loaded, see
+//! [`integrity`](super::integrity).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use memmap2::Mmap;
+use rkyv::{rancor, util::AlignedVec};
+use tracing::instrument;
+
+use crate::valhalla::graph_id::TileId;
+
+use super::{
+    integrity::{check_archive, CorruptTileError, Corruption},
+    tile_dir::{files_with_suffixes, tile_id_from_path, tile_path},
+    tile_ref::{AsTileRef, TileRef},
+    ArchivedInfernoTile, InfernoTile,
+};
+
+/// File extension of tile archives.
+pub const TILE_ARCHIVE_EXTENSION: &str = "inferno";
+
+const MAGIC: &[u8; 8] = b"INFERNO\0";
+const FORMAT_VERSION: u32 = 4;
+const ARCHIVE_HEADER_SIZE: usize = 16;
+/// Alignment rkyv needs for the start of an archive.
+const ARCHIVE_ALIGNMENT: usize = 16;
+
+/// Serializes a tile into the tile archive format.
+pub fn tile_to_archive_bytes(tile: &InfernoTile) -> Result<Vec<u8>, anyhow::Error> {
+    let archive = rkyv::to_bytes::<rancor::Error>(tile)
+        .map_err(|err| anyhow::anyhow!("Failed to serialize tile {}: {}", tile.tile_id(), err))?;
+    let mut bytes = Vec::with_capacity(ARCHIVE_HEADER_SIZE + archive.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(&archive).to_le_bytes());
+    bytes.extend_from_slice(&archive);
+    Ok(bytes)
+}
+
+/// Checks the header of a tile archive and returns the rkyv archive that follows it, along with
+/// its checksum.
+fn archive_payload(bytes: &[u8]) -> Result<(&[u8], u32), anyhow::Error> {
+    if bytes.len() < ARCHIVE_HEADER_SIZE || &bytes[0..8] != MAGIC {
+        return Err(Corruption::MissingHeader.into());
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().expect("Slice is 4 bytes"));
+    if version != FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Unsupported tile archive format version {}",
+            version
+        ));
+    }
+    let checksum = u32::from_le_bytes(bytes[12..16].try_into().expect("Slice is 4 bytes"));
+    Ok((&bytes[ARCHIVE_HEADER_SIZE..], checksum))
+}
+
+/// Validates a tile archive, which must be aligned, and returns the archived tile.
+fn check_tile_archive(bytes: &[u8]) -> Result<&ArchivedInfernoTile, anyhow::Error> {
+    let (payload, checksum) = archive_payload(bytes)?;
+    Ok(check_archive(payload, checksum)?)
+}
+
+/// Adds the path of the archive to an error, naming the tile if the archive is corrupt.
+fn archive_error(err: anyhow::Error, path: &Path) -> anyhow::Error {
+    match err.downcast::<Corruption>() {
+        Ok(corruption) => CorruptTileError {
+            tile_id: tile_id_from_path(path),
+            path: path.to_path_buf(),
+            corruption,
+        }
+        .into(),
+        Err(err) => anyhow::anyhow!("Failed to load {:?}: {}", path, err),
+    }
+}
+
+/// Validates and deserializes a tile from the tile archive format. A corrupt archive is reported
+/// as a `Corruption` error.
+pub fn tile_from_archive_bytes(bytes: &[u8]) -> Result<InfernoTile, anyhow::Error> {
+    // rkyv needs the archive to be aligned, which a file read into a `Vec` may not be. The header
+    // is a multiple of the alignment, so aligning the file aligns the archive.
+    let mut aligned = AlignedVec::<ARCHIVE_ALIGNMENT>::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    let archived = check_tile_archive(&aligned)?;
+    rkyv::deserialize::<InfernoTile, rancor::Error>(archived)
+        .map_err(|err| anyhow::anyhow!("Failed to deserialize tile archive: {}", err))
+}
+
+/// Path of a tile's archive in the tile archive directory `dir`.
+pub fn tile_archive_path(dir: &Path, tile_id: &TileId) -> PathBuf {
+    dir.join(tile_path(tile_id, TILE_ARCHIVE_EXTENSION))
+}
+
+/// Writes a tile to its archive in `dir`, creating any missing directories, and returns the
+/// archive's path.
+#[instrument(skip(tile), fields(tile_id = %tile.tile_id()))]
+pub fn write_tile_archive(dir: &Path, tile: &InfernoTile) -> Result<PathBuf, anyhow::Error> {
+    let path = tile_archive_path(dir, &tile.tile_id());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, tile_to_archive_bytes(tile)?)?;
+    Ok(path)
+}
+
+/// Reads a tile from an archive file. A corrupt archive is reported as a `CorruptTileError`.
+#[instrument]
+pub fn load_tile_archive(path: &Path) -> Result<InfernoTile, anyhow::Error> {
+    tile_from_archive_bytes(&fs::read(path)?).map_err(|err| archive_error(err, path))
+}
+
+/// Reads every tile archive in `dir`, in tile path order.
+pub fn load_tile_archives(dir: &Path) -> Result<Vec<InfernoTile>, anyhow::Error> {
+    tile_archive_paths(dir)?
+        .iter()
+        .map(|path| load_tile_archive(path))
+        .collect()
+}
+
+/// Returns the IDs of the tiles with archives in `dir`, sorted.
+pub fn tile_archive_ids(dir: &Path) -> Result<Vec<TileId>, anyhow::Error> {
+    let mut tile_ids = tile_archive_paths(dir)?
+        .iter()
+        .map(|path| {
+            tile_id_from_path(path)
+                .ok_or_else(|| anyhow::anyhow!("Unable to determine tile ID from path {:?}", path))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tile_ids.sort_by_key(|tile_id| tile_id.id);
+    Ok(tile_ids)
+}
+
+fn tile_archive_paths(dir: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let suffix = format!(".{}", TILE_ARCHIVE_EXTENSION);
+    files_with_suffixes(dir, &[&suffix])
+}
+
+/// A memory mapped tile archive, queried in place. Opening one only maps the file, so the tile's
+/// records are paged in from disk as queries touch them and are shared between processes mapping
+/// the same file.
+#[derive(Debug)]
+pub struct MappedTile {
+    mmap: Mmap,
+}
+
+impl MappedTile {
+    /// Maps and validates a tile archive. A corrupt archive is reported as a
+    /// `CorruptTileError`.
+    ///
+    /// # Safety
+    ///
+    /// The archive must not be modified or truncated while it's mapped.
+    #[instrument]
+    pub unsafe fn open(path: &Path) -> Result<MappedTile, anyhow::Error> {
+        let mmap = Mmap::map(&fs::File::open(path)?)?;
+        check_tile_archive(&mmap).map_err(|err| archive_error(err, path))?;
+        Ok(MappedTile { mmap })
+    }
+
+    /// The archived tile.
+    pub fn archived(&self) -> &ArchivedInfernoTile {
+        let payload = &self.mmap[ARCHIVE_HEADER_SIZE..];
+        // SAFETY: `open` validated the archive, and the caller of `open` guarantees it hasn't
+        // changed since.
+        unsafe { rkyv::access_unchecked::<ArchivedInfernoTile>(payload) }
+    }
+}
+
+impl AsTileRef for MappedTile {
+    fn tile_ref(&self) -> TileRef<'_> {
+        self.archived().tile_ref()
+    }
+}
+
+/// Maps every tile archive in `dir`, in tile path order.
+///
+/// # Safety
+///
+/// As for [`MappedTile::open`], for every archive in `dir`.
+pub unsafe fn map_tile_archives(dir: &Path) -> Result<Vec<MappedTile>, anyhow::Error> {
+    tile_archive_paths(dir)?
+        .iter()
+        .map(|path| unsafe { MappedTile::open(path) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geomath::LatLng,
+        inferno::{
+            graph::{EdgeSearch, InfernoTileGraph},
+            test_tiles::sample_tile_bytes,
+        },
+        valhalla::{graph_id::GraphEntityId, node_info::ValhallaNodeInfoData1, HasEntityPointer},
+    };
+
+    use super::*;
+
+    #[test]
+    fn archive_round_trip() {
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let bytes = tile_to_archive_bytes(&tile).unwrap();
+        assert_eq!(&bytes[0..8], MAGIC);
+        assert_eq!(tile_from_archive_bytes(&bytes).unwrap(), tile);
+
+        // Reading from an unaligned buffer still works.
+        let mut unaligned = vec![0];
+        unaligned.extend_from_slice(&bytes);
+        assert_eq!(tile_from_archive_bytes(&unaligned[1..]).unwrap(), tile);
+    }
+
+    #[test]
+    fn archive_directory_round_trip() {
+        let dir = std::env::temp_dir().join(format!("inferno-archive-{}", std::process::id()));
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let path = write_tile_archive(&dir, &tile).unwrap();
+        assert!(path.ends_with("2/000/792/230.inferno"));
+        assert_eq!(load_tile_archives(&dir).unwrap(), vec![tile]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mapped_tiles_match_owned_tiles() {
+        let dir = std::env::temp_dir().join(format!("inferno-mapped-{}", std::process::id()));
+        let tiles = vec![InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap()];
+        write_tile_archive(&dir, &tiles[0]).unwrap();
+        // SAFETY: The archive was just written by `write_tile_archive`.
+        let mapped = unsafe { map_tile_archives(&dir) }.unwrap();
+        assert_eq!(mapped.len(), 1);
+
+        let owned_graph = InfernoTileGraph::with_edge_search(&tiles, EdgeSearch::Bins);
+        let mapped_graph = InfernoTileGraph::with_edge_search(&mapped, EdgeSearch::Bins);
+        let point = LatLng::new(47.605, -122.4);
+        let edges = mapped_graph.edges_for_point(&point, 100.0, 10);
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges, owned_graph.edges_for_point(&point, 100.0, 10));
+        for (edge, _distance) in edges {
+            assert_eq!(
+                mapped_graph.edge_shape(&edge).unwrap(),
+                owned_graph.edge_shape(&edge).unwrap()
+            );
+            assert_eq!(mapped_graph.way_id(&edge).unwrap(), 123_456);
+            let names = mapped_graph.edge_names(&edge).unwrap();
+            assert_eq!(names[0].text(), "Main Street");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let mut bytes = tile_to_archive_bytes(&tile).unwrap();
+        assert!(tile_from_archive_bytes(&bytes[..8]).is_err());
+        bytes[8] = FORMAT_VERSION as u8 + 1;
+        assert!(tile_from_archive_bytes(&bytes).is_err());
+        bytes[0] = b'X';
+        assert!(tile_from_archive_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_corrupt_archives() {
+        let dir = std::env::temp_dir().join(format!("inferno-corrupt-{}", std::process::id()));
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let path = write_tile_archive(&dir, &tile).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        // A truncated archive fails its checksum, as does one with flipped bits.
+        fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+        let err = load_tile_archive(&path).unwrap_err();
+        let err = err.downcast_ref::<CorruptTileError>().unwrap();
+        assert_eq!(err.tile_id, Some(tile.tile_id()));
+        assert!(matches!(
+            err.corruption,
+            Corruption::ChecksumMismatch { .. }
+        ));
+        let mut flipped = bytes.clone();
+        flipped[ARCHIVE_HEADER_SIZE + 100] ^= 0xff;
+        fs::write(&path, &flipped).unwrap();
+        // SAFETY: The archive isn't modified while it's mapped.
+        assert!(unsafe { MappedTile::open(&path) }
+            .unwrap_err()
+            .is::<CorruptTileError>());
+
+        // An archive that matches its checksum but not the tile layout fails validation.
+        let mut invalid = bytes[..ARCHIVE_HEADER_SIZE].to_vec();
+        invalid.extend_from_slice(&[0xff; 64]);
+        let checksum = crc32fast::hash(&invalid[ARCHIVE_HEADER_SIZE..]);
+        invalid[12..16].copy_from_slice(&checksum.to_le_bytes());
+        fs::write(&path, &invalid).unwrap();
+        let err = load_tile_archive(&path).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CorruptTileError>().unwrap().corruption,
+            Corruption::InvalidArchive(_)
+        ));
+
+        fs::write(&path, []).unwrap();
+        let err = load_tile_archive(&path).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CorruptTileError>().unwrap().corruption,
+            Corruption::MissingHeader
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_inconsistent_tiles() {
+        // Give the first node more edges than the tile has.
+        let mut valhalla_bytes = sample_tile_bytes();
+        let data1 = 272 + 8;
+        let bits = u64::from_le_bytes(valhalla_bytes[data1..data1 + 8].try_into().unwrap());
+        let bits = ValhallaNodeInfoData1::from_bits(bits)
+            .with_edge_count(5)
+            .into_bits();
+        valhalla_bytes[data1..data1 + 8].copy_from_slice(&bits.to_le_bytes());
+        assert!(InfernoTile::from_valhalla(&valhalla_bytes).is_err());
+
+        // Archives are checked too, since they may not have come from `from_valhalla`.
+        let mut tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        tile.nodes.as_mut_slice()[0].data1.set_edge_count(5);
+        let err = tile_from_archive_bytes(&tile_to_archive_bytes(&tile).unwrap()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Corruption>(),
+            Some(Corruption::Inconsistent(_))
+        ));
+        // Building a graph from the unvalidated tile skips the node's edges instead of panicking.
+        let tiles = [tile];
+        InfernoTileGraph::with_edge_search(&tiles, EdgeSearch::RTree);
+        let tile = tiles[0].tile_ref();
+        assert!(tile.nodes.as_slice()[0].edges(&tile).is_empty());
+
+        // Point the first edge at an edge info past the end of the tile's edge infos.
+        let mut tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        tile.directed_edges.as_mut_slice()[0]
+            .restrictions2
+            .set_edge_info_offset(5);
+        let err = tile_from_archive_bytes(&tile_to_archive_bytes(&tile).unwrap()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Corruption>(),
+            Some(Corruption::Inconsistent(_))
+        ));
+
+        // Queries on a tile that wasn't validated report the missing edge info instead of
+        // panicking.
+        let tiles = [tile];
+        let graph = InfernoTileGraph::with_edge_search(&tiles, EdgeSearch::Bins);
+        let edge = GraphEntityId::from_tile_index(&tiles[0].tile_id(), 0);
+        let edge_info = graph.directed_edge(&edge).unwrap().get_entity();
+        assert!(graph.edge_info(&edge_info).is_err());
+        assert!(graph
+            .directed_edge(&GraphEntityId::from_tile_index(&tiles[0].tile_id(), 9))
+            .is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Typed versions of the attribute codes in Valhalla's graphconstants.h.
+
+use crate::valhalla::access_restrictions::ValhallaAccessRestriction;
+
+/// Defines a fieldless enum for a Valhalla attribute code, with an `Unknown` variant for codes
+/// this version of inferno doesn't know about.
+macro_rules! valhalla_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value this version of inferno doesn't know about.
+            Unknown(u8),
+        }
+
+        impl $name {
+            pub fn from_valhalla(value: u8) -> $name {
+                match value {
+                    $($value => $name::$variant,)*
+                    other => $name::Unknown(other),
+                }
+            }
+
+            pub fn to_valhalla(&self) -> u8 {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(other) => *other,
+                }
+            }
+        }
+    };
+}
+
+valhalla_enum! {
+    /// Specific use of an edge.
+    pub enum Use {
+        Road = 0,
+        Ramp = 1,
+        TurnChannel = 2,
+        Track = 3,
+        Driveway = 4,
+        Alley = 5,
+        ParkingAisle = 6,
+        EmergencyAccess = 7,
+        DriveThru = 8,
+        Culdesac = 9,
+        LivingStreet = 10,
+        ServiceRoad = 11,
+        Cycleway = 20,
+        MountainBike = 21,
+        Sidewalk = 24,
+        Footway = 25,
+        Steps = 26,
+        Path = 27,
+        Pedestrian = 28,
+        Bridleway = 29,
+        RestArea = 30,
+        ServiceArea = 31,
+        PedestrianCrossing = 32,
+        Elevator = 33,
+        Escalator = 34,
+        Platform = 35,
+        Other = 40,
+        Ferry = 41,
+        RailFerry = 42,
+        Construction = 43,
+        Rail = 50,
+        Bus = 51,
+        EgressConnection = 52,
+        PlatformConnection = 53,
+        TransitConnection = 54,
+    }
+}
+
+valhalla_enum! {
+    /// Classification or importance of a road.
+    pub enum RoadClass {
+        Motorway = 0,
+        Trunk = 1,
+        Primary = 2,
+        Secondary = 3,
+        Tertiary = 4,
+        Unclassified = 5,
+        Residential = 6,
+        ServiceOther = 7,
+    }
+}
+
+valhalla_enum! {
+    /// Smoothness of an edge's surface, from smoothest to roughest.
+    pub enum Surface {
+        PavedSmooth = 0,
+        Paved = 1,
+        PavedRough = 2,
+        Compacted = 3,
+        Dirt = 4,
+        Gravel = 5,
+        Path = 6,
+        Impassable = 7,
+    }
+}
+
+valhalla_enum! {
+    /// Type of a node.
+    pub enum NodeType {
+        StreetIntersection = 0,
+        Gate = 1,
+        Bollard = 2,
+        TollBooth = 3,
+        TransitEgress = 4,
+        TransitStation = 5,
+        MultiUseTransitPlatform = 6,
+        BikeShare = 7,
+        Parking = 8,
+        MotorwayJunction = 9,
+        BorderControl = 10,
+        TollGantry = 11,
+        SumpBuster = 12,
+        BuildingEntrance = 13,
+        Elevator = 14,
+    }
+}
+
+valhalla_enum! {
+    /// Type of an intersection.
+    pub enum IntersectionType {
+        Regular = 0,
+        /// Only two edges meet, so no maneuver is needed.
+        False = 1,
+        DeadEnd = 2,
+        Fork = 3,
+    }
+}
+
+valhalla_enum! {
+    /// Kind of an access restriction. The meaning of the restriction's value depends on its kind.
+    pub enum AccessType {
+        Hazmat = 0,
+        MaxHeight = 1,
+        MaxWidth = 2,
+        MaxLength = 3,
+        MaxWeight = 4,
+        MaxAxleLoad = 5,
+        TimedAllowed = 6,
+        TimedDenied = 7,
+        DestinationAllowed = 8,
+        MaxAxles = 9,
+    }
+}
+
+valhalla_enum! {
+    /// Kind of bicycle lane on an edge.
+    pub enum CycleLane {
+        None = 0,
+        Shared = 1,
+        Dedicated = 2,
+        Separated = 3,
+    }
+}
+
+valhalla_enum! {
+    /// Difficulty of a hiking edge on the SAC scale.
+    pub enum SacScale {
+        None = 0,
+        Hiking = 1,
+        MountainHiking = 2,
+        DemandingMountainHiking = 3,
+        AlpineHiking = 4,
+        DemandingAlpineHiking = 5,
+        DifficultAlpineHiking = 6,
+    }
+}
+
+valhalla_enum! {
+    /// Where an edge's default speed came from.
+    pub enum SpeedType {
+        /// The speed was tagged in OSM.
+        Tagged = 0,
+        /// The speed was estimated from the road class.
+        Classified = 1,
+    }
+}
+
+/// Travel modes allowed on an edge or through a node, as a bit mask of Valhalla's `k*Access`
+/// constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Access(u16);
+
+impl Access {
+    pub const AUTO: Access = Access(1 << 0);
+    pub const PEDESTRIAN: Access = Access(1 << 1);
+    pub const BICYCLE: Access = Access(1 << 2);
+    pub const TRUCK: Access = Access(1 << 3);
+    pub const EMERGENCY: Access = Access(1 << 4);
+    pub const TAXI: Access = Access(1 << 5);
+    pub const BUS: Access = Access(1 << 6);
+    pub const HOV: Access = Access(1 << 7);
+    pub const WHEELCHAIR: Access = Access(1 << 8);
+    pub const MOPED: Access = Access(1 << 9);
+    pub const MOTORCYCLE: Access = Access(1 << 10);
+    pub const ALL: Access = Access(0xfff);
+
+    pub fn from_bits(bits: u16) -> Access {
+        Access(bits)
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Access) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(&self, other: Access) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for Access {
+    type Output = Access;
+
+    fn bitor(self, rhs: Access) -> Access {
+        Access(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitAnd for Access {
+    type Output = Access;
+
+    fn bitand(self, rhs: Access) -> Access {
+        Access(self.0 & rhs.0)
+    }
+}
+
+/// A general access restriction on a directed edge, such as a maximum height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessRestriction {
+    access_type: AccessType,
+    modes: Access,
+    value: u64,
+}
+
+impl AccessRestriction {
+    pub(crate) fn new(restriction: &ValhallaAccessRestriction) -> AccessRestriction {
+        AccessRestriction {
+            access_type: restriction.access_type(),
+            modes: restriction.modes(),
+            value: restriction.value,
+        }
+    }
+
+    pub fn access_type(&self) -> AccessType {
+        self.access_type
+    }
+
+    /// Modes the restriction applies to.
+    pub fn modes(&self) -> Access {
+        self.modes
+    }
+
+    /// Raw value of the restriction. Its meaning depends on the access type, e.g. a limit for
+    /// `MaxHeight` or an encoded time domain for `TimedAllowed`.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+/// Returns the access restrictions in `restrictions` (sorted by edge index, as Valhalla stores
+/// them) on the directed edge at `edge_index`.
+pub(crate) fn access_restrictions_at(
+    restrictions: &[ValhallaAccessRestriction],
+    edge_index: usize,
+) -> Vec<AccessRestriction> {
+    let start =
+        restrictions.partition_point(|restriction| restriction.bitfield.edge_index() < edge_index);
+    restrictions[start..]
+        .iter()
+        .take_while(|restriction| restriction.bitfield.edge_index() == edge_index)
+        .map(AccessRestriction::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geomath::LatLng,
+        inferno::{
+            builder::{EdgeAttributes, GraphBuilder},
+            test_tiles::edit_tile,
+        },
+    };
+
+    use super::*;
+
+    /// Checks that every code survives `from_valhalla` and `to_valhalla`, and that `unused` is
+    /// read as `Unknown`.
+    macro_rules! assert_round_trips {
+        ($($name:ident: $unused:literal),* $(,)?) => {$(
+            for value in 0..=u8::MAX {
+                assert_eq!($name::from_valhalla(value).to_valhalla(), value);
+            }
+            assert_eq!($name::from_valhalla($unused), $name::Unknown($unused));
+        )*};
+    }
+
+    #[test]
+    fn round_trips_valhalla_codes() {
+        assert_round_trips!(
+            Use: 12,
+            RoadClass: 8,
+            Surface: 8,
+            NodeType: 15,
+            IntersectionType: 4,
+            AccessType: 10,
+            CycleLane: 4,
+            SacScale: 7,
+            SpeedType: 2,
+        );
+
+        assert_eq!(Use::from_valhalla(20), Use::Cycleway);
+        assert_eq!(Use::from_valhalla(54), Use::TransitConnection);
+        assert_eq!(RoadClass::from_valhalla(7), RoadClass::ServiceOther);
+        assert_eq!(Surface::from_valhalla(7), Surface::Impassable);
+        assert_eq!(NodeType::from_valhalla(14), NodeType::Elevator);
+        assert_eq!(IntersectionType::from_valhalla(3), IntersectionType::Fork);
+        assert_eq!(AccessType::from_valhalla(9), AccessType::MaxAxles);
+        assert_eq!(CycleLane::from_valhalla(3), CycleLane::Separated);
+        assert_eq!(SacScale::from_valhalla(6), SacScale::DifficultAlpineHiking);
+        assert_eq!(SpeedType::from_valhalla(1), SpeedType::Classified);
+    }
+
+    #[test]
+    fn combines_access_masks() {
+        let walk_or_cycle = Access::PEDESTRIAN | Access::BICYCLE;
+        assert_eq!(walk_or_cycle.bits(), 0b110);
+        assert_eq!(Access::from_bits(0b110), walk_or_cycle);
+
+        assert!(walk_or_cycle.contains(Access::BICYCLE));
+        assert!(walk_or_cycle.contains(walk_or_cycle));
+        assert!(!walk_or_cycle.contains(Access::BICYCLE | Access::AUTO));
+        assert!(walk_or_cycle.intersects(Access::BICYCLE | Access::AUTO));
+        assert!(!walk_or_cycle.intersects(Access::AUTO | Access::TRUCK));
+
+        assert_eq!(walk_or_cycle & Access::BICYCLE, Access::BICYCLE);
+        assert!((walk_or_cycle & Access::AUTO).is_empty());
+        assert!(Access::default().is_empty());
+        assert!(Access::ALL.contains(Access::MOTORCYCLE | Access::WHEELCHAIR));
+    }
+
+    #[test]
+    fn reads_record_attributes() {
+        let mut builder = GraphBuilder::new();
+        let start = builder.add_node(LatLng::new(47.6, -122.4));
+        let end = builder.add_node(LatLng::new(47.61, -122.4));
+        builder.add_edge(
+            start,
+            end,
+            EdgeAttributes {
+                road_class: RoadClass::Tertiary,
+                edge_use: Use::Cycleway,
+                forward_access: Access::PEDESTRIAN | Access::BICYCLE,
+                reverse_access: Access::BICYCLE,
+                ..Default::default()
+            },
+        );
+        let tile = builder.build().unwrap().into_tiles().remove(0);
+        let tile = edit_tile(tile, |tile| {
+            let edge = &mut tile.directed_edges.as_mut_slice()[0];
+            edge.data1.set_surface(Surface::Gravel.to_valhalla());
+            edge.data2
+                .set_cycle_lane(CycleLane::Dedicated.to_valhalla());
+            edge.data2
+                .set_sac_scale(SacScale::AlpineHiking.to_valhalla());
+            edge.data4
+                .set_speed_type(SpeedType::Classified.to_valhalla());
+            edge.restrictions2
+                .set_access_restriction(Access::BICYCLE.bits() as u64);
+
+            let node = &mut tile.nodes.as_mut_slice()[0];
+            node.data1
+                .set_node_type(NodeType::Gate.to_valhalla() as u32);
+            node.data1
+                .set_intersection(IntersectionType::DeadEnd.to_valhalla() as u32);
+            node.position_info.set_access(Access::PEDESTRIAN.bits());
+        });
+
+        let forward = &tile.directed_edges.as_slice()[0];
+        assert_eq!(forward.edge_use(), Use::Cycleway);
+        assert_eq!(forward.road_class(), RoadClass::Tertiary);
+        assert_eq!(forward.surface(), Surface::Gravel);
+        assert_eq!(
+            forward.forward_access(),
+            Access::PEDESTRIAN | Access::BICYCLE
+        );
+        assert_eq!(forward.reverse_access(), Access::BICYCLE);
+        assert_eq!(forward.access_restrictions(), Access::BICYCLE);
+        assert_eq!(forward.cycle_lane(), CycleLane::Dedicated);
+        assert_eq!(forward.sac_scale(), SacScale::AlpineHiking);
+        assert_eq!(forward.speed_type(), SpeedType::Classified);
+
+        let reverse = &tile.directed_edges.as_slice()[1];
+        assert_eq!(reverse.forward_access(), Access::BICYCLE);
+        assert_eq!(
+            reverse.reverse_access(),
+            Access::PEDESTRIAN | Access::BICYCLE
+        );
+        assert_eq!(reverse.surface(), Surface::PavedSmooth);
+        assert_eq!(reverse.cycle_lane(), CycleLane::None);
+        assert_eq!(reverse.speed_type(), SpeedType::Tagged);
+
+        let [start, end] = tile.nodes.as_slice() else {
+            panic!("Tile has two nodes");
+        };
+        assert_eq!(start.node_type(), NodeType::Gate);
+        assert_eq!(start.intersection_type(), IntersectionType::DeadEnd);
+        assert_eq!(start.access(), Access::PEDESTRIAN);
+        assert_eq!(end.node_type(), NodeType::StreetIntersection);
+        assert_eq!(end.intersection_type(), IntersectionType::Regular);
+        assert_eq!(end.access(), Access::ALL);
+    }
+}
@@ -0,0 +1,189 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::{
+    geomath::LatLng,
+    inferno::zero_copy::slice_in_place,
+    tiles::BoundingBox,
+    valhalla::{directed_edge::ValhallaDirectedEdge, graph_id::GraphEntityId},
+};
+
+/// Number of bins along each side of a tile.
+pub(crate) const BINS_DIM: usize = 5;
+/// Number of bins in a tile.
+pub(crate) const BIN_COUNT: usize = BINS_DIM * BINS_DIM;
+
+const GRAPH_ID_SIZE: usize = size_of::<u64>();
+const GRAPH_ID_MASK: u64 = 0x3fff_ffff_ffff;
+
+/// The tile's spatial index, as used by Valhalla's locator. The tile is divided into a 5x5 grid
+/// of bins, numbered row by row from the south west corner, and each bin lists the directed edges
+/// whose shape intersects it. Edges in a bin may belong to neighboring tiles on the same level.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct EdgeBins {
+    /// End offset of each bin in `edges`, so bin `i` spans `offsets[i - 1]..offsets[i]`.
+    offsets: [u32; BIN_COUNT],
+    edges: Vec<u64>,
+}
+
+impl EdgeBins {
+    /// Parses the edge bins starting at `ptr`, which Valhalla writes directly after the admins.
+    pub(crate) fn from_valhalla(
+        bytes: &[u8],
+        ptr: &mut usize,
+        offsets: [u32; BIN_COUNT],
+    ) -> Result<EdgeBins, anyhow::Error> {
+        if offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(anyhow::anyhow!("Invalid tile: bin offsets aren't sorted"));
+        }
+        let count = offsets[BIN_COUNT - 1] as usize;
+        let bin_bytes = bytes
+            .get(*ptr..*ptr + count * GRAPH_ID_SIZE)
+            .ok_or_else(|| anyhow::anyhow!("Invalid tile: edge bins out of bounds"))?;
+        let edges = bin_bytes
+            .chunks_exact(GRAPH_ID_SIZE)
+            .map(|chunk| {
+                u64::from_le_bytes(chunk.try_into().expect("Chunk is the size of a graph ID"))
+                    & GRAPH_ID_MASK
+            })
+            .collect();
+        *ptr += count * GRAPH_ID_SIZE;
+        Ok(EdgeBins { offsets, edges })
+    }
+
+    /// Builds the bins from the graph IDs of the edges in each of the `BIN_COUNT` bins.
+    pub(crate) fn new(bins: &[Vec<u64>]) -> EdgeBins {
+        assert_eq!(bins.len(), BIN_COUNT, "A tile has {} bins", BIN_COUNT);
+        let mut offsets = [0; BIN_COUNT];
+        let mut edges = Vec::new();
+        for (bin, bin_edges) in bins.iter().enumerate() {
+            edges.extend_from_slice(bin_edges);
+            offsets[bin] = edges.len() as u32;
+        }
+        EdgeBins { offsets, edges }
+    }
+
+    /// End offset of each bin, as stored in the tile header.
+    pub(crate) fn offsets(&self) -> [u32; BIN_COUNT] {
+        self.offsets
+    }
+
+    /// Appends the bins' edges in Valhalla's format to `bytes`.
+    pub(crate) fn write_valhalla(&self, bytes: &mut Vec<u8>) {
+        for edge in &self.edges {
+            bytes.extend_from_slice(&edge.to_le_bytes());
+        }
+    }
+
+    pub fn borrowed(&self) -> EdgeBinsRef<'_> {
+        EdgeBinsRef {
+            offsets: &self.offsets,
+            edges: &self.edges,
+        }
+    }
+}
+
+impl ArchivedEdgeBins {
+    pub fn borrowed(&self) -> EdgeBinsRef<'_> {
+        EdgeBinsRef {
+            offsets: slice_in_place(&self.offsets),
+            edges: slice_in_place(&self.edges),
+        }
+    }
+}
+
+/// Edge bins borrowed from `EdgeBins` or read in place from archived ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeBinsRef<'a> {
+    offsets: &'a [u32],
+    edges: &'a [u64],
+}
+
+impl EdgeBinsRef<'_> {
+    /// Returns the directed edges in bin `index`.
+    pub fn bin(&self, index: usize) -> Vec<GraphEntityId<ValhallaDirectedEdge>> {
+        if index >= BIN_COUNT {
+            return Vec::new();
+        }
+        let start = if index == 0 {
+            0
+        } else {
+            self.offsets[index - 1] as usize
+        };
+        let end = self.offsets[index] as usize;
+        self.edges[start..end]
+            .iter()
+            .map(|id| GraphEntityId::new(*id))
+            .collect()
+    }
+
+    /// Checks that the bin offsets are sorted and within the edges, as `bin` relies on.
+    pub(crate) fn check_consistency(&self) -> Result<(), String> {
+        if self.offsets.len() != BIN_COUNT || self.offsets.windows(2).any(|pair| pair[0] > pair[1])
+        {
+            return Err("bin offsets aren't sorted".to_string());
+        }
+        if self.offsets[BIN_COUNT - 1] as usize > self.edges.len() {
+            return Err(format!(
+                "bins end at {}, but there are {} bin entries",
+                self.offsets[BIN_COUNT - 1],
+                self.edges.len()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+/// Returns the indices of the bins of a tile with its south west corner at `base` that come
+/// within `radius_meters` of `point`. The `Vec` is empty if the tile doesn't.
+pub(crate) fn bins_near(
+    base: &LatLng,
+    tile_size: f64,
+    point: &LatLng,
+    radius_meters: f64,
+) -> Vec<usize> {
+    let bbox = BoundingBox::around(point, radius_meters);
+    let bin_size = tile_size / BINS_DIM as f64;
+    let cell_range = |min: f64, max: f64, base: f64| {
+        let min = ((min - base) / bin_size).floor();
+        let max = ((max - base) / bin_size).floor();
+        if max < 0.0 || min >= BINS_DIM as f64 {
+            return None;
+        }
+        Some(min.max(0.0) as usize..=(max as usize).min(BINS_DIM - 1))
+    };
+    let (Some(rows), Some(cols)) = (
+        cell_range(bbox.min.lat(), bbox.max.lat(), base.lat()),
+        cell_range(bbox.min.lng(), bbox.max.lng(), base.lng()),
+    ) else {
+        return Vec::new();
+    };
+    rows.flat_map(|row| cols.clone().map(move |col| row * BINS_DIM + col))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bins_near_point() {
+        let base = LatLng::new(47.0, -123.0);
+        // Center of bin (row 2, col 3) of a 1 degree tile, with a radius inside the bin.
+        let point = LatLng::new(47.5, -122.3);
+        assert_eq!(bins_near(&base, 1.0, &point, 100.0), vec![13]);
+        // A radius of most of a bin reaches the neighboring bins.
+        let bins = bins_near(&base, 1.0, &point, 15_000.0);
+        assert_eq!(bins, vec![7, 8, 9, 12, 13, 14, 17, 18, 19]);
+        // Points well outside the tile don't touch any bins.
+        let far = LatLng::new(49.0, -122.3);
+        assert!(bins_near(&base, 1.0, &far, 100.0).is_empty());
+    }
+}
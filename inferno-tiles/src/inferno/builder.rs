@@ -0,0 +1,763 @@
+//! Builds small synthetic graphs, for tests and fixtures that need a routable graph without real
+//! map data. Nodes are placed at coordinates and joined by edges, and `GraphBuilder::build` splits
+//! them into tiles, filling in what Valhalla's graph builder would: opposing edges, `leaves_tile`,
+//! node transitions between levels, edge infos, names and the spatial bins.
+//!
+//! Each tile is written with `InfernoTile::to_valhalla` and parsed back with
+//! `InfernoTile::from_valhalla`, so it goes through the same checks as tiles converted from real
+//! data.
+
+use std::collections::{BTreeMap, HashMap};
+
+use zerocopy::FromZeros;
+
+use crate::{
+    geomath::{distance_meters, LatLng},
+    tiles::{BoundingBox, TileLevel},
+    valhalla::{
+        directed_edge::ValhallaDirectedEdge,
+        edge_info::{ValhallaEdgeInfo, ValhallaEdgeInfoBitfield1, ValhallaEdgeInfoBitfield2},
+        graph_id::{GraphEntityId, TileId},
+        name_info::ValhallaNameInfo,
+        node_info::ValhallaNodeInfo,
+        node_transition::ValhallaNodeTransition,
+        tile_header::ValhallaTileHeader,
+    },
+};
+
+use super::{
+    attributes::{Access, RoadClass, Use},
+    bins::{EdgeBins, BINS_DIM, BIN_COUNT},
+    checked_vec::CheckedVec,
+    edge_info::{encode_shape, InfernoEdgeInfo},
+    elevation::{encode_edge_elevation, encode_mean_elevation, NO_MEAN_ELEVATION},
+    predicted_speeds::PredictedSpeeds,
+    text_list::TextList,
+    version::TileVersion,
+    InfernoTile,
+};
+
+/// A node added to a `GraphBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle(usize);
+
+/// A pair of opposing edges added to a `GraphBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EdgeHandle(usize);
+
+/// Attributes of a pair of edges, as seen travelling from the edge's start node to its end node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeAttributes {
+    pub way_id: u64,
+    pub names: Vec<String>,
+    pub speed_kph: u8,
+    pub road_class: RoadClass,
+    pub edge_use: Use,
+    /// Modes allowed from the start node to the end node.
+    pub forward_access: Access,
+    /// Modes allowed from the end node to the start node.
+    pub reverse_access: Access,
+    /// Shape points between the start and end nodes, which are added to the shape when built.
+    pub shape: Vec<LatLng>,
+    /// Elevation in meters sampled evenly along the edge from its start node to its end node, or
+    /// empty if the edge has no elevation data.
+    pub elevation: Vec<f64>,
+}
+
+impl Default for EdgeAttributes {
+    fn default() -> Self {
+        EdgeAttributes {
+            way_id: 0,
+            names: Vec::new(),
+            speed_kph: 50,
+            road_class: RoadClass::Residential,
+            edge_use: Use::Road,
+            forward_access: Access::ALL,
+            reverse_access: Access::ALL,
+            shape: Vec::new(),
+            elevation: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BuilderNode {
+    level: TileLevel,
+    position: LatLng,
+    /// The directed edges leaving the node, as their pair and whether they're the forward edge.
+    edges: Vec<(usize, bool)>,
+    transitions: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct BuilderEdge {
+    from: usize,
+    to: usize,
+    attributes: EdgeAttributes,
+}
+
+impl BuilderEdge {
+    /// Full shape of the edge from its start node to its end node.
+    fn shape(&self, nodes: &[BuilderNode]) -> Vec<LatLng> {
+        let mut shape = Vec::with_capacity(self.attributes.shape.len() + 2);
+        shape.push(nodes[self.from].position);
+        shape.extend_from_slice(&self.attributes.shape);
+        shape.push(nodes[self.to].position);
+        shape
+    }
+}
+
+/// Builds a graph of nodes and edges, which is split into tiles by `build`.
+#[derive(Debug, Clone, Default)]
+pub struct GraphBuilder {
+    nodes: Vec<BuilderNode>,
+    edges: Vec<BuilderEdge>,
+    dataset_id: u64,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the dataset ID written to every tile.
+    pub fn dataset_id(&mut self, dataset_id: u64) -> &mut Self {
+        self.dataset_id = dataset_id;
+        self
+    }
+
+    /// Adds a node on the local level.
+    pub fn add_node(&mut self, position: LatLng) -> NodeHandle {
+        self.add_node_on_level(TileLevel::LOCAL, position)
+    }
+
+    pub fn add_node_on_level(&mut self, level: TileLevel, position: LatLng) -> NodeHandle {
+        self.nodes.push(BuilderNode {
+            level,
+            position,
+            edges: Vec::new(),
+            transitions: Vec::new(),
+        });
+        NodeHandle(self.nodes.len() - 1)
+    }
+
+    /// Joins two nodes on the same level with a forward edge from `from` to `to` and its opposing
+    /// edge.
+    pub fn add_edge(
+        &mut self,
+        from: NodeHandle,
+        to: NodeHandle,
+        attributes: EdgeAttributes,
+    ) -> EdgeHandle {
+        let edge = self.edges.len();
+        self.edges.push(BuilderEdge {
+            from: from.0,
+            to: to.0,
+            attributes,
+        });
+        self.nodes[from.0].edges.push((edge, true));
+        self.nodes[to.0].edges.push((edge, false));
+        EdgeHandle(edge)
+    }
+
+    /// Marks two nodes on different levels as the same intersection, adding a transition from
+    /// each to the other.
+    pub fn add_transition(&mut self, a: NodeHandle, b: NodeHandle) -> &mut Self {
+        self.nodes[a.0].transitions.push(b.0);
+        self.nodes[b.0].transitions.push(a.0);
+        self
+    }
+
+    /// Splits the graph into tiles, which are sorted by tile ID.
+    pub fn build(&self) -> Result<SyntheticGraph, anyhow::Error> {
+        let mut tiles: BTreeMap<TileId, Vec<usize>> = BTreeMap::new();
+        let mut node_tiles = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.iter().enumerate() {
+            let tile_id = node.level.tile_id(&node.position).ok_or_else(|| {
+                anyhow::anyhow!("Node {} has an invalid position {:?}", index, node.position)
+            })?;
+            node_tiles.push(tile_id);
+            tiles.entry(tile_id).or_default().push(index);
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            if self.nodes[edge.from].level != self.nodes[edge.to].level {
+                return Err(anyhow::anyhow!(
+                    "Edge {} joins nodes on different levels",
+                    index
+                ));
+            }
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(other) = node
+                .transitions
+                .iter()
+                .find(|other| self.nodes[**other].level == node.level)
+            {
+                return Err(anyhow::anyhow!(
+                    "Transition between nodes {} and {} stays on the same level",
+                    index,
+                    other
+                ));
+            }
+        }
+
+        // Nodes and their edges are numbered in the order they were added within each tile.
+        let mut node_ids = vec![GraphEntityId::new(0); self.nodes.len()];
+        let mut edge_ids = vec![[GraphEntityId::new(0); 2]; self.edges.len()];
+        for (tile_id, nodes) in &tiles {
+            let mut edge_index = 0;
+            for (node_index, node) in nodes.iter().enumerate() {
+                node_ids[*node] = GraphEntityId::from_tile_index(tile_id, node_index);
+                for (edge, is_forward) in &self.nodes[*node].edges {
+                    edge_ids[*edge][usize::from(!is_forward)] =
+                        GraphEntityId::from_tile_index(tile_id, edge_index);
+                    edge_index += 1;
+                }
+            }
+        }
+
+        let graph = BuiltGraph {
+            builder: self,
+            node_tiles: &node_tiles,
+            node_ids: &node_ids,
+            edge_ids: &edge_ids,
+        };
+        let tiles = tiles
+            .iter()
+            .map(|(tile_id, nodes)| graph.tile(tile_id, nodes))
+            .collect::<Result<_, _>>()?;
+        Ok(SyntheticGraph {
+            tiles,
+            node_ids,
+            edge_ids,
+        })
+    }
+}
+
+/// The IDs assigned to a builder's nodes and edges, while its tiles are built.
+struct BuiltGraph<'a> {
+    builder: &'a GraphBuilder,
+    node_tiles: &'a [TileId],
+    node_ids: &'a [GraphEntityId<ValhallaNodeInfo>],
+    /// The forward and reverse directed edge of each pair.
+    edge_ids: &'a [[GraphEntityId<ValhallaDirectedEdge>; 2]],
+}
+
+impl BuiltGraph<'_> {
+    /// Builds the tile holding `nodes`, then writes it with `InfernoTile::to_valhalla` and parses
+    /// it back, which fills in the header's counts and offsets.
+    fn tile(&self, tile_id: &TileId, nodes: &[usize]) -> Result<InfernoTile, anyhow::Error> {
+        let level = TileLevel::of(tile_id).expect("Tile is on a valid level");
+        let base = level.tile_base(tile_id).expect("Tile is on its level");
+        let version = TileVersion::newest_supported();
+
+        let mut header = ValhallaTileHeader::new_zeroed();
+        header.metadata.set_graphid(tile_id.id);
+        header.base_ll = [base.lng() as f32, base.lat() as f32];
+        header.version = version.to_valhalla();
+        header.dataset_id = self.builder.dataset_id;
+        header.metadata.set_has_elevation(nodes.iter().any(|node| {
+            self.builder.nodes[*node]
+                .edges
+                .iter()
+                .any(|(edge, _)| !self.builder.edges[*edge].attributes.elevation.is_empty())
+        }));
+
+        let mut node_infos = CheckedVec::new(*tile_id);
+        let mut transitions = CheckedVec::new(*tile_id);
+        let mut directed_edges = CheckedVec::new(*tile_id);
+        let mut edge_infos = EdgeInfos::new(*tile_id);
+        for node in nodes {
+            let builder_node = &self.builder.nodes[*node];
+            let mut node_info = ValhallaNodeInfo::new_zeroed();
+            let (lat_offset, lat_offset7) =
+                position_offset(builder_node.position.lat(), base.lat());
+            let (lon_offset, lon_offset7) =
+                position_offset(builder_node.position.lng(), base.lng());
+            node_info
+                .position_info
+                .set_lat_offset_checked(lat_offset)
+                .and_then(|_| node_info.position_info.set_lon_offset_checked(lon_offset))
+                .map_err(|_| anyhow::anyhow!("Node {} is outside its tile", node))?;
+            node_info.position_info.set_lat_offset7(lat_offset7);
+            node_info.position_info.set_lon_offset7(lon_offset7);
+            node_info.position_info.set_access(Access::ALL.bits());
+            node_info.data1.set_edge_index(directed_edges.len());
+            node_info
+                .data1
+                .set_edge_count_checked(builder_node.edges.len())
+                .map_err(|_| anyhow::anyhow!("Node {} has too many edges", node))?;
+            node_info
+                .data2
+                .set_transition_index(transitions.len() as u32);
+            node_info
+                .data2
+                .set_transition_count_checked(builder_node.transitions.len() as u32)
+                .map_err(|_| anyhow::anyhow!("Node {} has too many transitions", node))?;
+            node_infos.push(node_info);
+
+            for other in &builder_node.transitions {
+                transitions.push(
+                    ValhallaNodeTransition::new()
+                        .with_end_node(self.node_ids[*other].graph_entity_id)
+                        .with_up(self.builder.nodes[*other].level.level() < level.level()),
+                );
+            }
+
+            for (local_index, (edge, is_forward)) in builder_node.edges.iter().enumerate() {
+                let edge_info = edge_infos.add(*edge, &self.builder.edges[*edge], self)?;
+                directed_edges.push(self.directed_edge(
+                    *edge,
+                    *is_forward,
+                    local_index,
+                    edge_info,
+                )?);
+            }
+        }
+
+        let tile = InfernoTile {
+            tile_id: *tile_id,
+            header,
+            version,
+            nodes: node_infos,
+            node_transitions: transitions,
+            directed_edges,
+            directed_edge_exts: CheckedVec::new(*tile_id),
+            access_restrictions: CheckedVec::new(*tile_id),
+            edge_infos: edge_infos.edge_infos,
+            transit_departures: CheckedVec::new(*tile_id),
+            transit_stops: CheckedVec::new(*tile_id),
+            transit_routes: CheckedVec::new(*tile_id),
+            transit_schedules: CheckedVec::new(*tile_id),
+            transit_transfers: CheckedVec::new(*tile_id),
+            signs: CheckedVec::new(*tile_id),
+            turn_lanes: CheckedVec::new(*tile_id),
+            admins: CheckedVec::new(*tile_id),
+            edge_bins: EdgeBins::new(&self.bins(tile_id, level)),
+            complex_restrictions_forward: CheckedVec::new(*tile_id),
+            complex_restrictions_reverse: CheckedVec::new(*tile_id),
+            text_list: TextList::new(edge_infos.text_list),
+            lane_connectivity: CheckedVec::new(*tile_id),
+            predicted_speeds: PredictedSpeeds::default(),
+        };
+        InfernoTile::from_valhalla(&tile.to_valhalla()?)
+    }
+
+    fn directed_edge(
+        &self,
+        edge: usize,
+        is_forward: bool,
+        local_index: usize,
+        edge_info_index: usize,
+    ) -> Result<ValhallaDirectedEdge, anyhow::Error> {
+        let builder_edge = &self.builder.edges[edge];
+        let attributes = &builder_edge.attributes;
+        let (start, end) = if is_forward {
+            (builder_edge.from, builder_edge.to)
+        } else {
+            (builder_edge.to, builder_edge.from)
+        };
+        let (access, reverse_access) = if is_forward {
+            (attributes.forward_access, attributes.reverse_access)
+        } else {
+            (attributes.reverse_access, attributes.forward_access)
+        };
+        // The opposing edge's position among the edges leaving the end node.
+        let opp_index = self.builder.nodes[end]
+            .edges
+            .iter()
+            .position(|other| *other == (edge, !is_forward))
+            .expect("Opposing edge leaves the end node");
+        let length = builder_edge
+            .shape(&self.builder.nodes)
+            .windows(2)
+            .map(|segment| distance_meters(&segment[0], &segment[1]))
+            .sum::<f64>()
+            .round() as u32;
+
+        let mut directed_edge = ValhallaDirectedEdge::new_zeroed();
+        directed_edge
+            .restrictions1
+            .set_end_node(self.node_ids[end].graph_entity_id);
+        directed_edge.restrictions1.set_opp_index(opp_index);
+        directed_edge.restrictions1.set_is_forward(is_forward);
+        directed_edge
+            .restrictions1
+            .set_leaves_tile(self.node_tiles[start] != self.node_tiles[end]);
+        directed_edge
+            .restrictions2
+            .set_edge_info_offset_checked(edge_info_index)
+            .map_err(|_| {
+                anyhow::anyhow!("Too many edge infos in tile {}", self.node_tiles[start])
+            })?;
+        directed_edge.data1.set_speed(attributes.speed_kph);
+        directed_edge
+            .data1
+            .set_free_flow_speed(attributes.speed_kph);
+        directed_edge
+            .data1
+            .set_constrained_flow_speed(attributes.speed_kph);
+        directed_edge
+            .data1
+            .set_use_type(attributes.edge_use.to_valhalla());
+        directed_edge
+            .data1
+            .set_classification(attributes.road_class.to_valhalla());
+        directed_edge.data2.set_forward_access_mask(access.bits());
+        directed_edge
+            .data2
+            .set_reverse_access_mask(reverse_access.bits());
+        directed_edge
+            .data3
+            .set_length_meters_checked(length)
+            .map_err(|_| anyhow::anyhow!("Edge {} is too long", edge))?;
+        directed_edge.data4.set_local_edge_index(local_index as u8);
+        directed_edge
+            .data4
+            .set_opposing_local_edge_index(opp_index as u8);
+        directed_edge
+            .data4
+            .set_is_named(!attributes.names.is_empty());
+        Ok(directed_edge)
+    }
+
+    /// Lists the forward edge of each pair on the tile's level in the bins its shape intersects.
+    fn bins(&self, tile_id: &TileId, level: TileLevel) -> Vec<Vec<u64>> {
+        let bounds = level.tile_bounds(tile_id).expect("Tile is on its level");
+        let bin_size = level.tile_size() / BINS_DIM as f64;
+        let bin_of =
+            |offset: f64| ((offset / bin_size).floor() as i64).clamp(0, BINS_DIM as i64 - 1);
+        let mut bins = vec![Vec::new(); BIN_COUNT];
+        for (index, edge) in self.builder.edges.iter().enumerate() {
+            if self.builder.nodes[edge.from].level != level {
+                continue;
+            }
+            let edge_id = self.edge_ids[index][0].graph_entity_id;
+            for segment in edge.shape(&self.builder.nodes).windows(2) {
+                let segment = BoundingBox::new(
+                    LatLng::new(
+                        segment[0].lat().min(segment[1].lat()),
+                        segment[0].lng().min(segment[1].lng()),
+                    ),
+                    LatLng::new(
+                        segment[0].lat().max(segment[1].lat()),
+                        segment[0].lng().max(segment[1].lng()),
+                    ),
+                );
+                if !bounds.intersects(&segment) {
+                    continue;
+                }
+                let rows = bin_of(segment.min.lat() - bounds.min.lat())
+                    ..=bin_of(segment.max.lat() - bounds.min.lat());
+                let columns = bin_of(segment.min.lng() - bounds.min.lng())
+                    ..=bin_of(segment.max.lng() - bounds.min.lng());
+                for row in rows {
+                    for column in columns.clone() {
+                        let bin = &mut bins[row as usize * BINS_DIM + column as usize];
+                        if !bin.contains(&edge_id) {
+                            bin.push(edge_id);
+                        }
+                    }
+                }
+            }
+        }
+        bins
+    }
+}
+
+/// The edge infos of a tile, one per pair of edges, and the text list of their names.
+struct EdgeInfos {
+    edge_infos: CheckedVec<InfernoEdgeInfo>,
+    indexes: HashMap<usize, usize>,
+    text_list: Vec<u8>,
+    names: HashMap<String, usize>,
+}
+
+impl EdgeInfos {
+    fn new(tile_id: TileId) -> Self {
+        EdgeInfos {
+            edge_infos: CheckedVec::new(tile_id),
+            indexes: HashMap::new(),
+            // Valhalla's text list starts with an empty string.
+            text_list: vec![0],
+            names: HashMap::new(),
+        }
+    }
+
+    /// Returns the index of the pair's edge info, adding it if this is the first of its edges in
+    /// the tile.
+    fn add(
+        &mut self,
+        index: usize,
+        edge: &BuilderEdge,
+        graph: &BuiltGraph<'_>,
+    ) -> Result<usize, anyhow::Error> {
+        if let Some(edge_info) = self.indexes.get(&index) {
+            return Ok(*edge_info);
+        }
+        let attributes = &edge.attributes;
+        let names = attributes
+            .names
+            .iter()
+            .map(|name| ValhallaNameInfo::new().with_name_offset(self.name_offset(name)))
+            .collect::<Vec<_>>();
+        let shape = edge.shape(&graph.builder.nodes);
+        let mut shape_bytes = Vec::new();
+        encode_shape(&mut shape_bytes, &shape);
+
+        let way_id = attributes.way_id;
+        let mut bitfield2 = ValhallaEdgeInfoBitfield2::new();
+        bitfield2
+            .set_name_count_checked(names.len())
+            .map_err(|_| anyhow::anyhow!("Edge {} has too many names", index))?;
+        bitfield2
+            .set_encoded_shape_size_checked(shape_bytes.len())
+            .map_err(|_| anyhow::anyhow!("Edge {} has too long a shape", index))?;
+        bitfield2.set_extended_wayid1((way_id >> 40) as u8);
+        // The way ID's top two bytes are only written if they're set.
+        bitfield2.set_extended_wayid_size(if way_id >> 48 != 0 { 2 } else { 0 });
+        let (mean_elevation, encoded_elevation) = if attributes.elevation.is_empty() {
+            (NO_MEAN_ELEVATION, Vec::new())
+        } else {
+            let mean = attributes.elevation.iter().sum::<f64>() / attributes.elevation.len() as f64;
+            let encoded = encode_edge_elevation(&attributes.elevation)
+                .ok_or_else(|| anyhow::anyhow!("Edge {} has too steep an elevation", index))?;
+            bitfield2.set_has_elevation(true);
+            (encode_mean_elevation(mean), encoded)
+        };
+        self.edge_infos.push(InfernoEdgeInfo {
+            info: ValhallaEdgeInfo {
+                way_id: way_id as u32,
+                bitfield1: ValhallaEdgeInfoBitfield1::new()
+                    .with_mean_elevation(mean_elevation)
+                    .with_extended_wayid0((way_id >> 32) as u8),
+                bitfield2,
+            },
+            names,
+            shape,
+            way_id,
+            encoded_elevation,
+        });
+        let edge_info = self.edge_infos.len() - 1;
+        self.indexes.insert(index, edge_info);
+        Ok(edge_info)
+    }
+
+    fn name_offset(&mut self, name: &str) -> usize {
+        if let Some(offset) = self.names.get(name) {
+            return *offset;
+        }
+        let offset = self.text_list.len();
+        self.text_list.extend_from_slice(name.as_bytes());
+        self.text_list.push(0);
+        self.names.insert(name.to_string(), offset);
+        offset
+    }
+}
+
+/// Splits a node's offset from its tile's base into the millionths of a degree and the 7th digit
+/// that Valhalla stores separately.
+fn position_offset(value: f64, base: f64) -> (u32, u8) {
+    let offset = ((value - base) * 1e7).round().max(0.0) as u32;
+    (offset / 10, (offset % 10) as u8)
+}
+
+/// The tiles built from a `GraphBuilder`, with the IDs its nodes and edges were given.
+#[derive(Debug, Clone)]
+pub struct SyntheticGraph {
+    tiles: Vec<InfernoTile>,
+    node_ids: Vec<GraphEntityId<ValhallaNodeInfo>>,
+    edge_ids: Vec<[GraphEntityId<ValhallaDirectedEdge>; 2]>,
+}
+
+impl SyntheticGraph {
+    /// The graph's tiles, sorted by tile ID.
+    pub fn tiles(&self) -> &[InfernoTile] {
+        &self.tiles
+    }
+
+    pub fn into_tiles(self) -> Vec<InfernoTile> {
+        self.tiles
+    }
+
+    /// The graph's tiles in Valhalla's format, in the same order as `tiles`.
+    pub fn valhalla_tiles(&self) -> Result<Vec<Vec<u8>>, anyhow::Error> {
+        self.tiles.iter().map(InfernoTile::to_valhalla).collect()
+    }
+
+    pub fn node_id(&self, node: NodeHandle) -> GraphEntityId<ValhallaNodeInfo> {
+        self.node_ids[node.0]
+    }
+
+    /// Returns the forward directed edge, from the edge's start node to its end node, and its
+    /// opposing edge.
+    pub fn edge_ids(
+        &self,
+        edge: EdgeHandle,
+    ) -> (
+        GraphEntityId<ValhallaDirectedEdge>,
+        GraphEntityId<ValhallaDirectedEdge>,
+    ) {
+        let [forward, reverse] = self.edge_ids[edge.0];
+        (forward, reverse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geomath::LatLng,
+        inferno::{
+            graph::{EdgeSearch, InfernoTileGraph},
+            integrity::check_tile_links,
+            tile_ref::AsTileRef,
+            version::TileVersion,
+            InfernoTile,
+        },
+        tiles::TileLevel,
+    };
+
+    use super::{EdgeAttributes, GraphBuilder};
+
+    fn assert_near(actual: LatLng, expected: LatLng, tolerance: f64) {
+        assert!(
+            (actual.lat() - expected.lat()).abs() < tolerance
+                && (actual.lng() - expected.lng()).abs() < tolerance,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn builds_a_street() {
+        let start = LatLng::new(47.6, -122.4);
+        let end = LatLng::new(47.6012345, -122.3987654);
+        let mut builder = GraphBuilder::new();
+        let a = builder.add_node(start);
+        let b = builder.add_node(end);
+        let street = builder.add_edge(
+            a,
+            b,
+            EdgeAttributes {
+                way_id: 0x0001_0002_0000_0003,
+                names: vec!["Main Street".to_string()],
+                shape: vec![LatLng::new(47.6005, -122.3995)],
+                ..Default::default()
+            },
+        );
+        let graph = builder.build().unwrap();
+        assert_eq!(graph.tiles().len(), 1);
+        let tile = &graph.tiles()[0];
+        assert_eq!(tile.version(), TileVersion::newest_supported());
+
+        let (forward, reverse) = graph.edge_ids(street);
+        let forward_edge = tile.directed_edges.get(&forward).unwrap();
+        let reverse_edge = tile.directed_edges.get(&reverse).unwrap();
+        assert_eq!(forward_edge.end_node(), graph.node_id(b));
+        assert_eq!(reverse_edge.end_node(), graph.node_id(a));
+        assert_eq!(forward_edge.opposing_edge_index(), 0);
+        assert!(!forward_edge.restrictions1.leaves_tile());
+        assert!(forward_edge.data3.length_meters() > 150);
+
+        let node = tile.nodes.get(&graph.node_id(b)).unwrap();
+        // Node positions keep the 7th digit, but shapes are stored to 6 digits.
+        assert_near(node.position(tile), end, 1e-9);
+        let names = tile.edge_names(&reverse).unwrap();
+        assert_eq!(names[0].text(), "Main Street");
+        assert_eq!(tile.way_id(&reverse), Some(0x0001_0002_0000_0003));
+        let shape = tile.edge_shape(&reverse).unwrap();
+        assert_eq!(shape.len(), 3);
+        assert_near(shape[0], end, 1e-6);
+
+        assert!(check_tile_links(&tile.tile_ref(), |_| None).is_empty());
+        let bytes = graph.valhalla_tiles().unwrap();
+        assert_eq!(&InfernoTile::from_valhalla(&bytes[0]).unwrap(), tile);
+    }
+
+    #[test]
+    fn splits_edges_across_tiles() {
+        // Local tiles are a quarter of a degree, so the edge crosses from one tile into the next.
+        let mut builder = GraphBuilder::new();
+        let a = builder.add_node(LatLng::new(47.6, -122.251));
+        let b = builder.add_node(LatLng::new(47.6, -122.249));
+        let c = builder.add_node(LatLng::new(47.601, -122.249));
+        let crossing = builder.add_edge(a, b, EdgeAttributes::default());
+        builder.add_edge(b, c, EdgeAttributes::default());
+        let graph = builder.build().unwrap();
+        assert_eq!(graph.tiles().len(), 2);
+
+        let (forward, reverse) = graph.edge_ids(crossing);
+        assert_ne!(forward.tile_id(), reverse.tile_id());
+        for tile in graph.tiles() {
+            let tile = tile.tile_ref();
+            let edge = tile
+                .directed_edges
+                .get(&forward)
+                .or_else(|| tile.directed_edges.get(&reverse))
+                .unwrap();
+            assert!(edge.restrictions1.leaves_tile());
+            let problems = check_tile_links(&tile, |tile_id| {
+                graph
+                    .tiles()
+                    .iter()
+                    .map(|tile| tile.tile_ref())
+                    .find(|tile| tile.tile_id() == *tile_id)
+            });
+            assert!(problems.is_empty(), "{:?}", problems);
+        }
+        // The second edge leaves the node after the crossing edge's opposing edge.
+        let edge = graph.tiles()[1]
+            .directed_edges
+            .get(&graph.edge_ids(crossing).1)
+            .unwrap();
+        assert_eq!(edge.opposing_edge_index(), 0);
+
+        for edge_search in [EdgeSearch::RTree, EdgeSearch::Bins] {
+            let tile_graph = InfernoTileGraph::with_edge_search(graph.tiles(), edge_search);
+            let edges = tile_graph.edges_for_point(&LatLng::new(47.6001, -122.2505), 50.0, 10);
+            assert!(
+                edges
+                    .iter()
+                    .any(|(edge, _)| *edge == forward || *edge == reverse),
+                "{:?} {:?}",
+                edge_search,
+                edges
+            );
+        }
+    }
+
+    #[test]
+    fn adds_transitions_between_levels() {
+        let position = LatLng::new(47.6, -122.4);
+        let mut builder = GraphBuilder::new();
+        let arterial = builder.add_node_on_level(TileLevel::ARTERIAL, position);
+        let local = builder.add_node(position);
+        builder.add_transition(arterial, local);
+        let graph = builder.build().unwrap();
+        assert_eq!(graph.tiles().len(), 2);
+
+        let arterial_tile = &graph.tiles()[0];
+        assert_eq!(
+            TileLevel::of(&arterial_tile.tile_id()),
+            Some(TileLevel::ARTERIAL)
+        );
+        let down = arterial_tile.node_transitions.as_slice()[0];
+        assert_eq!(down.end_node(), graph.node_id(local).graph_entity_id);
+        assert!(!down.up());
+        let up = graph.tiles()[1].node_transitions.as_slice()[0];
+        assert_eq!(up.end_node(), graph.node_id(arterial).graph_entity_id);
+        assert!(up.up());
+        let node = arterial_tile.nodes.get(&graph.node_id(arterial)).unwrap();
+        assert_eq!(node.data2.transition_count(), 1);
+    }
+
+    #[test]
+    fn rejects_edges_between_levels() {
+        let mut builder = GraphBuilder::new();
+        let a = builder.add_node_on_level(TileLevel::ARTERIAL, LatLng::new(47.6, -122.4));
+        let b = builder.add_node(LatLng::new(47.61, -122.4));
+        builder.add_edge(a, b, EdgeAttributes::default());
+        assert!(builder.build().is_err());
+    }
+}
@@ -1,11 +1,13 @@
-use rkyv::Archive;
+use rkyv::{Archive, Archived, Deserialize, Serialize};
 
 use crate::valhalla::{
     graph_id::{GraphEntityId, TileId},
     VEntity,
 };
 
-#[derive(Debug, Clone, Archive)]
+use super::zero_copy::{in_place, slice_in_place, ArchivedInPlace};
+
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
 pub struct CheckedVec<Inner: Archive> {
     graph_id: TileId,
     inner: Vec<Inner>,
@@ -24,21 +26,7 @@ impl<Inner: Archive> CheckedVec<Inner> {
     }
 
     pub fn get<'a>(&'a self, index: &GraphEntityId<Inner>) -> Option<VEntity<&'a Inner>> {
-        if index.tile_id() != self.graph_id {
-            if tracing::enabled!(tracing::Level::WARN) {
-                tracing::warn!(
-                    "Attempted to access element at incorrect tile ID. Expected {}, got {}",
-                    self.graph_id,
-                    index.tile_id()
-                );
-            }
-        }
-        let index = index.graph_index();
-        if index < self.inner.len() {
-            Some(VEntity::new(self.graph_id, &self.inner[index]))
-        } else {
-            None
-        }
+        self.borrowed().get(index)
     }
 
     pub fn len(&self) -> usize {
@@ -49,6 +37,14 @@ impl<Inner: Archive> CheckedVec<Inner> {
         self.inner.is_empty()
     }
 
+    pub fn as_slice(&self) -> &[Inner] {
+        &self.inner
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [Inner] {
+        &mut self.inner
+    }
+
     pub fn iter(&self) -> std::slice::Iter<'_, Inner> {
         self.inner.iter()
     }
@@ -87,6 +83,144 @@ impl<Inner: Archive> CheckedVec<Inner> {
     }
 
     pub fn slice(&self, start: GraphEntityId<Inner>, count: usize) -> &[Inner] {
-        &self.inner[start.graph_index()..start.graph_index() + count]
+        self.borrowed().slice(start, count)
+    }
+
+    pub fn borrowed(&self) -> CheckedSlice<'_, Inner> {
+        CheckedSlice {
+            graph_id: self.graph_id,
+            inner: &self.inner,
+        }
+    }
+}
+
+impl<Inner: ArchivedInPlace> ArchivedCheckedVec<Inner> {
+    /// Borrows the archived records in place.
+    pub fn borrowed(&self) -> CheckedSlice<'_, Inner> {
+        CheckedSlice {
+            graph_id: *in_place::<TileId>(&self.graph_id),
+            inner: slice_in_place(&self.inner),
+        }
+    }
+}
+
+/// The records of one kind in a tile, borrowed from a `CheckedVec` or read in place from an
+/// archived one.
+#[derive(Debug)]
+pub struct CheckedSlice<'a, Inner> {
+    graph_id: TileId,
+    inner: &'a [Inner],
+}
+
+// Implemented by hand so `Inner` doesn't need to be `Clone`.
+impl<Inner> Clone for CheckedSlice<'_, Inner> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Inner> Copy for CheckedSlice<'_, Inner> {}
+
+impl<'a, Inner> CheckedSlice<'a, Inner> {
+    pub fn get(&self, index: &GraphEntityId<Inner>) -> Option<VEntity<&'a Inner>> {
+        if index.tile_id() != self.graph_id && tracing::enabled!(tracing::Level::WARN) {
+            tracing::warn!(
+                "Attempted to access element at incorrect tile ID. Expected {}, got {}",
+                self.graph_id,
+                index.tile_id()
+            );
+        }
+        self.inner
+            .get(index.graph_index())
+            .map(|inner| VEntity::new(self.graph_id, inner))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &'a [Inner] {
+        self.inner
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'a, Inner> {
+        self.inner.iter()
+    }
+
+    /// Returns `count` elements from `start`, or an empty slice if they aren't all in the tile.
+    pub fn slice(&self, start: GraphEntityId<Inner>, count: usize) -> &'a [Inner] {
+        let start = start.graph_index();
+        start
+            .checked_add(count)
+            .and_then(|end| self.inner.get(start..end))
+            .unwrap_or_default()
+    }
+}
+
+/// Records with variable length data, such as edge infos, that are read through a borrowed form
+/// which both the record and its archived form can produce.
+pub(crate) trait Borrowed<'a> {
+    type Ref;
+
+    fn borrowed(&'a self) -> Self::Ref;
+}
+
+/// The records of one kind in a tile that are read through `Borrowed`, from owned or archived
+/// storage.
+pub(crate) enum RecordList<'a, Inner: Archive> {
+    Owned(&'a [Inner]),
+    Archived(&'a [Archived<Inner>]),
+}
+
+impl<Inner: Archive> Clone for RecordList<'_, Inner> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Inner: Archive> Copy for RecordList<'_, Inner> {}
+
+impl<'a, Inner, Ref> RecordList<'a, Inner>
+where
+    Inner: Archive + Borrowed<'a, Ref = Ref>,
+    Archived<Inner>: Borrowed<'a, Ref = Ref>,
+{
+    pub(crate) fn get(&self, index: usize) -> Option<Ref> {
+        match self {
+            RecordList::Owned(records) => records.get(index).map(Borrowed::borrowed),
+            RecordList::Archived(records) => records.get(index).map(Borrowed::borrowed),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Ref> + 'a {
+        let (owned, archived): (&'a [Inner], &'a [Archived<Inner>]) = match *self {
+            RecordList::Owned(records) => (records, &[]),
+            RecordList::Archived(records) => (&[], records),
+        };
+        owned
+            .iter()
+            .map(Borrowed::borrowed)
+            .chain(archived.iter().map(Borrowed::borrowed))
+    }
+}
+
+impl<'a, Inner: Archive> RecordList<'a, Inner> {
+    pub(crate) fn owned(records: &'a CheckedVec<Inner>) -> Self {
+        RecordList::Owned(&records.inner)
+    }
+
+    pub(crate) fn archived(records: &'a ArchivedCheckedVec<Inner>) -> Self {
+        RecordList::Archived(&records.inner)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            RecordList::Owned(records) => records.len(),
+            RecordList::Archived(records) => records.len(),
+        }
     }
 }
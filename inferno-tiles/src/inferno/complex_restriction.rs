@@ -0,0 +1,398 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, IntoBytes};
+
+use crate::valhalla::{
+    complex_restriction::{
+        ValhallaComplexRestriction, ValhallaComplexRestrictionVia, ValhallaTimeDomain,
+    },
+    directed_edge::ValhallaDirectedEdge,
+    graph_id::GraphEntityId,
+};
+
+use super::{
+    checked_vec::Borrowed,
+    zero_copy::{in_place, slice_in_place},
+};
+
+const COMPLEX_RESTRICTION_SIZE: usize = size_of::<ValhallaComplexRestriction>();
+const COMPLEX_RESTRICTION_VIA_SIZE: usize = size_of::<ValhallaComplexRestrictionVia>();
+
+/// A restriction spanning several edges (e.g. a via-way "no U-turn"), along with its via list.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct InfernoComplexRestriction {
+    pub(crate) restriction: ValhallaComplexRestriction,
+    pub(crate) vias: Vec<ValhallaComplexRestrictionVia>,
+}
+
+impl InfernoComplexRestriction {
+    /// Parses every complex restriction in `bytes`, which must span exactly one of the tile's
+    /// complex restriction lists.
+    pub(crate) fn parse_list(
+        bytes: &[u8],
+    ) -> Result<Vec<InfernoComplexRestriction>, anyhow::Error> {
+        let mut restrictions = Vec::new();
+        let mut ptr = 0;
+        while ptr < bytes.len() {
+            let restriction = ValhallaComplexRestriction::ref_from_bytes(
+                bytes
+                    .get(ptr..ptr + COMPLEX_RESTRICTION_SIZE)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid tile: complex restriction out of bounds")
+                    })?,
+            )
+            .map_err(|err| anyhow::anyhow!("Failed ValhallaComplexRestriction cast: {:?}", err))?;
+            ptr += COMPLEX_RESTRICTION_SIZE;
+
+            let via_count = restriction.data.via_count();
+            let mut vias = Vec::with_capacity(via_count);
+            for _ in 0..via_count {
+                let via = ValhallaComplexRestrictionVia::ref_from_bytes(
+                    bytes
+                        .get(ptr..ptr + COMPLEX_RESTRICTION_VIA_SIZE)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Invalid tile: complex restriction via out of bounds")
+                        })?,
+                )
+                .map_err(|err| {
+                    anyhow::anyhow!("Failed ValhallaComplexRestrictionVia cast: {:?}", err)
+                })?;
+                vias.push(*via);
+                ptr += COMPLEX_RESTRICTION_VIA_SIZE;
+            }
+
+            restrictions.push(InfernoComplexRestriction {
+                restriction: *restriction,
+                vias,
+            });
+        }
+        Ok(restrictions)
+    }
+
+    /// Appends the restriction and its vias in Valhalla's format to `bytes`.
+    pub(crate) fn write_valhalla(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self.restriction.as_bytes());
+        for via in &self.vias {
+            bytes.extend_from_slice(via.as_bytes());
+        }
+    }
+}
+
+impl<'a> Borrowed<'a> for InfernoComplexRestriction {
+    type Ref = ComplexRestrictionRef<'a>;
+
+    fn borrowed(&'a self) -> ComplexRestrictionRef<'a> {
+        ComplexRestrictionRef {
+            restriction: &self.restriction,
+            vias: &self.vias,
+        }
+    }
+}
+
+impl<'a> Borrowed<'a> for ArchivedInfernoComplexRestriction {
+    type Ref = ComplexRestrictionRef<'a>;
+
+    fn borrowed(&'a self) -> ComplexRestrictionRef<'a> {
+        ComplexRestrictionRef {
+            restriction: in_place(&self.restriction),
+            vias: slice_in_place(&self.vias),
+        }
+    }
+}
+
+/// A complex restriction borrowed from an `InfernoComplexRestriction` or read in place from an
+/// archived one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplexRestrictionRef<'a> {
+    restriction: &'a ValhallaComplexRestriction,
+    vias: &'a [ValhallaComplexRestrictionVia],
+}
+
+impl ComplexRestrictionRef<'_> {
+    pub fn from_edge(&self) -> GraphEntityId<ValhallaDirectedEdge> {
+        GraphEntityId::new(self.restriction.from.graph_id())
+    }
+
+    pub fn to_edge(&self) -> GraphEntityId<ValhallaDirectedEdge> {
+        GraphEntityId::new(self.restriction.to.graph_id())
+    }
+
+    /// Edges between the from and to edges, in travel order.
+    pub fn via_edges(&self) -> Vec<GraphEntityId<ValhallaDirectedEdge>> {
+        self.vias
+            .iter()
+            .map(|via| GraphEntityId::new(via.graph_id()))
+            .collect()
+    }
+
+    pub fn restriction_type(&self) -> RestrictionType {
+        RestrictionType::from_valhalla(self.restriction.data.restriction_type())
+    }
+
+    /// Access mode mask the restriction applies to.
+    pub fn modes(&self) -> u16 {
+        self.restriction.data.modes()
+    }
+
+    /// Date and time range the restriction applies to, or `None` if it always applies.
+    pub fn time_domain(&self) -> Option<TimeDomain> {
+        self.restriction
+            .data
+            .has_dt()
+            .then_some(TimeDomain(self.restriction.time_domain))
+    }
+}
+
+/// Kind of turn restriction, from Valhalla's `RestrictionType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RestrictionType {
+    NoLeftTurn,
+    NoRightTurn,
+    NoStraightOn,
+    NoUTurn,
+    OnlyRightTurn,
+    OnlyLeftTurn,
+    OnlyStraightOn,
+    NoEntry,
+    NoExit,
+    NoTurn,
+    /// A restriction type this version of inferno doesn't know about.
+    Unknown(u8),
+}
+
+impl RestrictionType {
+    pub(crate) fn from_valhalla(restriction_type: u8) -> RestrictionType {
+        match restriction_type {
+            0 => RestrictionType::NoLeftTurn,
+            1 => RestrictionType::NoRightTurn,
+            2 => RestrictionType::NoStraightOn,
+            3 => RestrictionType::NoUTurn,
+            4 => RestrictionType::OnlyRightTurn,
+            5 => RestrictionType::OnlyLeftTurn,
+            6 => RestrictionType::OnlyStraightOn,
+            7 => RestrictionType::NoEntry,
+            8 => RestrictionType::NoExit,
+            9 => RestrictionType::NoTurn,
+            other => RestrictionType::Unknown(other),
+        }
+    }
+
+    /// Is this an "only" restriction, which forbids every other maneuver?
+    pub fn is_only(&self) -> bool {
+        matches!(
+            self,
+            RestrictionType::OnlyRightTurn
+                | RestrictionType::OnlyLeftTurn
+                | RestrictionType::OnlyStraightOn
+        )
+    }
+}
+
+/// A date and time range from a conditional restriction, in Valhalla's `TimeDomain` encoding.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeDomain(ValhallaTimeDomain);
+
+impl TimeDomain {
+    /// Whether `begin_day_dow`/`end_day_dow` are the nth day of the week of `begin_week`/
+    /// `end_week`, rather than a day of the month.
+    pub fn is_nth_day_of_week(&self) -> bool {
+        self.0.dt_type()
+    }
+
+    /// Day of week mask, starting with Sunday in the lowest bit.
+    pub fn days_of_week(&self) -> u8 {
+        self.0.dow()
+    }
+
+    pub fn begin_month(&self) -> u8 {
+        self.0.begin_month()
+    }
+
+    pub fn begin_day_dow(&self) -> u8 {
+        self.0.begin_day_dow()
+    }
+
+    pub fn begin_week(&self) -> u8 {
+        self.0.begin_week()
+    }
+
+    pub fn begin_hours(&self) -> u8 {
+        self.0.begin_hrs()
+    }
+
+    pub fn begin_minutes(&self) -> u8 {
+        self.0.begin_mins()
+    }
+
+    pub fn end_month(&self) -> u8 {
+        self.0.end_month()
+    }
+
+    pub fn end_day_dow(&self) -> u8 {
+        self.0.end_day_dow()
+    }
+
+    pub fn end_week(&self) -> u8 {
+        self.0.end_week()
+    }
+
+    pub fn end_hours(&self) -> u8 {
+        self.0.end_hrs()
+    }
+
+    pub fn end_minutes(&self) -> u8 {
+        self.0.end_mins()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geomath::LatLng,
+        inferno::{
+            builder::{EdgeAttributes, GraphBuilder},
+            test_tiles::edit_tile,
+            InfernoTile,
+        },
+        valhalla::complex_restriction::{
+            ValhallaComplexRestrictionData, ValhallaComplexRestrictionEdge,
+        },
+    };
+
+    use super::*;
+
+    /// Valhalla's `kAutoAccess`.
+    const AUTO_ACCESS: u16 = 1;
+
+    fn restriction(
+        from: GraphEntityId<ValhallaDirectedEdge>,
+        vias: &[GraphEntityId<ValhallaDirectedEdge>],
+        to: GraphEntityId<ValhallaDirectedEdge>,
+        restriction_type: u8,
+        time_domain: Option<ValhallaTimeDomain>,
+    ) -> InfernoComplexRestriction {
+        InfernoComplexRestriction {
+            restriction: ValhallaComplexRestriction {
+                from: ValhallaComplexRestrictionEdge::new().with_graph_id(from.graph_entity_id),
+                to: ValhallaComplexRestrictionEdge::new().with_graph_id(to.graph_entity_id),
+                data: ValhallaComplexRestrictionData::new()
+                    .with_via_count(vias.len())
+                    .with_restriction_type(restriction_type)
+                    .with_modes(AUTO_ACCESS)
+                    .with_has_dt(time_domain.is_some()),
+                time_domain: time_domain.unwrap_or_default(),
+            },
+            vias: vias
+                .iter()
+                .map(|via| ValhallaComplexRestrictionVia::new().with_graph_id(via.graph_entity_id))
+                .collect(),
+        }
+    }
+
+    /// Sets the modes for which complex restrictions start and end on each of `edges`.
+    fn set_restriction_modes(
+        tile: &mut InfernoTile,
+        edges: &[(GraphEntityId<ValhallaDirectedEdge>, u64, u64)],
+    ) {
+        let directed_edges = tile.directed_edges.as_mut_slice();
+        for (edge, start, end) in edges {
+            let restrictions = &mut directed_edges[edge.graph_index()].restrictions2;
+            restrictions.set_start_restriction(*start);
+            restrictions.set_end_restriction(*end);
+        }
+    }
+
+    #[test]
+    fn finds_restrictions_in_each_direction() {
+        // A street turning right at c, approached from a through b.
+        let mut builder = GraphBuilder::new();
+        let a = builder.add_node(LatLng::new(47.6, -122.4));
+        let b = builder.add_node(LatLng::new(47.601, -122.4));
+        let c = builder.add_node(LatLng::new(47.602, -122.4));
+        let d = builder.add_node(LatLng::new(47.602, -122.399));
+        let ab = builder.add_edge(a, b, EdgeAttributes::default());
+        let bc = builder.add_edge(b, c, EdgeAttributes::default());
+        let cd = builder.add_edge(c, d, EdgeAttributes::default());
+        let graph = builder.build().unwrap();
+        let (ab, _) = graph.edge_ids(ab);
+        let (bc, cb) = graph.edge_ids(bc);
+        let (cd, dc) = graph.edge_ids(cd);
+        let tile = graph.into_tiles().remove(0);
+
+        // No right turn from a through b onto cd on weekday mornings, stored with the edge it
+        // ends on. No left turn from dc onto cb, stored with the edge it starts on.
+        let weekday_mornings = ValhallaTimeDomain::new()
+            .with_dow(0b0111110)
+            .with_begin_hrs(7)
+            .with_end_hrs(9);
+        let forward = restriction(ab, &[bc], cd, 1, Some(weekday_mornings));
+        let reverse = restriction(dc, &[], cb, 0, None);
+        let mut tile = edit_tile(tile, |tile| {
+            tile.complex_restrictions_forward.push(forward.clone());
+            tile.complex_restrictions_reverse.push(reverse.clone());
+            set_restriction_modes(tile, &[(cd, 0, 1), (dc, 1, 0)]);
+        });
+        assert_eq!(tile.complex_restrictions_forward.as_slice(), [forward]);
+        assert_eq!(tile.complex_restrictions_reverse.as_slice(), [reverse]);
+
+        let ending = tile.complex_restrictions_ending_on(&cd).unwrap();
+        assert_eq!(ending.len(), 1);
+        assert_eq!(ending[0].from_edge(), ab);
+        assert_eq!(ending[0].via_edges(), vec![bc]);
+        assert_eq!(ending[0].to_edge(), cd);
+        assert_eq!(ending[0].restriction_type(), RestrictionType::NoRightTurn);
+        assert_eq!(ending[0].modes(), AUTO_ACCESS);
+        let time_domain = ending[0].time_domain().unwrap();
+        assert_eq!(time_domain.days_of_week(), 0b0111110);
+        assert_eq!((time_domain.begin_hours(), time_domain.end_hours()), (7, 9));
+
+        let starting = tile.complex_restrictions_starting_on(&dc).unwrap();
+        assert_eq!(starting.len(), 1);
+        assert_eq!(starting[0].to_edge(), cb);
+        assert!(starting[0].via_edges().is_empty());
+        assert_eq!(starting[0].restriction_type(), RestrictionType::NoLeftTurn);
+        assert!(starting[0].time_domain().is_none());
+
+        // Each list is only searched from its own side of the restriction.
+        assert!(tile
+            .complex_restrictions_starting_on(&ab)
+            .unwrap()
+            .is_empty());
+        assert!(tile.complex_restrictions_ending_on(&cb).unwrap().is_empty());
+
+        // Edges without the start or end modes set aren't searched at all.
+        set_restriction_modes(&mut tile, &[(cd, 0, 0), (dc, 0, 0)]);
+        assert!(tile.complex_restrictions_ending_on(&cd).unwrap().is_empty());
+        assert!(tile
+            .complex_restrictions_starting_on(&dc)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parses_restriction_lists() {
+        let edge = |index: u64| GraphEntityId::new(index << 25);
+        let restrictions = vec![
+            restriction(edge(0), &[edge(1), edge(2)], edge(3), 3, None),
+            restriction(edge(4), &[], edge(5), 7, None),
+        ];
+        let mut bytes = Vec::new();
+        for restriction in &restrictions {
+            restriction.write_valhalla(&mut bytes);
+        }
+        assert_eq!(
+            bytes.len(),
+            2 * COMPLEX_RESTRICTION_SIZE + 2 * COMPLEX_RESTRICTION_VIA_SIZE
+        );
+        assert_eq!(
+            InfernoComplexRestriction::parse_list(&bytes).unwrap(),
+            restrictions
+        );
+        assert!(InfernoComplexRestriction::parse_list(&[])
+            .unwrap()
+            .is_empty());
+
+        // A list cut off in the middle of the first restriction's vias.
+        let truncated = &bytes[..COMPLEX_RESTRICTION_SIZE + COMPLEX_RESTRICTION_VIA_SIZE];
+        assert!(InfernoComplexRestriction::parse_list(truncated).is_err());
+    }
+}
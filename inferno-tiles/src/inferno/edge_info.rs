@@ -0,0 +1,426 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, IntoBytes};
+
+use crate::{
+    geomath::LatLng,
+    valhalla::{
+        edge_info::ValhallaEdgeInfo,
+        name_info::ValhallaNameInfo,
+        varint::{read_signed_varint, write_signed_varint},
+    },
+};
+
+use super::{
+    checked_vec::Borrowed,
+    elevation::{decode_edge_elevation, decode_mean_elevation},
+    text_list::TextListRef,
+    zero_copy::{in_place, slice_in_place},
+};
+
+const EDGE_INFO_SIZE: usize = size_of::<ValhallaEdgeInfo>();
+const NAME_INFO_SIZE: usize = size_of::<ValhallaNameInfo>();
+
+/// Tagged name type used by Valhalla for pronunciations (`TaggedValue::kLinguistic`, formerly
+/// `kPronunciation`).
+const TAGGED_VALUE_PRONUNCIATION: u8 = 2;
+
+/// Precision of the encoded shape coordinates (6 decimal digits).
+const SHAPE_PRECISION: f64 = 1e6;
+
+/// Edge info shared by a pair of opposing directed edges, along with its variable-length data.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct InfernoEdgeInfo {
+    pub(crate) info: ValhallaEdgeInfo,
+    pub(crate) names: Vec<ValhallaNameInfo>,
+    /// Shape of the edge in the forward direction.
+    pub(crate) shape: Vec<LatLng>,
+    /// Full OSM way ID, including the extended bytes.
+    pub(crate) way_id: u64,
+    /// Encoded elevation deltas, sampled evenly along the edge in the forward direction.
+    pub(crate) encoded_elevation: Vec<i8>,
+}
+
+impl InfernoEdgeInfo {
+    /// Parses an edge info record from `bytes`, which must be exactly the record.
+    ///
+    /// Valhalla's `EdgeInfo` (baldr/edgeinfo.h) is the fixed size fields, the name infos, the
+    /// encoded shape, the extended way ID bytes and then, if `has_elevation` is set, the encoded
+    /// elevation. The elevation's size isn't stored: `EdgeInfo::encoded_elevation(length,
+    /// interval)` in baldr/edgeinfo.cc derives it from the length of the edge. Records are packed
+    /// back to back, so here the encoded elevation is the rest of the record.
+    pub(crate) fn from_valhalla(bytes: &[u8]) -> Result<InfernoEdgeInfo, anyhow::Error> {
+        // Records aren't padded, so they may not be aligned for a cast in place.
+        let info = ValhallaEdgeInfo::read_from_bytes(
+            bytes
+                .get(0..EDGE_INFO_SIZE)
+                .ok_or_else(|| anyhow::anyhow!("Invalid tile: edge info out of bounds"))?,
+        )
+        .map_err(|err| anyhow::anyhow!("Failed ValhallaEdgeInfo cast: {:?}", err))?;
+        let mut ptr = EDGE_INFO_SIZE;
+
+        let name_count = info.bitfield2.name_count();
+        let mut names = Vec::with_capacity(name_count);
+        for _ in 0..name_count {
+            let name_bytes = bytes
+                .get(ptr..ptr + NAME_INFO_SIZE)
+                .ok_or_else(|| anyhow::anyhow!("Invalid tile: edge name info out of bounds"))?;
+            let name = ValhallaNameInfo::read_from_bytes(name_bytes)
+                .map_err(|err| anyhow::anyhow!("Failed ValhallaNameInfo cast: {:?}", err))?;
+            names.push(name);
+            ptr += NAME_INFO_SIZE;
+        }
+
+        let shape_size = info.bitfield2.encoded_shape_size();
+        let shape = decode_shape(
+            bytes
+                .get(ptr..ptr + shape_size)
+                .ok_or_else(|| anyhow::anyhow!("Invalid tile: edge shape out of bounds"))?,
+        )?;
+        ptr += shape_size;
+
+        // The seventh and eighth bytes of the extended way ID, if present, follow the shape. Way
+        // IDs are 64 bits, so there are at most two.
+        let extended_wayid_size = info.bitfield2.extended_wayid_size();
+        if extended_wayid_size > 2 {
+            return Err(anyhow::anyhow!(
+                "Invalid tile: extended way ID size {}",
+                extended_wayid_size
+            ));
+        }
+        let mut way_id = info.way_id as u64
+            | (info.bitfield1.extended_wayid0() as u64) << 32
+            | (info.bitfield2.extended_wayid1() as u64) << 40;
+        for byte_index in 0..extended_wayid_size {
+            let byte = *bytes
+                .get(ptr)
+                .ok_or_else(|| anyhow::anyhow!("Invalid tile: extended way ID out of bounds"))?;
+            way_id |= (byte as u64) << (48 + 8 * byte_index);
+            ptr += 1;
+        }
+
+        let mut encoded_elevation = Vec::new();
+        if info.bitfield2.has_elevation() {
+            encoded_elevation.extend(bytes[ptr..].iter().map(|byte| *byte as i8));
+        }
+
+        Ok(InfernoEdgeInfo {
+            info,
+            names,
+            shape,
+            way_id,
+            encoded_elevation,
+        })
+    }
+
+    /// Appends the record in Valhalla's format to `bytes`, the inverse of `from_valhalla`.
+    pub(crate) fn write_valhalla(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self.info.as_bytes());
+        for name in &self.names {
+            bytes.extend_from_slice(name.as_bytes());
+        }
+        encode_shape(bytes, &self.shape);
+        // `from_valhalla` rejects records with more than two, which no 64 bit way ID needs.
+        for byte_index in 0..self.info.bitfield2.extended_wayid_size().min(2) {
+            bytes.push((self.way_id >> (48 + 8 * byte_index)) as u8);
+        }
+        if self.info.bitfield2.has_elevation() {
+            bytes.extend(self.encoded_elevation.iter().map(|delta| *delta as u8));
+        }
+    }
+
+    /// Returns the full OSM way ID of the edge.
+    pub fn way_id(&self) -> u64 {
+        self.way_id
+    }
+
+    /// Mean elevation of the edge in meters, if the edge has elevation data.
+    pub fn mean_elevation(&self) -> Option<f64> {
+        self.borrowed().mean_elevation()
+    }
+
+    /// Elevation in meters at each point of the forward shape, or an empty `Vec` if the edge has
+    /// no elevation data.
+    pub fn elevation(&self) -> Vec<f64> {
+        self.borrowed().elevation()
+    }
+}
+
+impl<'a> Borrowed<'a> for InfernoEdgeInfo {
+    type Ref = EdgeInfoRef<'a>;
+
+    fn borrowed(&'a self) -> EdgeInfoRef<'a> {
+        EdgeInfoRef {
+            info: &self.info,
+            names: &self.names,
+            shape: &self.shape,
+            way_id: self.way_id,
+            encoded_elevation: &self.encoded_elevation,
+        }
+    }
+}
+
+impl<'a> Borrowed<'a> for ArchivedInfernoEdgeInfo {
+    type Ref = EdgeInfoRef<'a>;
+
+    fn borrowed(&'a self) -> EdgeInfoRef<'a> {
+        EdgeInfoRef {
+            info: in_place(&self.info),
+            names: slice_in_place(&self.names),
+            shape: slice_in_place(&self.shape),
+            way_id: self.way_id.to_native(),
+            encoded_elevation: &self.encoded_elevation,
+        }
+    }
+}
+
+/// Edge info borrowed from an `InfernoEdgeInfo` or read in place from an archived one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeInfoRef<'a> {
+    info: &'a ValhallaEdgeInfo,
+    names: &'a [ValhallaNameInfo],
+    shape: &'a [LatLng],
+    way_id: u64,
+    encoded_elevation: &'a [i8],
+}
+
+impl<'a> EdgeInfoRef<'a> {
+    /// Returns the full OSM way ID of the edge.
+    pub fn way_id(&self) -> u64 {
+        self.way_id
+    }
+
+    /// Shape of the edge in the forward direction.
+    pub fn shape(&self) -> &'a [LatLng] {
+        self.shape
+    }
+
+    /// Mean elevation of the edge in meters, if the edge has elevation data.
+    pub fn mean_elevation(&self) -> Option<f64> {
+        decode_mean_elevation(self.info.bitfield1.mean_elevation())
+    }
+
+    /// Elevation in meters at each point of the forward shape, or an empty `Vec` if the edge has
+    /// no elevation data.
+    pub fn elevation(&self) -> Vec<f64> {
+        match self.mean_elevation() {
+            Some(mean_elevation) => {
+                decode_edge_elevation(self.encoded_elevation, mean_elevation, self.shape)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub(crate) fn names(&self, text_list: TextListRef<'a>) -> Vec<EdgeName<'a>> {
+        self.names
+            .iter()
+            .filter_map(|name| EdgeName::new(name, text_list))
+            .collect()
+    }
+}
+
+/// Decodes a Valhalla edge shape: pairs of zigzag varint deltas (latitude first) at 6 digit
+/// precision.
+fn decode_shape(bytes: &[u8]) -> Result<Vec<LatLng>, anyhow::Error> {
+    let mut shape = Vec::new();
+    let mut ptr = 0;
+    let mut lat = 0i64;
+    let mut lng = 0i64;
+    while ptr < bytes.len() {
+        lat += read_signed_varint(bytes, &mut ptr)?;
+        lng += read_signed_varint(bytes, &mut ptr)?;
+        shape.push(LatLng::new(
+            lat as f64 / SHAPE_PRECISION,
+            lng as f64 / SHAPE_PRECISION,
+        ));
+    }
+    Ok(shape)
+}
+
+/// Appends `shape` to `bytes` in the encoding that `decode_shape` reads.
+pub(crate) fn encode_shape(bytes: &mut Vec<u8>, shape: &[LatLng]) {
+    let mut lat = 0i64;
+    let mut lng = 0i64;
+    for point in shape {
+        let point_lat = (point.lat() * SHAPE_PRECISION).round() as i64;
+        let point_lng = (point.lng() * SHAPE_PRECISION).round() as i64;
+        write_signed_varint(bytes, point_lat - lat);
+        write_signed_varint(bytes, point_lng - lng);
+        lat = point_lat;
+        lng = point_lng;
+    }
+}
+
+/// A name of an edge, resolved from the tile's text list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeName<'a> {
+    text: &'a str,
+    is_route_number: bool,
+    tag: Option<u8>,
+}
+
+impl<'a> EdgeName<'a> {
+    fn new(name: &ValhallaNameInfo, text_list: TextListRef<'a>) -> Option<EdgeName<'a>> {
+        let text = text_list.get(name.name_offset());
+        if text.is_none() {
+            tracing::warn!("Invalid text list offset {} for name", name.name_offset());
+        }
+        let text = text?;
+        let (text, tag) = if name.tagged() {
+            // Tagged names store their tag type in the first byte.
+            let tag = *text.as_bytes().first()?;
+            (text.get(1..)?, Some(tag))
+        } else {
+            (text, None)
+        };
+        Some(EdgeName {
+            text,
+            is_route_number: name.is_route_number(),
+            tag,
+        })
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Is this a route number (e.g. "I 5") rather than a street name?
+    pub fn is_route_number(&self) -> bool {
+        self.is_route_number
+    }
+
+    /// Is this a tagged name (pronunciation, level, layer, etc.) rather than a plain name?
+    pub fn is_tagged(&self) -> bool {
+        self.tag.is_some()
+    }
+
+    /// The raw Valhalla `TaggedValue` type of a tagged name.
+    pub fn tag(&self) -> Option<u8> {
+        self.tag
+    }
+
+    /// Is this a pronunciation of another name on the edge?
+    pub fn is_pronunciation(&self) -> bool {
+        self.tag == Some(TAGGED_VALUE_PRONUNCIATION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inferno::text_list::TextList,
+        valhalla::edge_info::{ValhallaEdgeInfoBitfield1, ValhallaEdgeInfoBitfield2},
+    };
+
+    use super::*;
+
+    #[test]
+    fn decodes_valhalla_shapes() {
+        // The Space Needle and a point 100 m south and 250 m east of it, as Valhalla's `encode7`
+        // writes them: latitude then longitude deltas, each zigzag encoded in 7 bit groups.
+        let bytes = [
+            0xb4, 0x86, 0xb5, 0x2d, 0xb9, 0x9b, 0xd7, 0x74, 0xcf, 0x0f, 0x88, 0x27,
+        ];
+        let shape = decode_shape(&bytes).unwrap();
+        assert_eq!(
+            shape,
+            vec![
+                LatLng::new(47.620506, -122.349277),
+                LatLng::new(47.619506, -122.346777),
+            ]
+        );
+
+        let mut encoded = Vec::new();
+        encode_shape(&mut encoded, &shape);
+        assert_eq!(encoded, bytes);
+    }
+
+    #[test]
+    fn rejects_extended_way_ids_past_64_bits() {
+        let mut info = ValhallaEdgeInfo {
+            way_id: 1,
+            bitfield1: ValhallaEdgeInfoBitfield1::new(),
+            bitfield2: ValhallaEdgeInfoBitfield2::new().with_extended_wayid_size(3),
+        };
+        let record = |info: &ValhallaEdgeInfo| [info.as_bytes(), &[0; 3]].concat();
+        assert!(InfernoEdgeInfo::from_valhalla(&record(&info)).is_err());
+
+        info.bitfield2.set_extended_wayid_size(2);
+        let edge_info = InfernoEdgeInfo::from_valhalla(&record(&info)).unwrap();
+        assert_eq!(edge_info.way_id(), 1);
+    }
+
+    #[test]
+    fn reads_elevation_to_the_end_of_the_record() {
+        // A record with no names, the shape from `decodes_valhalla_shapes` and elevation samples
+        // at 103, 107, 105.5 and 116.5 meters: a mean of 108, stored as 304 two meter steps from
+        // -500, and the quarter meter deltas with no count before them.
+        let shape = [
+            0xb4, 0x86, 0xb5, 0x2d, 0xb9, 0x9b, 0xd7, 0x74, 0xcf, 0x0f, 0x88, 0x27,
+        ];
+        let info = ValhallaEdgeInfo {
+            way_id: 42,
+            bitfield1: ValhallaEdgeInfoBitfield1::new().with_mean_elevation(304),
+            bitfield2: ValhallaEdgeInfoBitfield2::new()
+                .with_encoded_shape_size(shape.len())
+                .with_has_elevation(true),
+        };
+        let bytes = [info.as_bytes(), &shape, &[0x00, 0x10, 0xfa, 0x2c]].concat();
+
+        let edge_info = InfernoEdgeInfo::from_valhalla(&bytes).unwrap();
+        assert_eq!(edge_info.encoded_elevation, vec![0, 16, -6, 44]);
+        assert_eq!(edge_info.mean_elevation(), Some(108.0));
+        assert_eq!(edge_info.elevation(), vec![103.0, 116.5]);
+
+        let mut written = Vec::new();
+        edge_info.write_valhalla(&mut written);
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn reads_name_flags() {
+        let text_list = TextList::new(b"Main Street\0I 5\0\x02ay fayv\0\x01Level 2\0\0".to_vec());
+        let text_list = text_list.borrowed();
+        let name = |offset, is_route_number, tagged| {
+            EdgeName::new(
+                &ValhallaNameInfo::new()
+                    .with_name_offset(offset)
+                    .with_is_route_number(is_route_number)
+                    .with_tagged(tagged),
+                text_list,
+            )
+        };
+
+        let street = name(0, false, false).unwrap();
+        assert_eq!(street.text(), "Main Street");
+        assert!(!street.is_route_number());
+        assert!(!street.is_tagged());
+        assert_eq!(street.tag(), None);
+        assert!(!street.is_pronunciation());
+
+        let route = name(12, true, false).unwrap();
+        assert_eq!(route.text(), "I 5");
+        assert!(route.is_route_number());
+        assert!(!route.is_tagged());
+
+        let pronunciation = name(16, true, true).unwrap();
+        assert_eq!(pronunciation.text(), "ay fayv");
+        assert!(pronunciation.is_route_number());
+        assert!(pronunciation.is_tagged());
+        assert_eq!(pronunciation.tag(), Some(TAGGED_VALUE_PRONUNCIATION));
+        assert!(pronunciation.is_pronunciation());
+
+        let level = name(25, false, true).unwrap();
+        assert_eq!(level.text(), "Level 2");
+        assert_eq!(level.tag(), Some(1));
+        assert!(!level.is_pronunciation());
+
+        // A tagged name needs its tag byte, and offsets must point into the text list.
+        assert_eq!(name(34, false, true), None);
+        assert_eq!(name(36, false, false), None);
+    }
+
+    #[test]
+    fn rejects_truncated_shapes() {
+        assert!(decode_shape(&[0xb4, 0x86, 0xb5, 0x2d, 0xb9, 0x9b]).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::geomath::{distance_meters, LatLng};
+
+/// Minimum elevation Valhalla can store, in meters.
+const MIN_ELEVATION: f64 = -500.0;
+/// Precision of an edge's mean elevation, in meters.
+const MEAN_ELEVATION_PRECISION: f64 = 2.0;
+/// Stored mean elevation that means "no elevation data".
+pub(crate) const NO_MEAN_ELEVATION: u32 = 4095;
+/// Precision of the encoded elevation deltas, in meters.
+const ELEVATION_DELTA_PRECISION: f64 = 0.25;
+
+/// Decodes an edge's stored mean elevation into meters, or `None` if the edge has no elevation
+/// data.
+pub(crate) fn decode_mean_elevation(mean_elevation: u32) -> Option<f64> {
+    if mean_elevation == NO_MEAN_ELEVATION {
+        None
+    } else {
+        Some(MIN_ELEVATION + mean_elevation as f64 * MEAN_ELEVATION_PRECISION)
+    }
+}
+
+/// Encodes an edge's mean elevation in meters as Valhalla stores it, the inverse of
+/// `decode_mean_elevation`.
+pub(crate) fn encode_mean_elevation(mean_elevation: f64) -> u32 {
+    ((mean_elevation - MIN_ELEVATION) / MEAN_ELEVATION_PRECISION)
+        .round()
+        .clamp(0.0, (NO_MEAN_ELEVATION - 1) as f64) as u32
+}
+
+/// Encodes elevation samples spaced evenly along an edge as the deltas that
+/// `decode_edge_elevation` reads, or `None` if two neighbouring samples are too far apart to
+/// encode. The first delta is zero, since the profile is anchored by the mean elevation.
+pub(crate) fn encode_edge_elevation(samples: &[f64]) -> Option<Vec<i8>> {
+    let mut encoded = Vec::with_capacity(samples.len());
+    let mut previous = None;
+    for sample in samples {
+        // Deltas are taken between the rounded samples, so rounding errors don't accumulate.
+        let quantized = (sample / ELEVATION_DELTA_PRECISION).round() as i64;
+        let delta = previous.map_or(0, |previous| quantized - previous);
+        encoded.push(i8::try_from(delta).ok()?);
+        previous = Some(quantized);
+    }
+    Some(encoded)
+}
+
+/// Decodes an edge's encoded elevation and resamples it onto the points of its forward shape.
+///
+/// Valhalla's mjolnir/elevationbuilder.cc samples the edge at an even interval in the forward
+/// direction and stores `int8_t` deltas in 0.25 meter steps between the samples, with no count
+/// (see `InfernoEdgeInfo::from_valhalla`). The deltas only describe the relative profile, so the
+/// profile is shifted so that its mean matches the edge's `mean_elevation`, which is stored in 2
+/// meter steps from -500 meters.
+pub(crate) fn decode_edge_elevation(
+    encoded: &[i8],
+    mean_elevation: f64,
+    shape: &[LatLng],
+) -> Vec<f64> {
+    if encoded.is_empty() || shape.is_empty() {
+        return Vec::new();
+    }
+    let mut samples = Vec::with_capacity(encoded.len());
+    let mut elevation = 0.0;
+    for delta in encoded {
+        elevation += *delta as f64 * ELEVATION_DELTA_PRECISION;
+        samples.push(elevation);
+    }
+    let offset = mean_elevation - samples.iter().sum::<f64>() / samples.len() as f64;
+    for sample in &mut samples {
+        *sample += offset;
+    }
+
+    let distances = cumulative_distances(shape);
+    let length = *distances.last().unwrap_or(&0.0);
+    if samples.len() == 1 || length <= 0.0 {
+        return vec![samples[0]; shape.len()];
+    }
+    let interval = length / (samples.len() - 1) as f64;
+    distances
+        .iter()
+        .map(|distance| {
+            let position = distance / interval;
+            let index = (position.floor() as usize).min(samples.len() - 2);
+            let fraction = (position - index as f64).clamp(0.0, 1.0);
+            samples[index] + (samples[index + 1] - samples[index]) * fraction
+        })
+        .collect()
+}
+
+fn cumulative_distances(shape: &[LatLng]) -> Vec<f64> {
+    let mut distances = Vec::with_capacity(shape.len());
+    let mut total = 0.0;
+    distances.push(total);
+    for segment in shape.windows(2) {
+        total += distance_meters(&segment[0], &segment[1]);
+        distances.push(total);
+    }
+    distances
+}
+
+/// An elevation profile along a path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElevationProfile {
+    /// Pairs of distance along the path and elevation, both in meters.
+    points: Vec<(f64, f64)>,
+    total_ascent: f64,
+    total_descent: f64,
+}
+
+impl ElevationProfile {
+    /// Builds a profile from a shape and the elevation at each of its points.
+    pub fn new(shape: &[LatLng], elevation: &[f64]) -> ElevationProfile {
+        let mut profile = ElevationProfile::default();
+        profile.extend(shape, elevation);
+        profile
+    }
+
+    /// Appends a shape and the elevation at each of its points to the end of the profile. The
+    /// first point of `shape` is assumed to be the last point of the profile so far.
+    pub fn extend(&mut self, shape: &[LatLng], elevation: &[f64]) {
+        let start_distance = self.length();
+        let distances = cumulative_distances(shape);
+        for (distance, elevation) in distances.iter().zip(elevation) {
+            if let Some((_, previous)) = self.points.last() {
+                let change = elevation - previous;
+                if change > 0.0 {
+                    self.total_ascent += change;
+                } else {
+                    self.total_descent -= change;
+                }
+            }
+            self.points.push((start_distance + distance, *elevation));
+        }
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// Length of the profile in meters.
+    pub fn length(&self) -> f64 {
+        self.points
+            .last()
+            .map(|(distance, _)| *distance)
+            .unwrap_or(0.0)
+    }
+
+    /// Total elevation gained along the profile in meters.
+    pub fn total_ascent(&self) -> f64 {
+        self.total_ascent
+    }
+
+    /// Total elevation lost along the profile in meters.
+    pub fn total_descent(&self) -> f64 {
+        self.total_descent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_edge_elevation() {
+        assert_eq!(decode_mean_elevation(NO_MEAN_ELEVATION), None);
+        assert_eq!(decode_mean_elevation(0), Some(-500.0));
+        assert_eq!(decode_mean_elevation(305), Some(110.0));
+        assert_eq!(encode_mean_elevation(110.4), 305);
+
+        // Samples at 100, 104, 102.5 and 113.5 meters: a mean of 105 and deltas in quarter meters.
+        let encoded = [0, 16, -6, 44];
+        let shape = [LatLng::new(47.6, -122.4), LatLng::new(47.6, -122.39)];
+        let elevation = decode_edge_elevation(&encoded, 105.0, &shape);
+        assert_eq!(elevation, vec![100.0, 113.5]);
+
+        // Points between samples are interpolated along the shape.
+        let middle = LatLng::new(47.6, -122.395);
+        let elevation = decode_edge_elevation(&encoded, 105.0, &[shape[0], middle, shape[1]]);
+        assert!((elevation[1] - 103.25).abs() < 0.01, "{:?}", elevation);
+
+        assert_eq!(
+            encode_edge_elevation(&[100.0, 104.0, 102.5, 113.5]),
+            Some(encoded.to_vec())
+        );
+        assert_eq!(encode_edge_elevation(&[0.0, 40.0]), None);
+        assert!(decode_edge_elevation(&[], 105.0, &shape).is_empty());
+    }
+}
@@ -1,51 +1,250 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+    thread,
+};
 
 use rstar::{
     primitives::{GeomWithData, Line},
-    RTree,
+    PointDistance, RTree,
 };
 use tracing::{debug, instrument, warn};
 
 use crate::{
     geomath::{lat_lng_to_cartesian, LatLng},
+    tiles::TileLevel,
     valhalla::{
         directed_edge::ValhallaDirectedEdge,
-        edge_info::ValhallaEdgeInfo,
         graph_id::{GraphEntityId, TileId},
-        HasEntityPointer, VEntity,
+        node_info::ValhallaNodeInfo,
+        VEntity,
     },
 };
 
-use super::InfernoTile;
+use super::{
+    admin::Admin,
+    attributes::AccessRestriction,
+    bins::bins_near,
+    complex_restriction::ComplexRestrictionRef,
+    edge_info::{EdgeInfoRef, EdgeName, InfernoEdgeInfo},
+    elevation::ElevationProfile,
+    lanes::TurnLaneMask,
+    pack::TilePack,
+    sign::Sign,
+    tile_ref::{AsTileRef, TileRef},
+    transit::{ServiceDay, TransitDeparture},
+};
+
+type EdgeRTree = RTree<GeomWithData<Line<[f64; 3]>, GraphEntityId<ValhallaDirectedEdge>>>;
+/// The directed edges of a tile derived from each OSM way.
+type WayEdges = HashMap<u64, Vec<GraphEntityId<ValhallaDirectedEdge>>>;
+
+/// How `InfernoTileGraph` finds the edges near a point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeSearch {
+    /// Index every edge shape segment in an R-tree when the graph is loaded. Queries are fast,
+    /// but loading a large graph takes a while.
+    #[default]
+    RTree,
+    /// Use the spatial bins stored in each tile, so loading doesn't need to build an index.
+    /// Queries check the shape of every edge in the bins near the point.
+    Bins,
+}
+
+/// Where a graph's tiles are read from.
+enum TileSource<'a> {
+    /// Tiles given to the graph up front.
+    Tiles(HashMap<TileId, TileRef<'a>>),
+    /// Tiles read in place from a pack when they're first queried.
+    Pack(&'a TilePack),
+}
+
+impl<'a> TileSource<'a> {
+    fn get(&self, tile_id: &TileId) -> Option<TileRef<'a>> {
+        match self {
+            TileSource::Tiles(tiles) => tiles.get(tile_id).copied(),
+            TileSource::Pack(pack) => pack.tile(tile_id),
+        }
+    }
+
+    fn tile_ids(&self) -> Vec<TileId> {
+        match self {
+            TileSource::Tiles(tiles) => tiles.keys().copied().collect(),
+            TileSource::Pack(pack) => pack.tile_ids().collect(),
+        }
+    }
+}
 
 pub struct InfernoTileGraph<'a> {
-    tiles: HashMap<TileId, InfernoTileLoaded<'a>>,
+    tiles: TileSource<'a>,
+    /// The indexes built for each tile, which are built when the graph is created, or when the
+    /// tile is first searched for a graph over a pack.
+    loaded: HashMap<TileId, OnceLock<InfernoTileLoaded>>,
+    /// Each tile's way ID index, which is kept apart from `loaded` so way lookups don't build
+    /// the tiles' search indexes.
+    way_edges: HashMap<TileId, OnceLock<WayEdges>>,
+    edge_search: EdgeSearch,
 }
 
 impl<'a> InfernoTileGraph<'a> {
+    /// Builds a graph over owned tiles, or archived tiles read in place such as
+    /// `archive::MappedTile`s.
+    pub fn new<T: AsTileRef>(tiles: &'a [T]) -> Self {
+        Self::with_edge_search(tiles, EdgeSearch::default())
+    }
+
+    #[instrument(skip(tiles))]
+    pub fn with_edge_search<T: AsTileRef>(tiles: &'a [T], edge_search: EdgeSearch) -> Self {
+        Self::with_jobs(tiles, edge_search, 1)
+    }
+
+    /// Builds a graph like `with_edge_search`, building the tiles' indexes on `jobs` threads.
+    /// The graph is the same whatever the number of threads.
     #[instrument(skip(tiles))]
-    pub fn new(tiles: &'a [InfernoTile]) -> Self {
-        let tiles: HashMap<TileId, &InfernoTile> = tiles
+    pub fn with_jobs<T: AsTileRef>(tiles: &'a [T], edge_search: EdgeSearch, jobs: usize) -> Self {
+        let tiles = TileSource::Tiles(
+            tiles
+                .iter()
+                .map(|tile| {
+                    let tile = tile.tile_ref();
+                    (tile.tile_id(), tile)
+                })
+                .collect(),
+        );
+        let tile_ids = tiles.tile_ids();
+        debug!("Loading {} tiles on {} threads...", tile_ids.len(), jobs);
+        let load = |tile_id: &TileId| {
+            let tile = tiles.get(tile_id).expect("Tile is in the source");
+            (
+                *tile_id,
+                Self::load_tile(tile, &tiles, edge_search),
+                index_way_ids(&tile),
+            )
+        };
+        let loaded: Vec<_> = if jobs <= 1 {
+            tile_ids.iter().map(load).collect()
+        } else {
+            // Each thread takes the next unclaimed tile until they're all loaded, so a few large
+            // tiles don't hold up the rest.
+            let next = AtomicUsize::new(0);
+            thread::scope(|scope| {
+                let workers: Vec<_> = (0..jobs)
+                    .map(|_| {
+                        scope.spawn(|| {
+                            let mut loaded = Vec::new();
+                            while let Some(tile_id) =
+                                tile_ids.get(next.fetch_add(1, Ordering::Relaxed))
+                            {
+                                loaded.push(load(tile_id));
+                            }
+                            loaded
+                        })
+                    })
+                    .collect();
+                workers
+                    .into_iter()
+                    .flat_map(|worker| worker.join().expect("Tile loading thread panicked"))
+                    .collect()
+            })
+        };
+        let mut way_edges = HashMap::with_capacity(loaded.len());
+        let loaded: HashMap<_, _> = loaded
             .into_iter()
-            .map(|tile| (tile.tile_id(), tile))
-            .collect();
-        debug!("Loading {} tiles...", tiles.len());
-        let loaded_tiles: HashMap<_, _> = tiles
-            .values()
-            .map(|tile| (tile.tile_id(), Self::load_tile(tile, &tiles)))
+            .map(|(tile_id, loaded, tile_way_edges)| {
+                way_edges.insert(tile_id, OnceLock::from(tile_way_edges));
+                (tile_id, OnceLock::from(loaded))
+            })
             .collect();
-        debug!("Loaded {} tiles", tiles.len());
+        debug!("Loaded {} tiles", loaded.len());
 
         Self {
-            tiles: loaded_tiles,
+            tiles,
+            loaded,
+            way_edges,
+            edge_search,
+        }
+    }
+
+    /// Builds a graph over a tile pack. Nothing is read from the pack's tiles until they're
+    /// queried, and each tile's edge search index is built the first time it's searched.
+    #[instrument(skip(pack))]
+    pub fn from_pack(pack: &'a TilePack, edge_search: EdgeSearch) -> Self {
+        let tiles = TileSource::Pack(pack);
+        let tile_ids = tiles.tile_ids();
+        let loaded = tile_ids
+            .iter()
+            .map(|tile_id| (*tile_id, OnceLock::new()))
+            .collect();
+        let way_edges = tile_ids
+            .iter()
+            .map(|tile_id| (*tile_id, OnceLock::new()))
+            .collect();
+        Self {
+            tiles,
+            loaded,
+            way_edges,
+            edge_search,
+        }
+    }
+
+    fn tile(&self, tile_id: &TileId) -> Option<TileRef<'a>> {
+        self.tiles.get(tile_id)
+    }
+
+    /// Returns a tile's indexes, building them if this is the first time they're needed.
+    fn loaded_tile(&self, tile_id: &TileId) -> Option<&InfernoTileLoaded> {
+        let loaded = self.loaded.get(tile_id)?;
+        Some(loaded.get_or_init(|| {
+            let tile = self.tile(tile_id).expect("Tile is in the source");
+            Self::load_tile(tile, &self.tiles, self.edge_search)
+        }))
+    }
+
+    /// Returns a tile's way ID index, building it if this is the first time it's needed.
+    fn tile_way_edges(&self, tile_id: &TileId) -> Option<&WayEdges> {
+        let way_edges = self.way_edges.get(tile_id)?;
+        Some(way_edges.get_or_init(|| {
+            let tile = self.tile(tile_id).expect("Tile is in the source");
+            index_way_ids(&tile)
+        }))
+    }
+
+    /// Returns the tiles that may have edges within `max_distance_meters` of `point`: the tiles
+    /// within that distance on each level, and their neighbors, since edges can leave their
+    /// tile.
+    fn tiles_near(&self, point: &LatLng, max_distance_meters: f64) -> Vec<TileId> {
+        let mut tile_ids = HashSet::new();
+        for level in TileLevel::ALL {
+            for tile_id in level.tiles_within_radius(point, max_distance_meters) {
+                tile_ids.extend(level.neighbors(&tile_id));
+                tile_ids.insert(tile_id);
+            }
         }
+        let mut tile_ids: Vec<_> = tile_ids
+            .into_iter()
+            .filter(|tile_id| self.loaded.contains_key(tile_id))
+            .collect();
+        tile_ids.sort_by_key(|tile_id| tile_id.id);
+        tile_ids
     }
 
     #[instrument(skip(tile, tiles))]
     fn load_tile(
-        tile: &'a InfernoTile,
-        tiles: &HashMap<TileId, &InfernoTile>,
-    ) -> InfernoTileLoaded<'a> {
+        tile: TileRef<'a>,
+        tiles: &TileSource<'a>,
+        edge_search: EdgeSearch,
+    ) -> InfernoTileLoaded {
+        let rtree = match edge_search {
+            EdgeSearch::RTree => Some(Self::build_rtree(&tile, tiles)),
+            EdgeSearch::Bins => None,
+        };
+        InfernoTileLoaded { rtree }
+    }
+
+    fn build_rtree(tile: &TileRef<'a>, tiles: &TileSource<'a>) -> EdgeRTree {
         let mut elements = Vec::new();
         for (edge_index, edge) in tile.directed_edges.iter().enumerate() {
             debug!("Trying edge index: {}", edge_index);
@@ -60,10 +259,21 @@ impl<'a> InfernoTileGraph<'a> {
             };
             let (start_position, end_position) =
                 if let Some(end_node) = end_node_tile.nodes.get(&end_node_idx) {
-                    let opposing_edge = &end_node.edges(&end_node_tile)[edge.opposing_edge_index()];
+                    let Some(opposing_edge) = end_node
+                        .edges(&end_node_tile)
+                        .get(edge.opposing_edge_index())
+                    else {
+                        warn!(
+                            "Tile {} missing opposing edge for edge {} in tile {}",
+                            end_node_tile_id,
+                            edge_index,
+                            tile.tile_id()
+                        );
+                        continue;
+                    };
                     let start_position =
                         if let Some(start_node) = tile.nodes.get(&opposing_edge.end_node()) {
-                            lat_lng_to_cartesian(&start_node.position(&tile))
+                            lat_lng_to_cartesian(&start_node.position(tile))
                         } else {
                             warn!(
                                 "Tile {} missing start node {} for edge {} in tile {}",
@@ -77,7 +287,6 @@ impl<'a> InfernoTileGraph<'a> {
                     let end_position = lat_lng_to_cartesian(&end_node.position(&end_node_tile));
                     (start_position, end_position)
                 } else {
-                    dbg!(tile.tile_id(), end_node_tile_id);
                     warn!(
                         "Tile {} missing end node {} for edge {} in tile {}",
                         end_node_tile_id,
@@ -87,15 +296,31 @@ impl<'a> InfernoTileGraph<'a> {
                     );
                     continue;
                 };
-            elements.push(GeomWithData::new(
-                Line::new(start_position, end_position),
-                GraphEntityId::from_tile_index(&tile.tile_id(), edge_index),
-            ));
+            let edge_id = GraphEntityId::from_tile_index(&tile.tile_id(), edge_index);
+            let shape = tile.edge_shape(&edge_id).unwrap_or_default();
+            if shape.len() < 2 {
+                debug!("Edge {} has no shape, indexing its end nodes", edge_index);
+                elements.push(GeomWithData::new(
+                    Line::new(start_position, end_position),
+                    edge_id,
+                ));
+                continue;
+            }
+            for segment in shape.windows(2) {
+                elements.push(GeomWithData::new(
+                    Line::new(
+                        lat_lng_to_cartesian(&segment[0]),
+                        lat_lng_to_cartesian(&segment[1]),
+                    ),
+                    edge_id,
+                ));
+            }
         }
-        let rtree = RTree::bulk_load(elements);
-        InfernoTileLoaded { tile, rtree }
+        RTree::bulk_load(elements)
     }
 
+    /// Returns up to `max_edges` directed edges within `max_distance_meters` of `point` with
+    /// their distances in meters, nearest first.
     #[instrument(skip(self))]
     pub fn edges_for_point(
         &self,
@@ -104,53 +329,437 @@ impl<'a> InfernoTileGraph<'a> {
         max_edges: usize,
     ) -> Vec<(GraphEntityId<ValhallaDirectedEdge>, f64)> {
         let mut edges = Vec::new();
-        for (_tile_id, tile) in &self.tiles {
-            edges.extend(tile.edges_for_point(point, max_distance_meters, max_edges))
+        match self.edge_search {
+            EdgeSearch::RTree => {
+                for tile_id in self.tiles_near(point, max_distance_meters) {
+                    let tile = self.loaded_tile(&tile_id).expect("Tile is in the graph");
+                    edges.extend(tile.edges_for_point(point, max_distance_meters, max_edges))
+                }
+            }
+            EdgeSearch::Bins => edges = self.edges_for_point_in_bins(point, max_distance_meters),
         }
-        for (edge, _dist) in &edges {
-            let edge = self.directed_edge(edge).unwrap();
-            let edge_info: GraphEntityId<ValhallaEdgeInfo> = edge.get_entity();
-            let edge_info = self.edge_info(&edge_info).unwrap();
-            dbg!(edge_info);
+        // Nearest first, with ties broken by ID so both search modes give the same edges.
+        edges.sort_by(|(a_edge, a), (b_edge, b)| {
+            a.total_cmp(b)
+                .then(a_edge.graph_entity_id.cmp(&b_edge.graph_entity_id))
+        });
+        edges.truncate(max_edges);
+        edges
+    }
+
+    fn edges_for_point_in_bins(
+        &self,
+        point: &LatLng,
+        max_distance_meters: f64,
+    ) -> Vec<(GraphEntityId<ValhallaDirectedEdge>, f64)> {
+        let target = lat_lng_to_cartesian(point);
+        let mut seen_edges = HashSet::new();
+        let mut edges = Vec::new();
+        for tile_id in self.tiles_near(point, max_distance_meters) {
+            let Some(tile) = self.tile(&tile_id) else {
+                continue;
+            };
+            let Some(level) = TileLevel::of(&tile_id) else {
+                continue;
+            };
+            for bin in bins_near(
+                &tile.base_lat_lng(),
+                level.tile_size(),
+                point,
+                max_distance_meters,
+            ) {
+                for edge in tile.edge_bins().bin(bin) {
+                    // Bins hold one edge of each pair, so check its opposing edge as well.
+                    for edge in [Some(edge), self.opposing_edge(&edge)]
+                        .into_iter()
+                        .flatten()
+                    {
+                        if !seen_edges.insert(edge) {
+                            continue;
+                        }
+                        let Some(distance) = self.edge_distance(&edge, &target) else {
+                            continue;
+                        };
+                        if distance <= max_distance_meters {
+                            edges.push((edge, distance));
+                        }
+                    }
+                }
+            }
         }
         edges
     }
 
-    pub(crate) fn directed_edge(
+    /// Returns the distance in meters from `target` (in cartesian coordinates) to the nearest
+    /// segment of an edge's shape, or `None` if the edge isn't loaded or has no shape.
+    fn edge_distance(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+        target: &[f64; 3],
+    ) -> Option<f64> {
+        let shape = self.tile(&edge.tile_id())?.edge_shape(edge)?;
+        shape
+            .windows(2)
+            .map(|segment| {
+                Line::new(
+                    lat_lng_to_cartesian(&segment[0]),
+                    lat_lng_to_cartesian(&segment[1]),
+                )
+                .distance_2(target)
+            })
+            .min_by(|a, b| a.total_cmp(b))
+            .map(f64::sqrt)
+    }
+
+    /// Returns the directed edge running the opposite way along the same road, if its tile is
+    /// loaded.
+    fn opposing_edge(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<GraphEntityId<ValhallaDirectedEdge>> {
+        let directed_edge = self.tile(&edge.tile_id())?.directed_edges.get(edge)?;
+        let end_node_id = directed_edge.end_node();
+        let end_node_tile = self.tile(&end_node_id.tile_id())?;
+        let end_node = end_node_tile.nodes.get(&end_node_id)?;
+        Some(GraphEntityId::from_tile_index(
+            &end_node_id.tile_id(),
+            end_node.data1.edge_index() + directed_edge.opposing_edge_index(),
+        ))
+    }
+
+    pub fn directed_edge(
         &'a self,
         index: &GraphEntityId<ValhallaDirectedEdge>,
     ) -> Result<VEntity<&'a ValhallaDirectedEdge>, anyhow::Error> {
         let tile_id = index.tile_id();
-        if let Some(tile) = self.tiles.get(&tile_id) {
-            Ok(tile
-                .tile
-                .directed_edges
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.directed_edges
                 .get(index)
-                .expect("Missing directed edge"))
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
         } else {
             Err(anyhow::anyhow!("Missing tile {}", tile_id))
         }
     }
 
-    pub(crate) fn edge_info(
+    pub fn edge_info(
         &'a self,
-        index: &GraphEntityId<ValhallaEdgeInfo>,
-    ) -> Result<&'a ValhallaEdgeInfo, anyhow::Error> {
+        index: &GraphEntityId<InfernoEdgeInfo>,
+    ) -> Result<EdgeInfoRef<'a>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.edge_info(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing edge info {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the OSM way ID of a directed edge.
+    pub fn way_id(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<u64, anyhow::Error> {
         let tile_id = index.tile_id();
-        if let Some(tile) = self.tiles.get(&tile_id) {
-            Ok(&tile.tile.edge_infos.get(index).expect("Missing edge info"))
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.way_id(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns every directed edge in the graph derived from the given OSM way, across all tiles
+    /// and hierarchy levels. For a graph over a pack, the first lookup reads the way IDs of every
+    /// tile in the pack, but doesn't build their edge search indexes.
+    pub fn edges_for_way_id(&self, way_id: u64) -> Vec<GraphEntityId<ValhallaDirectedEdge>> {
+        let mut edges = Vec::new();
+        for tile_id in self.way_edges.keys() {
+            let tile_way_edges = self.tile_way_edges(tile_id).expect("Tile is in the graph");
+            if let Some(way_edges) = tile_way_edges.get(&way_id) {
+                edges.extend(way_edges.iter().copied());
+            }
+        }
+        edges.sort_by_key(|edge| edge.graph_entity_id);
+        edges
+    }
+
+    /// Returns the shape of a directed edge in its direction of travel.
+    pub fn edge_shape(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<LatLng>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.edge_shape(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the elevation in meters at each point of a directed edge's shape, in its direction
+    /// of travel. The `Vec` is empty if the edge has no elevation data.
+    pub fn edge_elevation(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<f64>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.edge_elevation(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Builds the elevation profile of a route made of consecutive directed edges.
+    pub fn route_elevation_profile(
+        &self,
+        edges: &[GraphEntityId<ValhallaDirectedEdge>],
+    ) -> Result<ElevationProfile, anyhow::Error> {
+        let mut profile = ElevationProfile::default();
+        for edge in edges {
+            let shape = self.edge_shape(edge)?;
+            let elevation = self.edge_elevation(edge)?;
+            if elevation.len() != shape.len() {
+                return Err(anyhow::anyhow!("No elevation data for edge {}", edge));
+            }
+            profile.extend(&shape, &elevation);
+        }
+        Ok(profile)
+    }
+
+    /// Returns the complex restrictions that end on a directed edge.
+    pub fn complex_restrictions_ending_on(
+        &'a self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<ComplexRestrictionRef<'a>>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.complex_restrictions_ending_on(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the complex restrictions that start on a directed edge.
+    pub fn complex_restrictions_starting_on(
+        &'a self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<ComplexRestrictionRef<'a>>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.complex_restrictions_starting_on(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the speed in kph of a directed edge at `seconds_of_week` (seconds since midnight
+    /// on Sunday), using its predicted speeds if it has any and its default speed otherwise.
+    pub fn speed_at(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+        seconds_of_week: u32,
+    ) -> Result<f32, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.speed_at(index, seconds_of_week)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the general access restrictions on a directed edge.
+    pub fn access_restrictions(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<AccessRestriction>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.access_restrictions(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the turn lane masks at the end of a directed edge, from left to right.
+    pub fn turn_lanes(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<TurnLaneMask>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.turn_lanes(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the lanes on `from_edge`, numbered from 1 starting at the left, that lead to
+    /// `to_edge`. The `Vec` is empty if there is no lane connectivity between the edges.
+    pub fn lanes_to_edge(
+        &self,
+        from_edge: &GraphEntityId<ValhallaDirectedEdge>,
+        to_edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let from_way_id = self.way_id(from_edge)?;
+        let tile_id = to_edge.tile_id();
+        let Some(tile) = self.tile(&tile_id) else {
+            return Err(anyhow::anyhow!("Missing tile {}", tile_id));
+        };
+        let connectivity = tile
+            .lane_connectivity(to_edge)
+            .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", to_edge))?;
+        let mut lanes: Vec<u8> = connectivity
+            .iter()
+            .filter(|connectivity| connectivity.from_way_id() == from_way_id)
+            .flat_map(|connectivity| connectivity.from_lanes())
+            .collect();
+        lanes.sort_unstable();
+        lanes.dedup();
+        Ok(lanes)
+    }
+
+    /// Returns the departures leaving a transit stop node on `day` at or after `time` (seconds
+    /// from midnight), paired with their next departure time and sorted by it.
+    pub fn departures_from_stop(
+        &'a self,
+        index: &GraphEntityId<ValhallaNodeInfo>,
+        day: ServiceDay,
+        time: u32,
+    ) -> Result<Vec<(u32, TransitDeparture<'a>)>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.departures_from_stop(index, day, time)
+                .ok_or_else(|| anyhow::anyhow!("Missing node {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the administrative area a node is in.
+    pub fn admin_for_node(
+        &'a self,
+        index: &GraphEntityId<ValhallaNodeInfo>,
+    ) -> Result<Admin<'a>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.admin_for_node(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing node or admin for node {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the administrative area of the end node of the edge nearest to `point`, or `None`
+    /// if there are no edges within `max_distance_meters`.
+    pub fn admin_for_point(
+        &'a self,
+        point: &LatLng,
+        max_distance_meters: f64,
+    ) -> Result<Option<Admin<'a>>, anyhow::Error> {
+        let nearest = self.edges_for_point(point, max_distance_meters, 1);
+        let Some((edge, _distance)) = nearest.first() else {
+            return Ok(None);
+        };
+        let end_node = self.directed_edge(edge)?.end_node();
+        self.admin_for_node(&end_node).map(Some)
+    }
+
+    /// Returns whether a directed edge crosses into a different country.
+    pub fn edge_crosses_country(
+        &self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<bool, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.edge_crosses_country(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the edges of a route that cross a country border, in route order.
+    pub fn route_country_crossings(
+        &self,
+        edges: &[GraphEntityId<ValhallaDirectedEdge>],
+    ) -> Result<Vec<GraphEntityId<ValhallaDirectedEdge>>, anyhow::Error> {
+        let mut crossings = Vec::new();
+        for edge in edges {
+            if self.edge_crosses_country(edge)? {
+                crossings.push(*edge);
+            }
+        }
+        Ok(crossings)
+    }
+
+    /// Returns the exit and guide signs on a directed edge.
+    pub fn edge_signs(
+        &'a self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<Sign<'a>>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.edge_signs(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the signs on a node, such as junction names.
+    pub fn node_signs(
+        &'a self,
+        index: &GraphEntityId<ValhallaNodeInfo>,
+    ) -> Result<Vec<Sign<'a>>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.node_signs(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing node {}", index))
+        } else {
+            Err(anyhow::anyhow!("Missing tile {}", tile_id))
+        }
+    }
+
+    /// Returns the names of a directed edge, resolved from its tile's text list.
+    pub fn edge_names(
+        &'a self,
+        index: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Result<Vec<EdgeName<'a>>, anyhow::Error> {
+        let tile_id = index.tile_id();
+        if let Some(tile) = self.tile(&tile_id) {
+            tile.edge_names(index)
+                .ok_or_else(|| anyhow::anyhow!("Missing directed edge {}", index))
         } else {
             Err(anyhow::anyhow!("Missing tile {}", tile_id))
         }
     }
 }
 
-pub struct InfernoTileLoaded<'a> {
-    tile: &'a InfernoTile,
-    rtree: RTree<GeomWithData<Line<[f64; 3]>, GraphEntityId<ValhallaDirectedEdge>>>,
+pub struct InfernoTileLoaded {
+    rtree: Option<EdgeRTree>,
+}
+
+/// Indexes the directed edges of a tile by the OSM way they're derived from.
+fn index_way_ids(tile: &TileRef<'_>) -> WayEdges {
+    let mut way_edges: WayEdges = HashMap::new();
+    for edge_index in 0..tile.directed_edges.len() {
+        let edge_id = GraphEntityId::from_tile_index(&tile.tile_id(), edge_index);
+        if let Some(way_id) = tile.way_id(&edge_id) {
+            way_edges.entry(way_id).or_default().push(edge_id);
+        }
+    }
+    way_edges
 }
 
-impl<'a> InfernoTileLoaded<'a> {
+impl InfernoTileLoaded {
     fn edges_for_point(
         &self,
         point: &LatLng,
@@ -158,15 +767,22 @@ impl<'a> InfernoTileLoaded<'a> {
         max_edges: usize,
     ) -> Vec<(GraphEntityId<ValhallaDirectedEdge>, f64)> {
         let mut edges = Vec::new();
-        for (edge, distance_sq) in self
-            .rtree
-            .nearest_neighbor_iter_with_distance_2(&lat_lng_to_cartesian(point))
+        let Some(rtree) = &self.rtree else {
+            return edges;
+        };
+        // Edges are indexed once per shape segment, so only keep the nearest segment of each.
+        let mut seen_edges = HashSet::new();
+        for (edge, distance_sq) in
+            rtree.nearest_neighbor_iter_with_distance_2(&lat_lng_to_cartesian(point))
         {
             let distance = distance_sq.sqrt();
             if distance > max_distance_meters {
                 break;
             }
-            edges.push((edge.data.clone(), distance));
+            if !seen_edges.insert(edge.data) {
+                continue;
+            }
+            edges.push((edge.data, distance));
             if edges.len() >= max_edges {
                 break;
             }
@@ -174,3 +790,236 @@ impl<'a> InfernoTileLoaded<'a> {
         edges
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use zerocopy::FromZeros;
+
+    use crate::{
+        inferno::{
+            builder::{EdgeAttributes, GraphBuilder, SyntheticGraph},
+            pack::{write_tile_pack, TilePack},
+            test_tiles::{edit_tile, sample_tile_bytes},
+            text_list::TextList,
+            InfernoTile,
+        },
+        valhalla::admin::ValhallaAdmin,
+    };
+
+    use super::*;
+
+    /// A ladder of streets along the boundary between two local tiles, at longitude -122.25.
+    fn ladder() -> SyntheticGraph {
+        let mut builder = GraphBuilder::new();
+        let mut previous = None;
+        for rung in 0..6 {
+            let lat = 47.6 + rung as f64 * 0.0005;
+            let west = builder.add_node(LatLng::new(lat, -122.2505));
+            let east = builder.add_node(LatLng::new(lat, -122.2495));
+            let way_id = 100 + rung as u64 % 2;
+            builder.add_edge(
+                west,
+                east,
+                EdgeAttributes {
+                    way_id,
+                    ..Default::default()
+                },
+            );
+            if let Some((previous_west, previous_east)) = previous {
+                builder.add_edge(previous_west, west, EdgeAttributes::default());
+                builder.add_edge(previous_east, east, EdgeAttributes::default());
+            }
+            previous = Some((west, east));
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn search_modes_find_the_same_edges() {
+        let graph = ladder();
+        assert_eq!(graph.tiles().len(), 2);
+        let rtree = InfernoTileGraph::with_edge_search(graph.tiles(), EdgeSearch::RTree);
+        let bins = InfernoTileGraph::with_edge_search(graph.tiles(), EdgeSearch::Bins);
+        let point = LatLng::new(47.6011, -122.2501);
+        for max_edges in [1, 3, 100] {
+            let edges = rtree.edges_for_point(&point, 200.0, max_edges);
+            assert_eq!(edges, bins.edges_for_point(&point, 200.0, max_edges));
+            assert!(edges.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            assert_eq!(edges.len(), max_edges.min(32));
+        }
+    }
+
+    #[test]
+    fn queries_dont_depend_on_jobs() {
+        let graph = ladder();
+        let points = [
+            LatLng::new(47.6011, -122.2501),
+            LatLng::new(47.6, -122.2505),
+            LatLng::new(47.6025, -122.2495),
+        ];
+        for edge_search in [EdgeSearch::RTree, EdgeSearch::Bins] {
+            let serial = InfernoTileGraph::with_jobs(graph.tiles(), edge_search, 1);
+            let parallel = InfernoTileGraph::with_jobs(graph.tiles(), edge_search, 4);
+            for point in &points {
+                let edges = serial.edges_for_point(point, 200.0, 10);
+                assert!(!edges.is_empty());
+                assert_eq!(edges, parallel.edges_for_point(point, 200.0, 10));
+            }
+            for way_id in [100, 101, 0] {
+                let edges = serial.edges_for_way_id(way_id);
+                assert!(!edges.is_empty());
+                assert_eq!(edges, parallel.edges_for_way_id(way_id));
+            }
+        }
+    }
+
+    #[test]
+    fn finds_admins_and_country_crossings() {
+        // A road from Washington into British Columbia, crossing a border between b and c, all
+        // in one tile.
+        let mut builder = GraphBuilder::new();
+        let a = builder.add_node(LatLng::new(48.86, -122.6));
+        let b = builder.add_node(LatLng::new(48.865, -122.6));
+        let c = builder.add_node(LatLng::new(48.875, -122.6));
+        let d = builder.add_node(LatLng::new(48.88, -122.6));
+        let ab = builder.add_edge(a, b, EdgeAttributes::default());
+        let bc = builder.add_edge(b, c, EdgeAttributes::default());
+        let cd = builder.add_edge(c, d, EdgeAttributes::default());
+        let graph = builder.build().unwrap();
+        assert_eq!(graph.tiles().len(), 1);
+        let node_ids = [a, b, c, d].map(|node| graph.node_id(node));
+        let [ab, bc, cd] = [ab, bc, cd].map(|edge| graph.edge_ids(edge));
+
+        let mut text_list = graph.tiles()[0].text_list.as_bytes().to_vec();
+        let mut admins = Vec::new();
+        for (country, state, country_iso, state_iso) in [
+            ("United States", "Washington", b"US", b"WA\0"),
+            ("Canada", "British Columbia", b"CA", b"BC\0"),
+        ] {
+            let mut admin = ValhallaAdmin::new_zeroed();
+            admin.country_offset = text_list.len() as u32;
+            text_list.extend_from_slice(country.as_bytes());
+            text_list.push(0);
+            admin.state_offset = text_list.len() as u32;
+            text_list.extend_from_slice(state.as_bytes());
+            text_list.push(0);
+            admin.country_iso = *country_iso;
+            admin.state_iso = *state_iso;
+            admins.push(admin);
+        }
+        let tile = edit_tile(graph.into_tiles().remove(0), |tile| {
+            tile.text_list = TextList::new(text_list);
+            for admin in admins {
+                tile.admins.push(admin);
+            }
+            for node in &node_ids[2..] {
+                tile.nodes.as_mut_slice()[node.graph_index()]
+                    .data1
+                    .set_admin_index(1);
+            }
+            for edge in [bc.0, bc.1] {
+                tile.directed_edges.as_mut_slice()[edge.graph_index()]
+                    .restrictions1
+                    .set_country_crossing(true);
+            }
+        });
+        let tiles = [tile];
+        let graph = InfernoTileGraph::new(&tiles);
+
+        let washington = graph.admin_for_node(&node_ids[0]).unwrap();
+        assert_eq!(washington.country_name(), "United States");
+        assert_eq!(washington.state_name(), "Washington");
+        assert_eq!(washington.country_iso(), "US");
+        assert_eq!(washington.state_iso(), "WA");
+        let british_columbia = graph.admin_for_node(&node_ids[3]).unwrap();
+        assert_eq!(british_columbia.country_iso(), "CA");
+        assert_eq!(british_columbia.state_name(), "British Columbia");
+        let missing = GraphEntityId::from_tile_index(&tiles[0].tile_id(), 4);
+        assert!(graph.admin_for_node(&missing).is_err());
+
+        // Both ends of the edges nearest these points are in the same admin.
+        let near_ab = LatLng::new(48.8625, -122.6001);
+        assert_eq!(
+            graph.admin_for_point(&near_ab, 50.0).unwrap(),
+            Some(washington)
+        );
+        let near_cd = LatLng::new(48.8775, -122.5999);
+        assert_eq!(
+            graph.admin_for_point(&near_cd, 50.0).unwrap(),
+            Some(british_columbia)
+        );
+        let far_away = LatLng::new(48.8, -122.6);
+        assert_eq!(graph.admin_for_point(&far_away, 50.0).unwrap(), None);
+
+        assert!(graph.edge_crosses_country(&bc.0).unwrap());
+        assert!(graph.edge_crosses_country(&bc.1).unwrap());
+        assert!(!graph.edge_crosses_country(&ab.0).unwrap());
+        assert_eq!(
+            graph.route_country_crossings(&[ab.0, bc.0, cd.0]).unwrap(),
+            vec![bc.0]
+        );
+        let missing = GraphEntityId::from_tile_index(&tiles[0].tile_id(), 6);
+        assert!(graph.edge_crosses_country(&missing).is_err());
+    }
+
+    #[test]
+    fn elevation_follows_the_direction_of_travel() {
+        let mut builder = GraphBuilder::new();
+        let start = builder.add_node(LatLng::new(47.6, -122.4));
+        let end = builder.add_node(LatLng::new(47.6, -122.39));
+        let hill = builder.add_edge(
+            start,
+            end,
+            EdgeAttributes {
+                shape: vec![LatLng::new(47.6, -122.395)],
+                // The mean, 106 meters, is a multiple of the 2 meter step it's stored in.
+                elevation: vec![101.0, 105.0, 103.5, 114.5],
+                ..Default::default()
+            },
+        );
+        let built = builder.build().unwrap();
+        let (forward, reverse) = built.edge_ids(hill);
+        let graph = InfernoTileGraph::new(built.tiles());
+
+        let elevation = graph.edge_elevation(&forward).unwrap();
+        assert_eq!(elevation.len(), 3);
+        assert_eq!((elevation[0], elevation[2]), (101.0, 114.5));
+        let mut reversed = graph.edge_elevation(&reverse).unwrap();
+        reversed.reverse();
+        assert_eq!(reversed, elevation);
+
+        let profile = graph.route_elevation_profile(&[forward]).unwrap();
+        assert_eq!(
+            (profile.total_ascent(), profile.total_descent()),
+            (13.5, 0.0)
+        );
+        let profile = graph.route_elevation_profile(&[forward, reverse]).unwrap();
+        assert_eq!(
+            (profile.total_ascent(), profile.total_descent()),
+            (13.5, 13.5)
+        );
+        assert_eq!(profile.points().len(), 6);
+    }
+
+    #[test]
+    fn way_lookups_on_packs_dont_build_search_indexes() {
+        let path = std::env::temp_dir().join(format!("inferno-way-pack-{}", std::process::id()));
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        write_tile_pack(&path, &[tile]).unwrap();
+        // SAFETY: The pack isn't modified while it's mapped.
+        let pack = unsafe { TilePack::open(&path) }.unwrap();
+
+        let graph = InfernoTileGraph::from_pack(&pack, EdgeSearch::RTree);
+        assert_eq!(graph.edges_for_way_id(123_456).len(), 2);
+        assert!(graph.edges_for_way_id(1).is_empty());
+        assert!(graph.loaded.values().all(|loaded| loaded.get().is_none()));
+
+        // Searching near the edge builds its tile's R-tree.
+        let point = LatLng::new(47.605, -122.4);
+        assert_eq!(graph.edges_for_point(&point, 100.0, 10).len(), 2);
+        assert!(graph.loaded.values().all(|loaded| loaded.get().is_some()));
+        fs::remove_file(&path).unwrap();
+    }
+}
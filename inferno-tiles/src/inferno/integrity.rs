@@ -0,0 +1,197 @@
+//! Validation of tile archives before they're read in place. An archive is checked against its
+//! checksum, then checked by rkyv's validator, then checked for the invariants that queries rely
+//! on to index into the tile's records, so a corrupt archive is rejected when it's loaded rather
+//! than causing a panic in a query.
+//!
+//! Links between tiles can't be checked one tile at a time, so `check_tile_links` checks a tile
+//! against the tiles its edges lead into, which only needs those tiles in memory.
+
+use std::{collections::BTreeSet, fmt::Display, path::PathBuf};
+
+use rkyv::rancor;
+
+use crate::valhalla::graph_id::TileId;
+
+use super::{
+    tile_ref::{AsTileRef, TileRef},
+    ArchivedInfernoTile,
+};
+
+/// How a tile archive is corrupt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    /// The archive is too short to have a header, or its header is missing, as happens when a
+    /// file is truncated or overwritten.
+    MissingHeader,
+    /// The archive's contents don't match the checksum written with it.
+    ChecksumMismatch { expected: u32, actual: u32 },
+    /// The archive isn't a valid rkyv archive of a tile.
+    InvalidArchive(String),
+    /// The tile's records are inconsistent, e.g. a node's edges run past the tile's last edge.
+    Inconsistent(String),
+}
+
+impl Display for Corruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Corruption::MissingHeader => write!(f, "missing header"),
+            Corruption::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch, expected {:08x} and found {:08x}",
+                expected, actual
+            ),
+            Corruption::InvalidArchive(err) => write!(f, "invalid archive: {}", err),
+            Corruption::Inconsistent(err) => write!(f, "inconsistent tile: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Corruption {}
+
+/// Error returned (wrapped in an `anyhow::Error`) when a tile archive fails validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptTileError {
+    /// The corrupt tile, or `None` if it's a tile archive whose path doesn't say which tile it
+    /// holds.
+    pub tile_id: Option<TileId>,
+    /// The tile archive or pack the tile was read from.
+    pub path: PathBuf,
+    pub corruption: Corruption,
+}
+
+impl Display for CorruptTileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.tile_id {
+            Some(tile_id) => write!(
+                f,
+                "Tile {} in {:?} is corrupt: {}",
+                tile_id, self.path, self.corruption
+            ),
+            None => write!(f, "Tile in {:?} is corrupt: {}", self.path, self.corruption),
+        }
+    }
+}
+
+impl std::error::Error for CorruptTileError {}
+
+/// Validates a tile archive, which must be aligned for rkyv, against its CRC-32 and returns the
+/// archived tile.
+pub(crate) fn check_archive(
+    bytes: &[u8],
+    checksum: u32,
+) -> Result<&ArchivedInfernoTile, Corruption> {
+    let actual = crc32fast::hash(bytes);
+    if actual != checksum {
+        return Err(Corruption::ChecksumMismatch {
+            expected: checksum,
+            actual,
+        });
+    }
+    let tile = rkyv::access::<ArchivedInfernoTile, rancor::Error>(bytes)
+        .map_err(|err| Corruption::InvalidArchive(err.to_string()))?;
+    tile.tile_ref()
+        .check_consistency()
+        .map_err(Corruption::Inconsistent)?;
+    Ok(tile)
+}
+
+/// Returns the IDs of the tiles that `tile`'s edges end in, including `tile` itself if any of
+/// its edges stay in it.
+pub fn linked_tile_ids(tile: &TileRef<'_>) -> BTreeSet<TileId> {
+    tile.directed_edges
+        .iter()
+        .map(|edge| edge.end_node().tile_id())
+        .collect()
+}
+
+/// Checks that every edge in `tile` links up with the tiles it leads into: its end node exists,
+/// the end node has the edge's opposing edge, and the opposing edge leads back to a node in
+/// `tile`. `neighbor` looks up the tiles in `linked_tile_ids`, and may return `None` for tiles
+/// missing from the dataset. Returns a description of each broken link.
+pub fn check_tile_links<'a>(
+    tile: &TileRef<'a>,
+    neighbor: impl Fn(&TileId) -> Option<TileRef<'a>>,
+) -> Vec<String> {
+    let tile_id = tile.tile_id();
+    let mut problems = Vec::new();
+    for (edge_index, edge) in tile.directed_edges.iter().enumerate() {
+        let end_node_id = edge.end_node();
+        let end_tile_id = end_node_id.tile_id();
+        let end_tile = if end_tile_id == tile_id {
+            Some(*tile)
+        } else {
+            neighbor(&end_tile_id)
+        };
+        let Some(end_tile) = end_tile else {
+            problems.push(format!(
+                "Edge {} in tile {} ends in missing tile {}",
+                edge_index, tile_id, end_tile_id
+            ));
+            continue;
+        };
+        let Some(end_node) = end_tile.nodes.get(&end_node_id) else {
+            problems.push(format!(
+                "Edge {} in tile {} ends at missing node {}",
+                edge_index, tile_id, end_node_id
+            ));
+            continue;
+        };
+        let Some(opposing_edge) = end_node.edges(&end_tile).get(edge.opposing_edge_index()) else {
+            problems.push(format!(
+                "Edge {} in tile {} has no opposing edge at node {}",
+                edge_index, tile_id, end_node_id
+            ));
+            continue;
+        };
+        let start_node_id = opposing_edge.end_node();
+        if start_node_id.tile_id() != tile_id || tile.nodes.get(&start_node_id).is_none() {
+            problems.push(format!(
+                "Edge {} in tile {} has an opposing edge that ends at node {} instead of a node in \
+                 its tile",
+                edge_index, tile_id, start_node_id
+            ));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inferno::{test_tiles::sample_tile_bytes, tile_ref::AsTileRef, InfernoTile},
+        valhalla::directed_edge::ValhallaDirectedEdgeRestrictions1,
+    };
+
+    use super::{check_tile_links, linked_tile_ids};
+
+    #[test]
+    fn checks_tile_links() {
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let tile = tile.tile_ref();
+        assert_eq!(
+            linked_tile_ids(&tile).into_iter().collect::<Vec<_>>(),
+            vec![tile.tile_id()]
+        );
+        assert!(check_tile_links(&tile, |_| None).is_empty());
+
+        // Point the first edge at a node past the end of the tile, which also breaks the second
+        // edge, whose opposing edge is the first.
+        let mut valhalla_bytes = sample_tile_bytes();
+        let restrictions1 = 272 + 2 * 32;
+        let bits = u64::from_le_bytes(
+            valhalla_bytes[restrictions1..restrictions1 + 8]
+                .try_into()
+                .unwrap(),
+        );
+        let restrictions = ValhallaDirectedEdgeRestrictions1::from_bits(bits);
+        let bits = restrictions
+            .with_end_node(restrictions.end_node() | (5 << 25))
+            .into_bits();
+        valhalla_bytes[restrictions1..restrictions1 + 8].copy_from_slice(&bits.to_le_bytes());
+        let tile = InfernoTile::from_valhalla(&valhalla_bytes).unwrap();
+        let problems = check_tile_links(&tile.tile_ref(), |_| None);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("missing node"), "{:?}", problems);
+        assert!(problems[1].contains("opposing edge"), "{:?}", problems);
+    }
+}
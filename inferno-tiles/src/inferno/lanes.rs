@@ -0,0 +1,213 @@
+use crate::valhalla::{lane_connectivity::ValhallaLaneConnectivity, turn_lanes::ValhallaTurnLanes};
+
+use super::text_list::TextListRef;
+
+/// Maximum number of lanes in a lane connectivity record.
+const MAX_LANES: usize = 16;
+
+/// Directions a lane allows, as a bit mask of Valhalla's `kTurnLane*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TurnLaneMask(u16);
+
+impl TurnLaneMask {
+    pub const EMPTY: TurnLaneMask = TurnLaneMask(0);
+    pub const NONE: TurnLaneMask = TurnLaneMask(1 << 0);
+    pub const THROUGH: TurnLaneMask = TurnLaneMask(1 << 1);
+    pub const SHARP_LEFT: TurnLaneMask = TurnLaneMask(1 << 2);
+    pub const LEFT: TurnLaneMask = TurnLaneMask(1 << 3);
+    pub const SLIGHT_LEFT: TurnLaneMask = TurnLaneMask(1 << 4);
+    pub const SLIGHT_RIGHT: TurnLaneMask = TurnLaneMask(1 << 5);
+    pub const RIGHT: TurnLaneMask = TurnLaneMask(1 << 6);
+    pub const SHARP_RIGHT: TurnLaneMask = TurnLaneMask(1 << 7);
+    pub const REVERSE: TurnLaneMask = TurnLaneMask(1 << 8);
+    pub const MERGE_TO_LEFT: TurnLaneMask = TurnLaneMask(1 << 9);
+    pub const MERGE_TO_RIGHT: TurnLaneMask = TurnLaneMask(1 << 10);
+
+    pub fn from_bits(bits: u16) -> TurnLaneMask {
+        TurnLaneMask(bits)
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn contains(&self, other: TurnLaneMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Parses Valhalla's turn lane text: one numeric mask per lane, left to right, separated by `|`.
+pub(crate) fn turn_lanes_at(
+    turn_lanes: &[ValhallaTurnLanes],
+    edge_index: usize,
+    text_list: TextListRef<'_>,
+) -> Vec<TurnLaneMask> {
+    let start = turn_lanes.partition_point(|lanes| lanes.edge_index() < edge_index);
+    let Some(lanes) = turn_lanes
+        .get(start)
+        .filter(|lanes| lanes.edge_index() == edge_index)
+    else {
+        return Vec::new();
+    };
+    let Some(text) = text_list.get(lanes.text_offset()) else {
+        tracing::warn!(
+            "Invalid text list offset {} for turn lanes",
+            lanes.text_offset()
+        );
+        return Vec::new();
+    };
+    text.split('|')
+        .map(|lane| TurnLaneMask(lane.trim().parse().unwrap_or(0)))
+        .collect()
+}
+
+/// Lane connectivity between a from edge (identified by OSM way ID) and a to edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaneConnectivity {
+    from_way_id: u64,
+    lanes: Vec<(u8, u8)>,
+}
+
+impl LaneConnectivity {
+    pub(crate) fn new(connectivity: &ValhallaLaneConnectivity) -> LaneConnectivity {
+        let lanes = (0..MAX_LANES)
+            .map(|lane| {
+                (
+                    ((connectivity.from_lanes >> (lane * 4)) & 0xf) as u8,
+                    ((connectivity.to_lanes >> (lane * 4)) & 0xf) as u8,
+                )
+            })
+            .take_while(|(from, to)| *from != 0 && *to != 0)
+            .collect();
+        LaneConnectivity {
+            from_way_id: connectivity.from_way_id,
+            lanes,
+        }
+    }
+
+    /// OSM way ID of the from edge.
+    pub fn from_way_id(&self) -> u64 {
+        self.from_way_id
+    }
+
+    /// Pairs of connected lanes as `(from_lane, to_lane)`, numbered from 1.
+    pub fn lanes(&self) -> &[(u8, u8)] {
+        &self.lanes
+    }
+
+    /// Lanes on the from edge, numbered from 1, that lead to the to edge.
+    pub fn from_lanes(&self) -> Vec<u8> {
+        let mut lanes: Vec<u8> = self.lanes.iter().map(|(from, _)| *from).collect();
+        lanes.sort_unstable();
+        lanes.dedup();
+        lanes
+    }
+}
+
+/// Returns the lane connectivity records for the to edge at `edge_index`.
+pub(crate) fn lane_connectivity_at(
+    connectivity: &[ValhallaLaneConnectivity],
+    edge_index: usize,
+) -> Vec<LaneConnectivity> {
+    let start = connectivity.partition_point(|record| record.to.edge_index() < edge_index);
+    connectivity[start..]
+        .iter()
+        .take_while(|record| record.to.edge_index() == edge_index)
+        .map(LaneConnectivity::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inferno::text_list::TextList, valhalla::lane_connectivity::ValhallaLaneConnectivityTo,
+    };
+
+    use super::*;
+
+    fn connectivity(to_edge: usize, from_lanes: u64, to_lanes: u64) -> ValhallaLaneConnectivity {
+        ValhallaLaneConnectivity {
+            to: ValhallaLaneConnectivityTo::new().with_edge_index(to_edge),
+            from_way_id: 42,
+            to_lanes,
+            from_lanes,
+        }
+    }
+
+    #[test]
+    fn parses_turn_lane_text() {
+        let text_list = TextList::new(b"\08|2|2|96\0|1|\0".to_vec());
+        let turn_lanes = [
+            ValhallaTurnLanes::new()
+                .with_edge_index(1)
+                .with_text_offset(1),
+            ValhallaTurnLanes::new()
+                .with_edge_index(3)
+                .with_text_offset(10),
+            ValhallaTurnLanes::new()
+                .with_edge_index(4)
+                .with_text_offset(100),
+        ];
+        let lanes = |edge_index| turn_lanes_at(&turn_lanes, edge_index, text_list.borrowed());
+
+        // Lanes are listed left to right.
+        let left_through_right = lanes(1);
+        assert_eq!(left_through_right.len(), 4);
+        assert_eq!(left_through_right[0], TurnLaneMask::LEFT);
+        assert_eq!(left_through_right[1], TurnLaneMask::THROUGH);
+        assert!(left_through_right[3].contains(TurnLaneMask::SLIGHT_RIGHT));
+        assert!(left_through_right[3].contains(TurnLaneMask::RIGHT));
+        assert!(!left_through_right[3].contains(TurnLaneMask::THROUGH));
+
+        // Lanes without a mask are empty.
+        assert_eq!(
+            lanes(3),
+            vec![TurnLaneMask::EMPTY, TurnLaneMask::NONE, TurnLaneMask::EMPTY]
+        );
+        assert!(lanes(2).is_empty());
+        assert!(lanes(4).is_empty());
+    }
+
+    #[test]
+    fn unpacks_lane_connectivity() {
+        // Each lane is a 4 bit lane number, starting from the lowest bits, so from lanes 1, 2 and
+        // 3 all lead to lane 1.
+        let merge = LaneConnectivity::new(&connectivity(2, 0x321, 0x111));
+        assert_eq!(merge.from_way_id(), 42);
+        assert_eq!(merge.lanes(), [(1, 1), (2, 1), (3, 1)]);
+        assert_eq!(merge.from_lanes(), vec![1, 2, 3]);
+
+        // A zero lane number ends the list.
+        assert!(LaneConnectivity::new(&connectivity(2, 0, 0x1))
+            .lanes()
+            .is_empty());
+        let stopped = LaneConnectivity::new(&connectivity(2, 0x302, 0x111));
+        assert_eq!(stopped.lanes(), [(2, 1)]);
+
+        // All 16 lanes are used.
+        let widest = LaneConnectivity::new(&connectivity(
+            2,
+            0xfedc_ba98_7654_3211,
+            0x1111_1111_1111_1112,
+        ));
+        assert_eq!(widest.lanes().len(), MAX_LANES);
+        assert_eq!(widest.lanes()[0], (1, 2));
+        assert_eq!(widest.lanes()[15], (15, 1));
+        assert_eq!(widest.from_lanes(), (1..=15).collect::<Vec<_>>());
+
+        let records = [
+            connectivity(1, 0x1, 0x1),
+            connectivity(2, 0x1, 0x2),
+            connectivity(2, 0x2, 0x3),
+            connectivity(4, 0x1, 0x1),
+        ];
+        let to_two = lane_connectivity_at(&records, 2);
+        assert_eq!(to_two.len(), 2);
+        assert_eq!(to_two[1].lanes(), [(2, 3)]);
+        assert!(lane_connectivity_at(&records, 3).is_empty());
+    }
+}
@@ -1,22 +1,60 @@
+pub mod admin;
+pub mod archive;
+pub mod attributes;
+pub mod bins;
+pub mod builder;
 pub mod checked_vec;
+pub mod complex_restriction;
+pub mod edge_info;
+pub mod elevation;
 pub mod graph;
+pub mod integrity;
+pub mod lanes;
+pub mod pack;
+pub mod predicted_speeds;
+pub mod sign;
+pub mod tarball;
+#[cfg(test)]
+pub(crate) mod test_tiles;
+pub mod text_list;
+pub mod tile_dir;
+pub mod tile_ref;
+pub mod transit;
+mod valhalla_writer;
+pub mod version;
+pub mod zero_copy;
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
+use admin::Admin;
+use attributes::AccessRestriction;
+use bins::{EdgeBins, EdgeBinsRef};
 use checked_vec::CheckedVec;
-use rkyv::Archive;
+use complex_restriction::{ComplexRestrictionRef, InfernoComplexRestriction};
+use edge_info::{EdgeName, InfernoEdgeInfo};
+use lanes::{LaneConnectivity, TurnLaneMask};
+use predicted_speeds::PredictedSpeeds;
+use rkyv::{Archive, Deserialize, Serialize};
+use sign::Sign;
+use text_list::TextList;
+use tile_ref::AsTileRef;
 use tracing::{debug, instrument, trace, warn};
-use zerocopy::FromBytes;
+use transit::{
+    ServiceDay, TransitDeparture, TransitRoute, TransitSchedule, TransitStop, TransitTransfer,
+};
+use version::TileVersion;
+use zerocopy::{FromBytes, Immutable, KnownLayout};
 
 use crate::{
     geomath::LatLng,
+    tiles::TileLevel,
     valhalla::{
         access_restrictions::ValhallaAccessRestriction,
         admin::ValhallaAdmin,
         directed_edge::ValhallaDirectedEdge,
         directed_edge_ext::ValhallaDirectedEdgeExt,
-        edge_info::ValhallaEdgeInfo,
         graph_id::{GraphEntityId, TileId},
+        lane_connectivity::ValhallaLaneConnectivity,
         node_info::ValhallaNodeInfo,
         node_transition::ValhallaNodeTransition,
         sign::ValhallaSign,
@@ -26,18 +64,38 @@ use crate::{
         transit_schedule::ValhallaTransitSchedule,
         transit_stop::ValhallaTransitStop,
         transit_transfer::ValhallaTransitTransfer,
+        turn_lanes::ValhallaTurnLanes,
     },
 };
 
-#[derive(Clone, Debug, Archive)]
+#[derive(Clone, Debug, PartialEq, Archive, Serialize, Deserialize)]
 pub struct InfernoTile {
     tile_id: TileId,
     header: ValhallaTileHeader,
+    version: TileVersion,
     nodes: CheckedVec<ValhallaNodeInfo>,
     node_transitions: CheckedVec<ValhallaNodeTransition>,
     directed_edges: CheckedVec<ValhallaDirectedEdge>,
+    /// One extension per directed edge if the header's `has_ext_directededge` is set, else none.
+    directed_edge_exts: CheckedVec<ValhallaDirectedEdgeExt>,
     access_restrictions: CheckedVec<ValhallaAccessRestriction>,
-    edge_infos: CheckedVec<ValhallaEdgeInfo>,
+    edge_infos: CheckedVec<InfernoEdgeInfo>,
+    transit_departures: CheckedVec<ValhallaTransitDeparture>,
+    transit_stops: CheckedVec<ValhallaTransitStop>,
+    transit_routes: CheckedVec<ValhallaTransitRoute>,
+    transit_schedules: CheckedVec<ValhallaTransitSchedule>,
+    transit_transfers: CheckedVec<ValhallaTransitTransfer>,
+    signs: CheckedVec<ValhallaSign>,
+    turn_lanes: CheckedVec<ValhallaTurnLanes>,
+    admins: CheckedVec<ValhallaAdmin>,
+    edge_bins: EdgeBins,
+    /// Complex restrictions ending on edges in this tile.
+    complex_restrictions_forward: CheckedVec<InfernoComplexRestriction>,
+    /// Complex restrictions starting on edges in this tile.
+    complex_restrictions_reverse: CheckedVec<InfernoComplexRestriction>,
+    text_list: TextList,
+    lane_connectivity: CheckedVec<ValhallaLaneConnectivity>,
+    predicted_speeds: PredictedSpeeds,
 }
 
 const HEADER_SIZE: usize = size_of::<ValhallaTileHeader>();
@@ -46,14 +104,14 @@ const NODE_TRANSITION_SIZE: usize = size_of::<ValhallaNodeTransition>();
 const DIRECTED_EDGE_SIZE: usize = size_of::<ValhallaDirectedEdge>();
 const DIRECTED_EDGE_EXT_SIZE: usize = size_of::<ValhallaDirectedEdgeExt>();
 const ACCESS_RESTRICTION_SIZE: usize = size_of::<ValhallaAccessRestriction>();
-const TRANSIT_DEPARTURE_SIZE: usize = size_of::<ValhallaTransitDeparture>();
-const TRANSIT_STOP_SIZE: usize = size_of::<ValhallaTransitStop>();
-const TRANSIT_ROUTE_SIZE: usize = size_of::<ValhallaTransitRoute>();
-const TRANSIT_SCHEDULE_SIZE: usize = size_of::<ValhallaTransitSchedule>();
-const TRANSIT_TRANSFER_SIZE: usize = size_of::<ValhallaTransitTransfer>();
-const SIGN_SIZE: usize = size_of::<ValhallaSign>();
-const ADMIN_SIZE: usize = size_of::<ValhallaAdmin>();
-const EDGE_INFO_SIZE: usize = size_of::<ValhallaEdgeInfo>();
+const LANE_CONNECTIVITY_SIZE: usize = size_of::<ValhallaLaneConnectivity>();
+
+/// Tolerance in degrees when comparing a tile's base position to the tiling, which accounts for
+/// the header storing it as `f32`.
+const BASE_TOLERANCE: f64 = 1e-4;
+
+/// Hierarchy level of transit tiles.
+const TRANSIT_LEVEL: u8 = 3;
 
 impl InfernoTile {
     #[instrument(skip(bytes))]
@@ -65,9 +123,25 @@ impl InfernoTile {
             .map_err(|err| anyhow::anyhow!("Failed ValhallaTileHeader cast: {:?}", err))?;
 
         let tile_id = TileId::new(header.metadata.graphid());
+        let version = TileVersion::from_valhalla(&header.version)?;
+        version.check_supported()?;
+        debug!(%version, "Tile version");
+        let expected_base = TileLevel::of(&tile_id).and_then(|level| level.tile_base(&tile_id));
+        let base = LatLng::new(header.base_ll[1] as f64, header.base_ll[0] as f64);
+        if !expected_base.is_some_and(|expected| {
+            (expected.lat() - base.lat()).abs() < BASE_TOLERANCE
+                && (expected.lng() - base.lng()).abs() < BASE_TOLERANCE
+        }) {
+            warn!(
+                "Tile {} base {:?} doesn't match its position in the tiling, {:?}",
+                tile_id, base, expected_base
+            );
+        }
+
         let mut nodes = CheckedVec::new(tile_id);
         let mut node_transitions = CheckedVec::new(tile_id);
         let mut directed_edges = CheckedVec::new(tile_id);
+        let mut directed_edge_exts = CheckedVec::new(tile_id);
         let mut access_restrictions = CheckedVec::new(tile_id);
         let mut edge_infos = CheckedVec::new(tile_id);
 
@@ -115,7 +189,7 @@ impl InfernoTile {
             let transition =
                 ValhallaNodeTransition::ref_from_bytes(&bytes[ptr..ptr + NODE_TRANSITION_SIZE])
                     .map_err(|err| anyhow::anyhow!("Failed ValhallaTileHeader cast: {:?}", err))?;
-            node_transitions.push(transition.clone());
+            node_transitions.push(*transition);
             ptr += NODE_TRANSITION_SIZE;
         }
         trace!(
@@ -123,32 +197,46 @@ impl InfernoTile {
             header.counts2.transition_count(),
             ptr
         );
-        directed_edges.reserve_exact(header.counts1.directed_edges_count());
+        let mut edges = Vec::with_capacity(header.counts1.directed_edges_count());
         for _ in 0..header.counts1.directed_edges_count() {
             if ptr + DIRECTED_EDGE_SIZE >= bytes.len() {
                 return Err(anyhow::anyhow!(
                     "Invalid tile: not enough bytes for specified directed edge count"
                 ));
             }
-            let mut edge =
-                ValhallaDirectedEdge::ref_from_bytes(&bytes[ptr..ptr + DIRECTED_EDGE_SIZE])
-                    .map_err(|err| anyhow::anyhow!("Failed ValhallaTileHeader cast: {:?}", err))?
-                    .clone();
-
-            let edge_info_idx = if let Some(index) =
-                edge_info_offset_map.get(&edge.restrictions2.edge_info_offset())
-            {
+            let edge = ValhallaDirectedEdge::ref_from_bytes(&bytes[ptr..ptr + DIRECTED_EDGE_SIZE])
+                .map_err(|err| anyhow::anyhow!("Failed ValhallaTileHeader cast: {:?}", err))?;
+            edges.push(edge.clone());
+            ptr += DIRECTED_EDGE_SIZE;
+        }
+
+        // Edge infos are packed back to back up to the text list and don't store their size, so
+        // each one ends where the next one that an edge refers to starts.
+        let edge_info_starts: BTreeSet<usize> = edges
+            .iter()
+            .map(|edge| edge.restrictions2.edge_info_offset())
+            .collect();
+        let edge_info_section = bytes
+            .get(header.edge_info_offset as usize..header.text_list_offset as usize)
+            .ok_or_else(|| anyhow::anyhow!("Invalid tile: edge infos out of bounds"))?;
+        directed_edges.reserve_exact(edges.len());
+        for mut edge in edges {
+            let offset = edge.restrictions2.edge_info_offset();
+            let edge_info_idx = if let Some(index) = edge_info_offset_map.get(&offset) {
                 *index
             } else {
                 let new_idx = edge_infos.len();
-                edge_info_offset_map.insert(edge.restrictions2.edge_info_offset(), new_idx);
-                let start_ptr =
-                    header.edge_info_offset as usize + edge.restrictions2.edge_info_offset();
-                edge_infos.push(
-                    ValhallaEdgeInfo::ref_from_bytes(&bytes[start_ptr..start_ptr + EDGE_INFO_SIZE])
-                        .map_err(|err| anyhow::anyhow!("Failed ValhallaEdgeInfo cast: {:?}", err))?
-                        .clone(),
-                );
+                edge_info_offset_map.insert(offset, new_idx);
+                let end = edge_info_starts
+                    .range(offset + 1..)
+                    .next()
+                    .copied()
+                    .unwrap_or(edge_info_section.len());
+                edge_infos.push(InfernoEdgeInfo::from_valhalla(
+                    edge_info_section.get(offset..end).ok_or_else(|| {
+                        anyhow::anyhow!("Invalid tile: edge info offset out of bounds")
+                    })?,
+                )?);
                 new_idx
             };
             edge.restrictions2
@@ -159,9 +247,7 @@ impl InfernoTile {
                         err
                     )
                 })?;
-
             directed_edges.push(edge);
-            ptr += DIRECTED_EDGE_SIZE;
         }
         trace!(
             "Parsed {} directed edges, ptr: 0x{:x}",
@@ -169,7 +255,21 @@ impl InfernoTile {
             ptr
         );
         if header.metadata.has_ext_directededge() {
-            ptr += DIRECTED_EDGE_EXT_SIZE * header.counts1.directed_edges_count();
+            directed_edge_exts.reserve_exact(header.counts1.directed_edges_count());
+            for _ in 0..header.counts1.directed_edges_count() {
+                let ext = ValhallaDirectedEdgeExt::ref_from_bytes(
+                    bytes
+                        .get(ptr..ptr + DIRECTED_EDGE_EXT_SIZE)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                            "Invalid tile: not enough bytes for specified directed edge extensions"
+                        )
+                        })?,
+                )
+                .map_err(|err| anyhow::anyhow!("Failed ValhallaDirectedEdgeExt cast: {:?}", err))?;
+                directed_edge_exts.push(ext.clone());
+                ptr += DIRECTED_EDGE_EXT_SIZE;
+            }
             trace!(
                 "Parsed {} directed edge extensions, ptr: 0x{:x}",
                 header.counts1.directed_edges_count(),
@@ -188,7 +288,7 @@ impl InfernoTile {
                 &bytes[ptr..ptr + ACCESS_RESTRICTION_SIZE],
             )
             .map_err(|err| anyhow::anyhow!("Failed ValhallaTileHeader cast: {:?}", err))?;
-            access_restrictions.push(edge.clone());
+            access_restrictions.push(*edge);
             ptr += ACCESS_RESTRICTION_SIZE;
         }
         trace!(
@@ -197,13 +297,153 @@ impl InfernoTile {
             ptr
         );
 
-        ptr += header.counts3.departure_count() * TRANSIT_DEPARTURE_SIZE;
-        ptr += header.counts3.stop_count() * TRANSIT_STOP_SIZE;
-        ptr += header.counts4.route_count() * TRANSIT_ROUTE_SIZE;
-        ptr += header.counts4.schedule_count() * TRANSIT_SCHEDULE_SIZE;
-        ptr += header.counts3.transfer_count() * TRANSIT_TRANSFER_SIZE;
-        ptr += header.counts4.sign_count() * SIGN_SIZE;
-        ptr += header.counts5.admin_count() * ADMIN_SIZE;
+        let mut transit_departures = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts3.departure_count(),
+            &mut transit_departures,
+            "transit departure",
+        )?;
+        let mut transit_stops = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts3.stop_count(),
+            &mut transit_stops,
+            "transit stop",
+        )?;
+        let mut transit_routes = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts4.route_count(),
+            &mut transit_routes,
+            "transit route",
+        )?;
+        let mut transit_schedules = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts4.schedule_count(),
+            &mut transit_schedules,
+            "transit schedule",
+        )?;
+        let mut transit_transfers = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts3.transfer_count(),
+            &mut transit_transfers,
+            "transit transfer",
+        )?;
+        let mut signs = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts4.sign_count(),
+            &mut signs,
+            "sign",
+        )?;
+        let mut turn_lanes = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts2.turn_lane_count(),
+            &mut turn_lanes,
+            "turn lanes",
+        )?;
+        let mut admins = CheckedVec::new(tile_id);
+        parse_records(
+            bytes,
+            &mut ptr,
+            header.counts5.admin_count(),
+            &mut admins,
+            "admin",
+        )?;
+        let edge_bins = EdgeBins::from_valhalla(bytes, &mut ptr, header.bin_offsets)?;
+        trace!(
+            "Parsed {} edge bin entries, ptr: 0x{:x}",
+            edge_bins.borrowed().len(),
+            ptr
+        );
+
+        let forward_start = header.complex_restriction_forward_offset as usize;
+        let reverse_start = header.complex_restriction_reverse_offset as usize;
+        let mut complex_restrictions_forward = CheckedVec::new(tile_id);
+        for restriction in InfernoComplexRestriction::parse_list(
+            bytes.get(forward_start..reverse_start).ok_or_else(|| {
+                anyhow::anyhow!("Invalid tile: forward complex restrictions out of bounds")
+            })?,
+        )? {
+            complex_restrictions_forward.push(restriction);
+        }
+        let mut complex_restrictions_reverse = CheckedVec::new(tile_id);
+        for restriction in InfernoComplexRestriction::parse_list(
+            bytes
+                .get(reverse_start..header.edge_info_offset as usize)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Invalid tile: reverse complex restrictions out of bounds")
+                })?,
+        )? {
+            complex_restrictions_reverse.push(restriction);
+        }
+        trace!(
+            "Parsed {} forward and {} reverse complex restrictions",
+            complex_restrictions_forward.len(),
+            complex_restrictions_reverse.len()
+        );
+
+        let text_list_start = header.text_list_offset as usize;
+        let text_list_end = if header.late_connectivity_offset > header.text_list_offset {
+            header.late_connectivity_offset as usize
+        } else {
+            bytes.len()
+        };
+        let text_list = TextList::new(
+            bytes
+                .get(text_list_start..text_list_end)
+                .ok_or_else(|| anyhow::anyhow!("Invalid tile: text list out of bounds"))?
+                .to_vec(),
+        );
+        trace!("Parsed text list of {} bytes", text_list.len());
+
+        let mut lane_connectivity = CheckedVec::new(tile_id);
+        if header.late_connectivity_offset > 0 {
+            let lane_connectivity_start = header.late_connectivity_offset as usize;
+            let lane_connectivity_end = if header.counts1.predicted_speeds_count() > 0 {
+                header.predicted_speeds_offset as usize
+            } else {
+                bytes.len()
+            };
+            let mut lane_connectivity_ptr = lane_connectivity_start;
+            parse_records(
+                bytes,
+                &mut lane_connectivity_ptr,
+                lane_connectivity_end.saturating_sub(lane_connectivity_start)
+                    / LANE_CONNECTIVITY_SIZE,
+                &mut lane_connectivity,
+                "lane connectivity",
+            )?;
+        }
+
+        let predicted_speeds = if header.counts1.predicted_speeds_count() > 0 {
+            PredictedSpeeds::from_valhalla(
+                bytes
+                    .get(header.predicted_speeds_offset as usize..)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid tile: predicted speeds out of bounds")
+                    })?,
+                header.counts1.directed_edges_count(),
+                header.counts1.predicted_speeds_count(),
+            )?
+        } else {
+            PredictedSpeeds::default()
+        };
+        trace!(
+            "Parsed {} predicted speed profiles",
+            header.counts1.predicted_speeds_count()
+        );
 
         debug!(
             "Valhalla tile parsed successfully. ptr: 0x{:x}, len: 0x{:x}",
@@ -211,30 +451,239 @@ impl InfernoTile {
             bytes.len()
         );
 
-        Ok(InfernoTile {
+        let tile = InfernoTile {
             tile_id: TileId::new(header.metadata.graphid()),
-            header: header.clone(),
+            header: *header,
+            version,
             nodes,
             node_transitions,
             directed_edges,
+            directed_edge_exts,
             access_restrictions,
             edge_infos,
-        })
+            transit_departures,
+            transit_stops,
+            transit_routes,
+            transit_schedules,
+            transit_transfers,
+            signs,
+            turn_lanes,
+            admins,
+            edge_bins,
+            complex_restrictions_forward,
+            complex_restrictions_reverse,
+            text_list,
+            lane_connectivity,
+            predicted_speeds,
+        };
+        tile.tile_ref()
+            .check_consistency()
+            .map_err(|err| anyhow::anyhow!("Invalid tile: {}", err))?;
+        Ok(tile)
     }
 
     pub fn base_lat_lng(&self) -> LatLng {
-        LatLng::new(self.header.base_ll[1] as f64, self.header.base_ll[0] as f64)
+        self.tile_ref().base_lat_lng()
     }
 
     pub fn tile_id(&self) -> TileId {
         self.tile_id
     }
 
-    pub(crate) fn edge_slice<'a>(
-        &'a self,
-        start: GraphEntityId<ValhallaDirectedEdge>,
-        count: usize,
-    ) -> &'a [ValhallaDirectedEdge] {
-        self.directed_edges.slice(start, count)
+    /// Version of Valhalla that wrote this tile.
+    pub fn version(&self) -> TileVersion {
+        self.version
+    }
+
+    /// ID of the OSM dataset this tile was built from, e.g. the latest OSM changeset ID.
+    pub fn dataset_id(&self) -> u64 {
+        self.header.dataset_id
+    }
+
+    /// The tile's spatial index of directed edges.
+    pub fn edge_bins(&self) -> EdgeBinsRef<'_> {
+        self.edge_bins.borrowed()
+    }
+
+    /// Does this tile have elevation data for its nodes and edges?
+    pub fn has_elevation(&self) -> bool {
+        self.header.metadata.has_elevation()
+    }
+
+    // The queries below are implemented once on `TileRef`, so they work the same on archived
+    // tiles.
+
+    /// Returns the names of a directed edge in this tile, or `None` if the edge isn't in this
+    /// tile.
+    pub fn edge_names(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<EdgeName<'_>>> {
+        self.tile_ref().edge_names(edge)
+    }
+
+    /// Returns the OSM way ID of a directed edge, or `None` if the edge isn't in this tile.
+    pub fn way_id(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<u64> {
+        self.tile_ref().way_id(edge)
+    }
+
+    /// Returns the signs on a directed edge, or `None` if the edge isn't in this tile.
+    pub fn edge_signs(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<Vec<Sign<'_>>> {
+        self.tile_ref().edge_signs(edge)
+    }
+
+    /// Returns the signs on a node, such as junction names, or `None` if the node isn't in this
+    /// tile.
+    pub fn node_signs(&self, node: &GraphEntityId<ValhallaNodeInfo>) -> Option<Vec<Sign<'_>>> {
+        self.tile_ref().node_signs(node)
+    }
+
+    /// Returns the transit stop at `index` in this tile.
+    pub fn transit_stop(&self, index: usize) -> Option<TransitStop<'_>> {
+        self.tile_ref().transit_stop(index)
+    }
+
+    /// Returns the index of the transit stop at a transit node, or `None` if the node isn't in
+    /// this tile or isn't a transit stop.
+    pub fn transit_stop_index(&self, node: &GraphEntityId<ValhallaNodeInfo>) -> Option<usize> {
+        self.tile_ref().transit_stop_index(node)
+    }
+
+    /// Returns the transit route at `index` in this tile.
+    pub fn transit_route(&self, index: usize) -> Option<TransitRoute<'_>> {
+        self.tile_ref().transit_route(index)
+    }
+
+    /// Returns the transit schedule at `index` in this tile.
+    pub fn transit_schedule(&self, index: usize) -> Option<TransitSchedule> {
+        self.tile_ref().transit_schedule(index)
+    }
+
+    /// Returns the transfers from the transit stop at `stop_index`.
+    pub fn transit_transfers_from_stop(&self, stop_index: usize) -> Vec<TransitTransfer> {
+        self.tile_ref().transit_transfers_from_stop(stop_index)
+    }
+
+    /// Returns the departures along transit edges leaving a stop node on `day` at or after `time`
+    /// (seconds from midnight), paired with their next departure time and sorted by it. Returns
+    /// `None` if the node isn't in this tile.
+    pub fn departures_from_stop(
+        &self,
+        node: &GraphEntityId<ValhallaNodeInfo>,
+        day: ServiceDay,
+        time: u32,
+    ) -> Option<Vec<(u32, TransitDeparture<'_>)>> {
+        self.tile_ref().departures_from_stop(node, day, time)
+    }
+
+    /// Returns the complex restrictions that end on a directed edge, or `None` if the edge isn't
+    /// in this tile.
+    pub fn complex_restrictions_ending_on(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<ComplexRestrictionRef<'_>>> {
+        self.tile_ref().complex_restrictions_ending_on(edge)
+    }
+
+    /// Returns the complex restrictions that start on a directed edge, or `None` if the edge
+    /// isn't in this tile.
+    pub fn complex_restrictions_starting_on(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<ComplexRestrictionRef<'_>>> {
+        self.tile_ref().complex_restrictions_starting_on(edge)
+    }
+
+    /// Returns the general access restrictions on a directed edge, or `None` if the edge isn't in
+    /// this tile.
+    pub fn access_restrictions(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<AccessRestriction>> {
+        self.tile_ref().access_restrictions(edge)
+    }
+
+    /// Returns the turn lane masks at the end of a directed edge, from left to right, or `None`
+    /// if the edge isn't in this tile. The `Vec` is empty if the edge has no turn lanes.
+    pub fn turn_lanes(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<TurnLaneMask>> {
+        self.tile_ref().turn_lanes(edge)
+    }
+
+    /// Returns the lane connectivity records whose to edge is the given directed edge, or `None`
+    /// if the edge isn't in this tile.
+    pub fn lane_connectivity(
+        &self,
+        to_edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<LaneConnectivity>> {
+        self.tile_ref().lane_connectivity(to_edge)
+    }
+
+    /// Returns the predicted speed in kph of a directed edge at `seconds_of_week` (seconds since
+    /// midnight on Sunday), falling back to the edge's default speed if it has no predicted
+    /// speeds. Returns `None` if the edge isn't in this tile.
+    pub fn speed_at(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+        seconds_of_week: u32,
+    ) -> Option<f32> {
+        self.tile_ref().speed_at(edge, seconds_of_week)
+    }
+
+    /// Returns the administrative area a node is in, or `None` if the node isn't in this tile or
+    /// its admin index is invalid.
+    pub fn admin_for_node(&self, node: &GraphEntityId<ValhallaNodeInfo>) -> Option<Admin<'_>> {
+        self.tile_ref().admin_for_node(node)
+    }
+
+    /// Returns whether a directed edge crosses into a different country, or `None` if the edge
+    /// isn't in this tile.
+    pub fn edge_crosses_country(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<bool> {
+        self.tile_ref().edge_crosses_country(edge)
+    }
+
+    /// Returns the elevation in meters at each point of a directed edge's shape, in its direction
+    /// of travel. The `Vec` is empty if the edge has no elevation data. Returns `None` if the edge
+    /// isn't in this tile.
+    pub fn edge_elevation(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<Vec<f64>> {
+        self.tile_ref().edge_elevation(edge)
+    }
+
+    /// Returns the shape of a directed edge in its direction of travel, or `None` if the edge
+    /// isn't in this tile.
+    pub fn edge_shape(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<Vec<LatLng>> {
+        self.tile_ref().edge_shape(edge)
+    }
+}
+
+/// Parses `count` fixed-size records of type `T` starting at `ptr` into `out`, advancing `ptr`
+/// past them.
+fn parse_records<T>(
+    bytes: &[u8],
+    ptr: &mut usize,
+    count: usize,
+    out: &mut CheckedVec<T>,
+    name: &str,
+) -> Result<(), anyhow::Error>
+where
+    T: Archive + FromBytes + KnownLayout + Immutable + Clone,
+{
+    let size = size_of::<T>();
+    out.reserve_exact(count);
+    for _ in 0..count {
+        let record_bytes = bytes.get(*ptr..*ptr + size).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid tile: not enough bytes for specified {} count",
+                name
+            )
+        })?;
+        let record = T::ref_from_bytes(record_bytes)
+            .map_err(|err| anyhow::anyhow!("Failed {} cast: {:?}", name, err))?;
+        out.push(record.clone());
+        *ptr += size;
     }
+    trace!("Parsed {} {} records, ptr: 0x{:x}", count, name, *ptr);
+    Ok(())
 }
@@ -0,0 +1,600 @@
+//! Tile packs: every converted tile of a region in a single file, read in place through a memory
+//! map so only the tiles a query touches are paged in.
+//!
+//! A pack starts with an 80 byte header, followed by the rkyv archive of each tile and then an
+//! index of the tiles. All integers are little endian.
+//!
+//! | Offset | Size | Contents                                                   |
+//! |--------|------|------------------------------------------------------------|
+//! | 0      | 8    | Magic bytes `INFPACK\0`                                    |
+//! | 8      | 4    | Format version                                             |
+//! | 12     | 4    | Number of tiles                                            |
+//! | 16     | 8    | Dataset ID of the tiles                                    |
+//! | 24     | 8    | Creation time, in seconds since the Unix epoch             |
+//! | 32     | 8    | Offset of the index                                        |
+//! | 40     | 32   | Bounds of the tiles: min lat, min lng, max lat, max lng    |
+//! | 72     | 8    | Reserved, zero                                             |
+//!
+//! The index has one 32 byte [`PackIndexEntry`] per tile, sorted by tile ID, so a tile is found
+//! with a binary search. Archives and the index start on 16 byte boundaries, which keeps them
+//! aligned in a memory map.
+
+use std::{
+    fs,
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use memmap2::Mmap;
+use rkyv::{rancor, util::AlignedVec};
+use tracing::{debug, instrument, warn};
+use zerocopy::{FromBytes, FromZeros, Immutable, IntoBytes, KnownLayout};
+
+use crate::{
+    geomath::LatLng,
+    tiles::{BoundingBox, TileLevel},
+    valhalla::graph_id::TileId,
+};
+
+use super::{
+    integrity::{check_archive, CorruptTileError},
+    tile_ref::{AsTileRef, TileRef},
+    ArchivedInfernoTile, InfernoTile,
+};
+
+const MAGIC: &[u8; 8] = b"INFPACK\0";
+const FORMAT_VERSION: u32 = 1;
+const PACK_HEADER_SIZE: usize = 80;
+/// Alignment of archives and the index within a pack.
+const PACK_ALIGNMENT: u64 = 16;
+
+/// The contents of a pack's header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TilePackHeader {
+    pub format_version: u32,
+    pub tile_count: u32,
+    /// Dataset ID of the pack's tiles, see `InfernoTile::dataset_id`.
+    pub dataset_id: u64,
+    /// When the pack was written, to the second.
+    pub created: SystemTime,
+    /// Bounds of the pack's tiles.
+    pub bounds: BoundingBox,
+}
+
+/// Location of a tile's archive in a pack.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromBytes, IntoBytes, KnownLayout, Immutable)]
+pub struct PackIndexEntry {
+    tile_id: u64,
+    /// Offset of the archive from the start of the pack
+    offset: u64,
+    /// Size of the archive in bytes
+    len: u64,
+    /// CRC-32 of the archive
+    checksum: u32,
+    _reserved: u32,
+}
+
+impl PackIndexEntry {
+    pub fn tile_id(&self) -> TileId {
+        TileId::new(self.tile_id)
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+}
+
+fn align_up(offset: u64) -> u64 {
+    offset.next_multiple_of(PACK_ALIGNMENT)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(
+        bytes[offset..offset + 4]
+            .try_into()
+            .expect("Slice is 4 bytes"),
+    )
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(
+        bytes[offset..offset + 8]
+            .try_into()
+            .expect("Slice is 8 bytes"),
+    )
+}
+
+fn read_f64(bytes: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(
+        bytes[offset..offset + 8]
+            .try_into()
+            .expect("Slice is 8 bytes"),
+    )
+}
+
+/// Parses a pack header, returning it along with the offset of the index.
+fn parse_header(bytes: &[u8]) -> Result<(TilePackHeader, u64), anyhow::Error> {
+    if bytes.len() < PACK_HEADER_SIZE || &bytes[0..8] != MAGIC {
+        return Err(anyhow::anyhow!("Invalid tile pack: missing header"));
+    }
+    let format_version = read_u32(bytes, 8);
+    if format_version != FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Unsupported tile pack format version {}",
+            format_version
+        ));
+    }
+    let header = TilePackHeader {
+        format_version,
+        tile_count: read_u32(bytes, 12),
+        dataset_id: read_u64(bytes, 16),
+        created: SystemTime::UNIX_EPOCH + Duration::from_secs(read_u64(bytes, 24)),
+        bounds: BoundingBox::new(
+            LatLng::new(read_f64(bytes, 40), read_f64(bytes, 48)),
+            LatLng::new(read_f64(bytes, 56), read_f64(bytes, 64)),
+        ),
+    };
+    Ok((header, read_u64(bytes, 32)))
+}
+
+fn header_bytes(header: &TilePackHeader, index_offset: u64) -> [u8; PACK_HEADER_SIZE] {
+    let created = header
+        .created
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut bytes = [0; PACK_HEADER_SIZE];
+    bytes[0..8].copy_from_slice(MAGIC);
+    bytes[8..12].copy_from_slice(&header.format_version.to_le_bytes());
+    bytes[12..16].copy_from_slice(&header.tile_count.to_le_bytes());
+    bytes[16..24].copy_from_slice(&header.dataset_id.to_le_bytes());
+    bytes[24..32].copy_from_slice(&created.to_le_bytes());
+    bytes[32..40].copy_from_slice(&index_offset.to_le_bytes());
+    let bounds = header.bounds;
+    for (i, value) in [
+        bounds.min.lat(),
+        bounds.min.lng(),
+        bounds.max.lat(),
+        bounds.max.lng(),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        bytes[40 + i * 8..48 + i * 8].copy_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+/// Writes a tile pack one tile at a time, so tiles can be written as they're converted. The
+/// header and index are written by `finish`.
+pub struct TilePackWriter {
+    file: BufWriter<fs::File>,
+    offset: u64,
+    entries: Vec<PackIndexEntry>,
+    dataset_id: Option<u64>,
+    bounds: Option<BoundingBox>,
+    created: SystemTime,
+}
+
+impl TilePackWriter {
+    /// Creates a pack at `path`, replacing any existing file. `created` is recorded in the header.
+    #[instrument]
+    pub fn create(path: &Path, created: SystemTime) -> Result<TilePackWriter, anyhow::Error> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        // Reserve space for the header until the index offset is known.
+        file.write_all(&[0; PACK_HEADER_SIZE])?;
+        Ok(TilePackWriter {
+            file,
+            offset: PACK_HEADER_SIZE as u64,
+            entries: Vec::new(),
+            dataset_id: None,
+            bounds: None,
+            created,
+        })
+    }
+
+    /// Appends a tile to the pack.
+    #[instrument(skip(self, tile), fields(tile_id = %tile.tile_id()))]
+    pub fn add_tile(&mut self, tile: &InfernoTile) -> Result<(), anyhow::Error> {
+        let archive = rkyv::to_bytes::<rancor::Error>(tile).map_err(|err| {
+            anyhow::anyhow!("Failed to serialize tile {}: {}", tile.tile_id(), err)
+        })?;
+
+        match self.dataset_id {
+            None => self.dataset_id = Some(tile.dataset_id()),
+            Some(dataset_id) if dataset_id != tile.dataset_id() => warn!(
+                "Tile {} has dataset ID {}, but the pack has {}",
+                tile.tile_id(),
+                tile.dataset_id(),
+                dataset_id
+            ),
+            Some(_) => {}
+        }
+        if let Some(tile_bounds) =
+            TileLevel::of(&tile.tile_id()).and_then(|level| level.tile_bounds(&tile.tile_id()))
+        {
+            self.bounds = Some(match self.bounds {
+                None => tile_bounds,
+                Some(bounds) => BoundingBox::new(
+                    LatLng::new(
+                        bounds.min.lat().min(tile_bounds.min.lat()),
+                        bounds.min.lng().min(tile_bounds.min.lng()),
+                    ),
+                    LatLng::new(
+                        bounds.max.lat().max(tile_bounds.max.lat()),
+                        bounds.max.lng().max(tile_bounds.max.lng()),
+                    ),
+                ),
+            });
+        }
+
+        let offset = self.pad()?;
+        self.file.write_all(&archive)?;
+        self.offset += archive.len() as u64;
+        self.entries.push(PackIndexEntry {
+            tile_id: tile.tile_id().id,
+            offset,
+            len: archive.len() as u64,
+            checksum: crc32fast::hash(&archive),
+            _reserved: 0,
+        });
+        Ok(())
+    }
+
+    /// Pads the file to the next aligned offset and returns it.
+    fn pad(&mut self) -> Result<u64, anyhow::Error> {
+        let aligned = align_up(self.offset);
+        self.file
+            .write_all(&[0; PACK_ALIGNMENT as usize][..(aligned - self.offset) as usize])?;
+        self.offset = aligned;
+        Ok(aligned)
+    }
+
+    /// Writes the index and header, and returns the header.
+    #[instrument(skip(self))]
+    pub fn finish(mut self) -> Result<TilePackHeader, anyhow::Error> {
+        self.entries.sort_by_key(|entry| entry.tile_id);
+        if let Some(duplicate) = self
+            .entries
+            .windows(2)
+            .find(|entries| entries[0].tile_id == entries[1].tile_id)
+        {
+            return Err(anyhow::anyhow!(
+                "Tile {} was added to the pack more than once",
+                duplicate[0].tile_id()
+            ));
+        }
+        let index_offset = self.pad()?;
+        self.file.write_all(self.entries.as_bytes())?;
+
+        let header = TilePackHeader {
+            format_version: FORMAT_VERSION,
+            tile_count: self.entries.len() as u32,
+            dataset_id: self.dataset_id.unwrap_or_default(),
+            // The header only has whole seconds.
+            created: SystemTime::UNIX_EPOCH
+                + Duration::from_secs(
+                    self.created
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                ),
+            bounds: self.bounds.unwrap_or(BoundingBox::new(
+                LatLng::new(0.0, 0.0),
+                LatLng::new(0.0, 0.0),
+            )),
+        };
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header_bytes(&header, index_offset))?;
+        self.file.flush()?;
+        debug!("Wrote {} tiles", header.tile_count);
+        Ok(header)
+    }
+}
+
+/// Writes `tiles` to a pack at `path`, recording the current time as its creation time.
+pub fn write_tile_pack(
+    path: &Path,
+    tiles: &[InfernoTile],
+) -> Result<TilePackHeader, anyhow::Error> {
+    let mut writer = TilePackWriter::create(path, SystemTime::now())?;
+    for tile in tiles {
+        writer.add_tile(tile)?;
+    }
+    writer.finish()
+}
+
+/// Checks that the index of a pack is where the header says, at the end of the file.
+fn check_index_location(
+    header: &TilePackHeader,
+    index_offset: u64,
+    file_len: u64,
+) -> Result<(), anyhow::Error> {
+    let index_len = header.tile_count as u64 * size_of::<PackIndexEntry>() as u64;
+    if index_offset.checked_add(index_len) != Some(file_len) {
+        return Err(anyhow::anyhow!(
+            "Invalid tile pack: index of {} tiles at {} doesn't end the {} byte file",
+            header.tile_count,
+            index_offset,
+            file_len
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a pack's index is sorted and that every archive is aligned and before the index.
+fn check_index(index: &[PackIndexEntry], index_offset: u64) -> Result<(), anyhow::Error> {
+    for (i, entry) in index.iter().enumerate() {
+        let in_bounds = entry
+            .offset
+            .checked_add(entry.len)
+            .is_some_and(|end| end <= index_offset);
+        if !in_bounds || entry.offset % PACK_ALIGNMENT != 0 {
+            return Err(anyhow::anyhow!(
+                "Invalid tile pack: bad location for tile {}",
+                entry.tile_id()
+            ));
+        }
+        if i > 0 && index[i - 1].tile_id >= entry.tile_id {
+            return Err(anyhow::anyhow!("Invalid tile pack: index isn't sorted"));
+        }
+    }
+    Ok(())
+}
+
+/// Validates every tile in a pack, reading one tile at a time rather than mapping the pack.
+/// Returns the corrupt tiles, or an error if the pack's header or index is invalid.
+#[instrument]
+pub fn verify_tile_pack(path: &Path) -> Result<Vec<CorruptTileError>, anyhow::Error> {
+    let invalid = |err: anyhow::Error| anyhow::anyhow!("Failed to verify {:?}: {}", path, err);
+    let mut file = fs::File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut header_bytes = [0; PACK_HEADER_SIZE];
+    if file_len < PACK_HEADER_SIZE as u64 {
+        return Err(invalid(anyhow::anyhow!(
+            "Invalid tile pack: missing header"
+        )));
+    }
+    file.read_exact(&mut header_bytes)?;
+    let (header, index_offset) = parse_header(&header_bytes).map_err(invalid)?;
+    check_index_location(&header, index_offset, file_len).map_err(invalid)?;
+
+    let mut index = vec![PackIndexEntry::new_zeroed(); header.tile_count as usize];
+    file.seek(SeekFrom::Start(index_offset))?;
+    file.read_exact(index.as_mut_bytes())?;
+    check_index(&index, index_offset).map_err(invalid)?;
+
+    let mut corrupt = Vec::new();
+    let mut archive = AlignedVec::<{ PACK_ALIGNMENT as usize }>::new();
+    for entry in &index {
+        archive.clear();
+        archive.resize(entry.len as usize, 0);
+        file.seek(SeekFrom::Start(entry.offset))?;
+        file.read_exact(&mut archive)?;
+        if let Err(corruption) = check_archive(&archive, entry.checksum) {
+            let err = CorruptTileError {
+                tile_id: Some(entry.tile_id()),
+                path: path.to_path_buf(),
+                corruption,
+            };
+            warn!("{}", err);
+            corrupt.push(err);
+        }
+    }
+    debug!(
+        "Verified {} tiles, {} corrupt",
+        header.tile_count,
+        corrupt.len()
+    );
+    Ok(corrupt)
+}
+
+/// A memory mapped tile pack. Opening a pack reads only its header and index. Tiles are read in
+/// place, so they're paged in from disk the first time they're queried.
+#[derive(Debug)]
+pub struct TilePack {
+    mmap: Mmap,
+    header: TilePackHeader,
+    index_offset: usize,
+}
+
+impl TilePack {
+    /// Maps a tile pack and validates its index and every tile in it, which reads the whole pack.
+    /// A corrupt tile is reported as a `CorruptTileError`.
+    ///
+    /// # Safety
+    ///
+    /// The pack must not be modified or truncated while it's mapped.
+    #[instrument]
+    pub unsafe fn open(path: &Path) -> Result<TilePack, anyhow::Error> {
+        // SAFETY: Every tile is validated before the pack is returned.
+        let pack = unsafe { TilePack::open_trusted(path) }?;
+        for entry in pack.index() {
+            check_archive(pack.entry_bytes(entry), entry.checksum).map_err(|corruption| {
+                CorruptTileError {
+                    tile_id: Some(entry.tile_id()),
+                    path: path.to_path_buf(),
+                    corruption,
+                }
+            })?;
+        }
+        Ok(pack)
+    }
+
+    /// Maps a tile pack and validates its index, without reading its tiles, so opening a large
+    /// pack is fast and only the tiles that are queried are paged in.
+    ///
+    /// # Safety
+    ///
+    /// Tiles aren't validated, so the pack must be trusted, such as a pack written by
+    /// [`TilePackWriter`] or checked by [`verify_tile_pack`]. It must not be modified or
+    /// truncated while it's mapped.
+    #[instrument]
+    pub unsafe fn open_trusted(path: &Path) -> Result<TilePack, anyhow::Error> {
+        let mmap = Mmap::map(&fs::File::open(path)?)?;
+        let pack = TilePack::from_mmap(mmap)
+            .map_err(|err| anyhow::anyhow!("Failed to open {:?}: {}", path, err))?;
+        debug!("Opened pack of {} tiles", pack.header.tile_count);
+        Ok(pack)
+    }
+
+    fn from_mmap(mmap: Mmap) -> Result<TilePack, anyhow::Error> {
+        let (header, index_offset) = parse_header(&mmap)?;
+        check_index_location(&header, index_offset, mmap.len() as u64)?;
+        let pack = TilePack {
+            mmap,
+            header,
+            index_offset: index_offset as usize,
+        };
+        let index = <[PackIndexEntry]>::ref_from_bytes(&pack.mmap[pack.index_offset..])
+            .map_err(|err| anyhow::anyhow!("Failed PackIndexEntry cast: {:?}", err))?;
+        check_index(index, index_offset)?;
+        Ok(pack)
+    }
+
+    pub fn header(&self) -> &TilePackHeader {
+        &self.header
+    }
+
+    /// The pack's index, sorted by tile ID.
+    pub fn index(&self) -> &[PackIndexEntry] {
+        <[PackIndexEntry]>::ref_from_bytes(&self.mmap[self.index_offset..])
+            .expect("Index was checked when the pack was opened")
+    }
+
+    /// IDs of every tile in the pack, in ascending order.
+    pub fn tile_ids(&self) -> impl Iterator<Item = TileId> + '_ {
+        self.index().iter().map(PackIndexEntry::tile_id)
+    }
+
+    pub fn contains(&self, tile_id: &TileId) -> bool {
+        self.entry(tile_id).is_some()
+    }
+
+    fn entry(&self, tile_id: &TileId) -> Option<&PackIndexEntry> {
+        let index = self.index();
+        index
+            .binary_search_by_key(&tile_id.id, |entry| entry.tile_id)
+            .ok()
+            .map(|i| &index[i])
+    }
+
+    fn entry_bytes(&self, entry: &PackIndexEntry) -> &[u8] {
+        &self.mmap[entry.offset as usize..(entry.offset + entry.len) as usize]
+    }
+
+    /// The bytes of a tile's archive, or `None` if the pack doesn't contain the tile.
+    pub fn archive_bytes(&self, tile_id: &TileId) -> Option<&[u8]> {
+        Some(self.entry_bytes(self.entry(tile_id)?))
+    }
+
+    /// Returns an archived tile, or `None` if the pack doesn't contain it.
+    pub fn archived_tile(&self, tile_id: &TileId) -> Option<&ArchivedInfernoTile> {
+        let bytes = self.archive_bytes(tile_id)?;
+        // SAFETY: The archive was validated by `open`, or its caller guarantees it's valid.
+        Some(unsafe { rkyv::access_unchecked::<ArchivedInfernoTile>(bytes) })
+    }
+
+    /// Returns a tile, or `None` if the pack doesn't contain it.
+    pub fn tile(&self, tile_id: &TileId) -> Option<TileRef<'_>> {
+        Some(self.archived_tile(tile_id)?.tile_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inferno::{
+        graph::{EdgeSearch, InfernoTileGraph},
+        integrity::Corruption,
+        test_tiles::sample_tile_bytes,
+    };
+
+    use super::*;
+
+    #[test]
+    fn pack_round_trip() {
+        let path = std::env::temp_dir().join(format!("inferno-pack-{}", std::process::id()));
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut writer = TilePackWriter::create(&path, created).unwrap();
+        writer.add_tile(&tile).unwrap();
+        let written = writer.finish().unwrap();
+
+        // SAFETY: The pack isn't modified while it's mapped.
+        let pack = unsafe { TilePack::open(&path) }.unwrap();
+        assert_eq!(pack.header(), &written);
+        assert_eq!(pack.header().tile_count, 1);
+        assert_eq!(pack.header().dataset_id, 7);
+        assert_eq!(pack.header().created, created);
+        assert_eq!(pack.header().bounds.min, LatLng::new(47.5, -122.5));
+        assert_eq!(pack.header().bounds.max, LatLng::new(47.75, -122.25));
+        assert_eq!(pack.tile_ids().collect::<Vec<_>>(), vec![tile.tile_id()]);
+        assert!(pack.tile(&TileId::from_level_index(2, 0)).is_none());
+
+        let graph = InfernoTileGraph::from_pack(&pack, EdgeSearch::RTree);
+        let point = LatLng::new(47.605, -122.4);
+        let edges = graph.edges_for_point(&point, 100.0, 10);
+        assert_eq!(edges.len(), 2);
+        for (edge, _distance) in edges {
+            assert_eq!(graph.edge_names(&edge).unwrap()[0].text(), "Main Street");
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_bad_packs() {
+        let path = std::env::temp_dir().join(format!("inferno-bad-pack-{}", std::process::id()));
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        assert!(write_tile_pack(&path, &[tile.clone(), tile.clone()]).is_err());
+
+        write_tile_pack(&path, &[tile]).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        // SAFETY: The packs aren't modified while they're mapped.
+        assert!(unsafe { TilePack::open(&path) }.is_err());
+        assert!(verify_tile_pack(&path).is_err());
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        fs::write(&path, &bad_magic).unwrap();
+        assert!(unsafe { TilePack::open(&path) }.is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn finds_corrupt_tiles() {
+        let path =
+            std::env::temp_dir().join(format!("inferno-corrupt-pack-{}", std::process::id()));
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        write_tile_pack(&path, std::slice::from_ref(&tile)).unwrap();
+        assert_eq!(verify_tile_pack(&path).unwrap(), Vec::new());
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[PACK_HEADER_SIZE + 10] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+        let corrupt = verify_tile_pack(&path).unwrap();
+        assert_eq!(corrupt.len(), 1);
+        assert_eq!(corrupt[0].tile_id, Some(tile.tile_id()));
+        assert!(matches!(
+            corrupt[0].corruption,
+            Corruption::ChecksumMismatch { .. }
+        ));
+        // SAFETY: The pack isn't modified while it's mapped.
+        let err = unsafe { TilePack::open(&path) }.unwrap_err();
+        assert_eq!(err.downcast_ref::<CorruptTileError>(), Some(&corrupt[0]));
+        fs::remove_file(&path).unwrap();
+    }
+}
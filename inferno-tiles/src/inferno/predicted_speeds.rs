@@ -0,0 +1,199 @@
+use std::f32::consts::PI;
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::zero_copy::slice_in_place;
+
+/// Width of a speed bucket in seconds.
+const SPEED_BUCKET_SIZE_SECONDS: u32 = 5 * 60;
+/// Number of speed buckets in a week.
+const BUCKETS_PER_WEEK: u32 = 7 * 24 * 60 * 60 / SPEED_BUCKET_SIZE_SECONDS;
+/// Number of DCT coefficients stored per profile.
+pub(crate) const COEFFICIENT_COUNT: usize = 200;
+
+/// Historical speed profiles, compressed as DCT-II coefficients the same way Valhalla stores
+/// them.
+#[derive(Debug, Clone, PartialEq, Default, Archive, Serialize, Deserialize)]
+pub struct PredictedSpeeds {
+    /// Offset into `profiles` of each directed edge's coefficients, indexed by edge.
+    offsets: Vec<u32>,
+    profiles: Vec<i16>,
+}
+
+impl PredictedSpeeds {
+    /// Parses the predicted speeds section, which starts with one `u32` offset per directed edge
+    /// followed by `profile_count` profiles of `COEFFICIENT_COUNT` `i16` coefficients.
+    pub(crate) fn from_valhalla(
+        bytes: &[u8],
+        edge_count: usize,
+        profile_count: usize,
+    ) -> Result<PredictedSpeeds, anyhow::Error> {
+        let offsets_size = edge_count * size_of::<u32>();
+        let profiles_size = profile_count * COEFFICIENT_COUNT * size_of::<i16>();
+        let offsets = bytes
+            .get(..offsets_size)
+            .ok_or_else(|| anyhow::anyhow!("Invalid tile: predicted speed offsets out of bounds"))?
+            .chunks_exact(size_of::<u32>())
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        let profiles = bytes
+            .get(offsets_size..offsets_size + profiles_size)
+            .ok_or_else(|| anyhow::anyhow!("Invalid tile: predicted speed profiles out of bounds"))?
+            .chunks_exact(size_of::<i16>())
+            .map(|chunk| i16::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(PredictedSpeeds { offsets, profiles })
+    }
+
+    /// Appends the section in Valhalla's format to `bytes`.
+    pub(crate) fn write_valhalla(&self, bytes: &mut Vec<u8>) {
+        for offset in &self.offsets {
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+        for coefficient in &self.profiles {
+            bytes.extend_from_slice(&coefficient.to_le_bytes());
+        }
+    }
+
+    /// Number of profiles, as counted in the tile header.
+    pub(crate) fn profile_count(&self) -> usize {
+        self.profiles.len() / COEFFICIENT_COUNT
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    pub fn borrowed(&self) -> PredictedSpeedsRef<'_> {
+        PredictedSpeedsRef {
+            offsets: &self.offsets,
+            profiles: &self.profiles,
+        }
+    }
+}
+
+impl ArchivedPredictedSpeeds {
+    pub fn borrowed(&self) -> PredictedSpeedsRef<'_> {
+        PredictedSpeedsRef {
+            offsets: slice_in_place(&self.offsets),
+            profiles: slice_in_place(&self.profiles),
+        }
+    }
+}
+
+/// Predicted speeds borrowed from `PredictedSpeeds` or read in place from archived ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PredictedSpeedsRef<'a> {
+    offsets: &'a [u32],
+    profiles: &'a [i16],
+}
+
+impl PredictedSpeedsRef<'_> {
+    /// Returns the predicted speed in kph of the edge at `edge_index` at `seconds_of_week`
+    /// (seconds since midnight on Sunday), or `None` if the edge has no profile.
+    pub(crate) fn speed(&self, edge_index: usize, seconds_of_week: u32) -> Option<f32> {
+        let offset = *self.offsets.get(edge_index)? as usize;
+        let coefficients = self.profiles.get(offset..offset + COEFFICIENT_COUNT)?;
+        let bucket = (seconds_of_week / SPEED_BUCKET_SIZE_SECONDS) % BUCKETS_PER_WEEK;
+        Some(decompress_speed_bucket(coefficients, bucket))
+    }
+}
+
+/// Evaluates the inverse DCT-II of `coefficients` at `bucket`, matching Valhalla's
+/// `decompress_speed_bucket`.
+fn decompress_speed_bucket(coefficients: &[i16], bucket: u32) -> f32 {
+    let normalization = (2.0 / BUCKETS_PER_WEEK as f32).sqrt();
+    let phase = PI / BUCKETS_PER_WEEK as f32 * (bucket as f32 + 0.5);
+    let mut speed = coefficients[0] as f32 / 2f32.sqrt();
+    for (c, coefficient) in coefficients.iter().enumerate().skip(1) {
+        speed += *coefficient as f32 * (phase * c as f32).cos();
+    }
+    speed * normalization
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inferno::test_tiles::{edit_tile, sample_tile},
+        valhalla::graph_id::GraphEntityId,
+    };
+
+    use super::*;
+
+    const WEEK_SECONDS: u32 = BUCKETS_PER_WEEK * SPEED_BUCKET_SIZE_SECONDS;
+
+    fn profile(first_coefficients: &[i16]) -> Vec<i16> {
+        let mut coefficients = vec![0; COEFFICIENT_COUNT];
+        coefficients[..first_coefficients.len()].copy_from_slice(first_coefficients);
+        coefficients
+    }
+
+    fn assert_speed(actual: Option<f32>, expected: f32) {
+        let actual = actual.unwrap();
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn decompresses_speed_buckets() {
+        // Valhalla's decompress_speed_bucket gives these speeds for the two profiles: 50 kph all
+        // week, and 50 kph plus half a cosine from about 18.5 kph on Sunday morning to about
+        // 81.5 kph at the end of Saturday.
+        let mut profiles = profile(&[2245]);
+        profiles.extend(profile(&[2245, -1000]));
+        let speeds = PredictedSpeeds {
+            offsets: vec![0, COEFFICIENT_COUNT as u32],
+            profiles,
+        };
+        let speeds = speeds.borrowed();
+        for bucket in [0, 1007, 2015] {
+            assert_speed(
+                speeds.speed(0, bucket * SPEED_BUCKET_SIZE_SECONDS),
+                50.000_124,
+            );
+        }
+        assert_speed(speeds.speed(1, 0), 18.503_094);
+        assert_speed(
+            speeds.speed(1, 1007 * SPEED_BUCKET_SIZE_SECONDS),
+            49.975_583,
+        );
+        assert_speed(
+            speeds.speed(1, 1008 * SPEED_BUCKET_SIZE_SECONDS + 299),
+            50.024_665,
+        );
+        assert_speed(speeds.speed(1, WEEK_SECONDS - 1), 81.497_154);
+
+        // Times past the end of the week wrap around to its start.
+        assert_speed(speeds.speed(1, WEEK_SECONDS), 18.503_094);
+        assert_speed(speeds.speed(1, 2 * WEEK_SECONDS - 1), 81.497_154);
+        assert_eq!(speeds.speed(2, 0), None);
+    }
+
+    #[test]
+    fn falls_back_to_the_edge_speed() {
+        let tile = sample_tile();
+        let forward = GraphEntityId::from_tile_index(&tile.tile_id(), 0);
+        let reverse = GraphEntityId::from_tile_index(&tile.tile_id(), 1);
+        // Without predicted speeds, both edges use their default speed.
+        assert_eq!(tile.speed_at(&forward, 0), Some(50.0));
+
+        // Both edges have a 30 kph profile, but only the forward edge is flagged as using it.
+        let tile = edit_tile(tile, |tile| {
+            tile.directed_edges.as_mut_slice()[0]
+                .data1
+                .set_predicted_speed(true);
+            tile.predicted_speeds = PredictedSpeeds {
+                offsets: vec![0, 0],
+                profiles: profile(&[1347]),
+            };
+        });
+        assert_speed(tile.speed_at(&forward, 0), 30.000_074);
+        assert_eq!(tile.speed_at(&reverse, 0), Some(50.0));
+        let missing = GraphEntityId::from_tile_index(&tile.tile_id(), 2);
+        assert_eq!(tile.speed_at(&missing, 0), None);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::valhalla::sign::ValhallaSign;
+
+use super::text_list::TextListRef;
+
+/// Kind of a sign, from Valhalla's `Sign::Type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignKind {
+    /// Exit number, e.g. "23B".
+    ExitNumber,
+    /// Route the exit branches onto, e.g. "I 95 North".
+    ExitBranch,
+    /// Destination the exit leads toward, e.g. "Baltimore".
+    ExitToward,
+    /// Name of the exit, e.g. "Gettysburg Pike".
+    ExitName,
+    /// Route a guide sign branches onto.
+    GuideBranch,
+    /// Destination a guide sign points toward.
+    GuideToward,
+    /// Name of a junction.
+    JunctionName,
+    /// Guidance view image of a junction.
+    GuidanceViewJunction,
+    /// Guidance view image of a signboard.
+    GuidanceViewSignboard,
+    /// Name of a toll booth.
+    TollName,
+    /// Pronunciation or other linguistic data for the preceding sign.
+    Linguistic,
+    /// A sign type this version of inferno doesn't know about.
+    Unknown(u8),
+}
+
+impl SignKind {
+    pub(crate) fn from_valhalla(sign_type: u8) -> SignKind {
+        match sign_type {
+            0 => SignKind::ExitNumber,
+            1 => SignKind::ExitBranch,
+            2 => SignKind::ExitToward,
+            3 => SignKind::ExitName,
+            4 => SignKind::GuideBranch,
+            5 => SignKind::GuideToward,
+            6 => SignKind::JunctionName,
+            7 => SignKind::GuidanceViewJunction,
+            8 => SignKind::GuidanceViewSignboard,
+            9 => SignKind::TollName,
+            255 => SignKind::Linguistic,
+            other => SignKind::Unknown(other),
+        }
+    }
+
+    /// Is this a sign that Valhalla attaches to a node rather than to a directed edge?
+    pub fn is_node_sign(&self) -> bool {
+        matches!(self, SignKind::JunctionName | SignKind::TollName)
+    }
+}
+
+/// A sign on a directed edge or node, resolved from the tile's text list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sign<'a> {
+    kind: SignKind,
+    text: &'a str,
+    is_route_number: bool,
+    tag: Option<u8>,
+}
+
+impl<'a> Sign<'a> {
+    pub(crate) fn new(sign: &ValhallaSign, text_list: TextListRef<'a>) -> Option<Sign<'a>> {
+        let text = text_list.get(sign.text_offset());
+        if text.is_none() {
+            tracing::warn!("Invalid text list offset {} for sign", sign.text_offset());
+        }
+        let text = text?;
+        let (text, tag) = if sign.tagged() {
+            // Tagged signs store their tag type in the first byte.
+            let tag = *text.as_bytes().first()?;
+            (text.get(1..)?, Some(tag))
+        } else {
+            (text, None)
+        };
+        Some(Sign {
+            kind: SignKind::from_valhalla(sign.sign_type()),
+            text,
+            is_route_number: sign.route_num_type(),
+            tag,
+        })
+    }
+
+    pub fn kind(&self) -> SignKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Is the sign text a route number (e.g. "I 95") rather than a name?
+    pub fn is_route_number(&self) -> bool {
+        self.is_route_number
+    }
+
+    /// The raw Valhalla `TaggedValue` type of tagged sign text.
+    pub fn tag(&self) -> Option<u8> {
+        self.tag
+    }
+}
+
+/// Returns the signs in `signs` (sorted by index, as Valhalla stores them) at `index` that are
+/// node signs if `node_signs` is set, or edge signs otherwise.
+pub(crate) fn signs_at<'a>(
+    signs: &'a [ValhallaSign],
+    index: usize,
+    node_signs: bool,
+    text_list: TextListRef<'a>,
+) -> Vec<Sign<'a>> {
+    let start = signs.partition_point(|sign| sign.index() < index);
+    signs[start..]
+        .iter()
+        .take_while(|sign| sign.index() == index)
+        .filter(|sign| SignKind::from_valhalla(sign.sign_type()).is_node_sign() == node_signs)
+        .filter_map(|sign| Sign::new(sign, text_list))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inferno::{
+            test_tiles::{edit_tile, sample_tile},
+            text_list::TextList,
+        },
+        valhalla::graph_id::GraphEntityId,
+    };
+
+    use super::*;
+
+    /// Valhalla's `Sign::Type::kLinguistic`.
+    const LINGUISTIC: u8 = 255;
+
+    #[test]
+    fn reads_sign_kinds() {
+        for sign_type in (0..=9).chain([LINGUISTIC]) {
+            assert!(!matches!(
+                SignKind::from_valhalla(sign_type),
+                SignKind::Unknown(_)
+            ));
+        }
+        assert_eq!(SignKind::from_valhalla(6), SignKind::JunctionName);
+        assert_eq!(SignKind::from_valhalla(9), SignKind::TollName);
+        assert_eq!(SignKind::from_valhalla(42), SignKind::Unknown(42));
+
+        assert!(SignKind::JunctionName.is_node_sign());
+        assert!(SignKind::TollName.is_node_sign());
+        assert!(!SignKind::ExitNumber.is_node_sign());
+        assert!(!SignKind::Unknown(42).is_node_sign());
+    }
+
+    #[test]
+    fn finds_edge_and_node_signs() {
+        let tile = sample_tile();
+        let forward = GraphEntityId::from_tile_index(&tile.tile_id(), 0);
+        let reverse = GraphEntityId::from_tile_index(&tile.tile_id(), 1);
+        let start = GraphEntityId::from_tile_index(&tile.tile_id(), 0);
+        let end = GraphEntityId::from_tile_index(&tile.tile_id(), 1);
+
+        let mut text_list = tile.text_list.as_bytes().to_vec();
+        let mut add_text = |text: &str| {
+            let offset = text_list.len();
+            text_list.extend_from_slice(text.as_bytes());
+            text_list.push(0);
+            offset
+        };
+        // Signs are sorted by index, and node signs share the list with the signs of the edges
+        // with the same index.
+        let signs = [
+            (0, 0, false, false, add_text("23B")),
+            (0, 1, true, false, add_text("I 95 North")),
+            (0, LINGUISTIC, false, true, add_text("\u{2}aɪ naɪnti faɪv")),
+            (0, 6, false, false, add_text("Main Junction")),
+            (0, 42, false, false, add_text("Mystery")),
+            // Text past the end of the text list is skipped.
+            (0, 2, false, false, 10_000),
+            (1, 2, false, false, add_text("Baltimore")),
+            (1, 9, false, false, add_text("Main Toll")),
+        ];
+        let text_list = TextList::new(text_list);
+        let tile = edit_tile(tile, |tile| {
+            tile.text_list = text_list;
+            for (index, sign_type, is_route_number, tagged, text_offset) in signs {
+                tile.signs.push(
+                    ValhallaSign::new()
+                        .with_index(index)
+                        .with_sign_type(sign_type)
+                        .with_route_num_type(is_route_number)
+                        .with_tagged(tagged)
+                        .with_text_offset(text_offset),
+                );
+            }
+            tile.directed_edges.as_mut_slice()[0]
+                .data2
+                .set_has_signs(true);
+        });
+
+        let edge_signs = tile.edge_signs(&forward).unwrap();
+        let summary: Vec<_> = edge_signs
+            .iter()
+            .map(|sign| (sign.kind(), sign.text(), sign.is_route_number(), sign.tag()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (SignKind::ExitNumber, "23B", false, None),
+                (SignKind::ExitBranch, "I 95 North", true, None),
+                (SignKind::Linguistic, "aɪ naɪnti faɪv", false, Some(2)),
+                (SignKind::Unknown(42), "Mystery", false, None),
+            ]
+        );
+        let node_signs = tile.node_signs(&start).unwrap();
+        assert_eq!(node_signs.len(), 1);
+        assert_eq!(node_signs[0].kind(), SignKind::JunctionName);
+        assert_eq!(node_signs[0].text(), "Main Junction");
+        assert_eq!(tile.node_signs(&end).unwrap()[0].text(), "Main Toll");
+
+        // The reverse edge has a sign at its index, but isn't flagged as having signs.
+        assert!(tile.edge_signs(&reverse).unwrap().is_empty());
+        let tile = edit_tile(tile, |tile| {
+            tile.directed_edges.as_mut_slice()[1]
+                .data2
+                .set_has_signs(true);
+        });
+        let reverse_signs = tile.edge_signs(&reverse).unwrap();
+        assert_eq!(reverse_signs.len(), 1);
+        assert_eq!(reverse_signs[0].kind(), SignKind::ExitToward);
+        assert_eq!(reverse_signs[0].text(), "Baltimore");
+
+        let missing_edge = GraphEntityId::from_tile_index(&tile.tile_id(), 2);
+        assert_eq!(tile.edge_signs(&missing_edge), None);
+        let missing_node = GraphEntityId::from_tile_index(&tile.tile_id(), 2);
+        assert_eq!(tile.node_signs(&missing_node), None);
+    }
+}
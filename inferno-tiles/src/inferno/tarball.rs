@@ -0,0 +1,150 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use tracing::{debug, instrument};
+use zerocopy::FromBytes;
+
+use crate::valhalla::{graph_id::TileId, tile_index::ValhallaTileIndexEntry};
+
+use super::InfernoTile;
+
+const INDEX_FILE_NAME: &str = "index.bin";
+const INDEX_ENTRY_SIZE: usize = size_of::<ValhallaTileIndexEntry>();
+
+/// Location of a tile's data inside a tarball.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileLocation {
+    /// Offset of the tile's data from the start of the tarball.
+    pub offset: u64,
+    /// Size of the tile in bytes.
+    pub size: u32,
+}
+
+/// Parses the contents of a tarball's index.bin into a map from tile ID to tile location.
+pub fn parse_tile_index(bytes: &[u8]) -> Result<BTreeMap<TileId, TileLocation>, anyhow::Error> {
+    if !bytes.len().is_multiple_of(INDEX_ENTRY_SIZE) {
+        return Err(anyhow::anyhow!(
+            "Invalid tile index: size {} isn't a multiple of the entry size {}",
+            bytes.len(),
+            INDEX_ENTRY_SIZE
+        ));
+    }
+    let mut index = BTreeMap::new();
+    for entry_bytes in bytes.chunks_exact(INDEX_ENTRY_SIZE) {
+        let entry = ValhallaTileIndexEntry::ref_from_bytes(entry_bytes)
+            .map_err(|err| anyhow::anyhow!("Failed ValhallaTileIndexEntry cast: {:?}", err))?;
+        index.insert(
+            TileId::new(entry.tile_id as u64),
+            TileLocation {
+                offset: entry.offset,
+                size: entry.size,
+            },
+        );
+    }
+    Ok(index)
+}
+
+/// An uncompressed Valhalla tile tarball with an index.bin, as written by
+/// valhalla_build_extract. Tiles are read on demand using the index, without scanning the
+/// tarball.
+pub struct ValhallaTarball {
+    file: File,
+    index: BTreeMap<TileId, TileLocation>,
+}
+
+impl ValhallaTarball {
+    /// Opens a tarball and reads its index. valhalla_build_extract writes index.bin as the first
+    /// entry, so this normally reads only the first entry, but any other entries before it are
+    /// skipped.
+    #[instrument]
+    pub fn open(path: &Path) -> Result<ValhallaTarball, anyhow::Error> {
+        let mut archive = tar::Archive::new(File::open(path)?);
+        let mut index = None;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.path()?.file_name() != Some(INDEX_FILE_NAME.as_ref()) {
+                debug!("Skipping {:?} while looking for the index", entry.path()?);
+                continue;
+            }
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            index = Some(parse_tile_index(&bytes)?);
+            break;
+        }
+        let index = index
+            .ok_or_else(|| anyhow::anyhow!("Tarball {:?} has no {}", path, INDEX_FILE_NAME))?;
+        debug!("Read index of {} tiles", index.len());
+        Ok(ValhallaTarball {
+            file: File::open(path)?,
+            index,
+        })
+    }
+
+    /// IDs of every tile in the tarball, in ascending order.
+    pub fn tile_ids(&self) -> impl Iterator<Item = TileId> + '_ {
+        self.index.keys().copied()
+    }
+
+    pub fn location(&self, tile_id: &TileId) -> Option<TileLocation> {
+        self.index.get(tile_id).copied()
+    }
+
+    /// Reads the raw Valhalla bytes of a tile, or `None` if the tarball doesn't contain it.
+    pub fn read_tile_bytes(&mut self, tile_id: &TileId) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let Some(location) = self.location(tile_id) else {
+            return Ok(None);
+        };
+        self.file.seek(SeekFrom::Start(location.offset))?;
+        let mut bytes = vec![0; location.size as usize];
+        self.file.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// Reads and converts a tile, or returns `None` if the tarball doesn't contain it.
+    pub fn read_tile(&mut self, tile_id: &TileId) -> Result<Option<InfernoTile>, anyhow::Error> {
+        self.read_tile_bytes(tile_id)?
+            .map(|bytes| InfernoTile::from_valhalla(&bytes))
+            .transpose()
+    }
+
+    /// Reads and converts the given tiles, skipping any that the tarball doesn't contain. Use
+    /// this to build an `InfernoTileGraph` of just the tiles a query needs.
+    pub fn read_tiles(&mut self, tile_ids: &[TileId]) -> Result<Vec<InfernoTile>, anyhow::Error> {
+        let mut tiles = Vec::with_capacity(tile_ids.len());
+        for tile_id in tile_ids {
+            if let Some(tile) = self.read_tile(tile_id)? {
+                tiles.push(tile);
+            }
+        }
+        Ok(tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_index_entries() {
+        let mut bytes = Vec::new();
+        for (offset, tile_id, size) in [(1536u64, 0x2a_u32, 4096u32), (6144, 0x51, 512)] {
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&tile_id.to_le_bytes());
+            bytes.extend_from_slice(&size.to_le_bytes());
+        }
+        let index = parse_tile_index(&bytes).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get(&TileId::new(0x51)),
+            Some(&TileLocation {
+                offset: 6144,
+                size: 512
+            })
+        );
+        assert!(parse_tile_index(&bytes[..20]).is_err());
+    }
+}
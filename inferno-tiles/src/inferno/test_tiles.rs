@@ -0,0 +1,186 @@
+//! Tiles for tests.
+//!
+//! `sample_tile_bytes` reads a Valhalla tile from `fixtures/`, which `sample_tile_layout` lays
+//! out byte by byte, so that parser tests don't check inferno's reader against its own writer.
+//! `sample_tile` makes the same road with the synthetic graph builder, for tests of higher level
+//! queries that need realistic edge attributes, and `edit_tile` adds what the builder doesn't
+//! model to a built tile.
+
+use crate::{
+    geomath::LatLng,
+    valhalla::{
+        directed_edge::{ValhallaDirectedEdgeRestrictions1, ValhallaDirectedEdgeRestrictions2},
+        edge_info::{ValhallaEdgeInfoBitfield1, ValhallaEdgeInfoBitfield2},
+        graph_id::TileId,
+        name_info::ValhallaNameInfo,
+        node_info::{ValhallaNodeInfoData1, ValhallaNodeInfoPositionInfo},
+        tile_header::{ValhallaTileHeaderCounts1, ValhallaTileHeaderMetadata},
+    },
+};
+
+use super::{
+    builder::{EdgeAttributes, GraphBuilder},
+    InfernoTile,
+};
+
+/// Size of the tile header, up to and including `tile_size`, which the trailing empty slots pad
+/// out to 272 bytes.
+const HEADER_SIZE: usize = 272;
+const NODE_SIZE: usize = 32;
+const EDGE_SIZE: usize = 48;
+
+/// Appends `value` as a zigzag varint.
+fn push_signed_varint(bytes: &mut Vec<u8>, value: i64) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Returns a local level tile near Seattle with two nodes joined by a pair of opposing edges
+/// along "Main Street", one edge bin entry and no restrictions or transit data.
+pub(crate) fn sample_tile_bytes() -> Vec<u8> {
+    include_bytes!("../../fixtures/sample_tile.gph").to_vec()
+}
+
+/// Lays out the bytes of `fixtures/sample_tile.gph` field by field.
+fn sample_tile_layout() -> Vec<u8> {
+    let tile_id = TileId::from_level_index(2, 550 * 1440 + 230);
+    let tile = tile_id.id;
+
+    let mut nodes = Vec::new();
+    for (lat_offset, edge_index) in [(100_000, 0), (110_000, 1)] {
+        let position = ValhallaNodeInfoPositionInfo::new()
+            .with_lat_offset(lat_offset)
+            .with_lon_offset(100_000)
+            .with_access(0xfff);
+        let data1 = ValhallaNodeInfoData1::new()
+            .with_edge_index(edge_index)
+            .with_edge_count(1);
+        nodes.extend_from_slice(&position.into_bits().to_le_bytes());
+        nodes.extend_from_slice(&data1.into_bits().to_le_bytes());
+        nodes.extend_from_slice(&[0; 16]);
+    }
+
+    let mut edges = Vec::new();
+    for (end_node, is_forward) in [(1u64, true), (0, false)] {
+        let restrictions1 = ValhallaDirectedEdgeRestrictions1::new()
+            .with_end_node(tile | (end_node << 25))
+            .with_opp_index(0)
+            .with_is_forward(is_forward);
+        let restrictions2 = ValhallaDirectedEdgeRestrictions2::new().with_edge_info_offset(0);
+        edges.extend_from_slice(&restrictions1.into_bits().to_le_bytes());
+        edges.extend_from_slice(&restrictions2.into_bits().to_le_bytes());
+        edges.extend_from_slice(&[0; EDGE_SIZE - 16]);
+    }
+
+    let mut shape = Vec::new();
+    push_signed_varint(&mut shape, 47_600_000);
+    push_signed_varint(&mut shape, -122_400_000);
+    push_signed_varint(&mut shape, 10_000);
+    push_signed_varint(&mut shape, 0);
+    let mut edge_info = Vec::new();
+    edge_info.extend_from_slice(&123_456u32.to_le_bytes());
+    edge_info.extend_from_slice(&ValhallaEdgeInfoBitfield1::new().into_bits().to_le_bytes());
+    let bitfield2 = ValhallaEdgeInfoBitfield2::new()
+        .with_name_count(1)
+        .with_encoded_shape_size(shape.len());
+    edge_info.extend_from_slice(&bitfield2.into_bits().to_le_bytes());
+    let name = ValhallaNameInfo::new().with_name_offset(1);
+    edge_info.extend_from_slice(&name.into_bits().to_le_bytes());
+    edge_info.extend_from_slice(&shape);
+
+    let text_list = b"\0Main Street\0";
+
+    let bins_start = HEADER_SIZE + nodes.len() + edges.len();
+    let edge_info_offset = bins_start + size_of::<u64>();
+    let text_list_offset = edge_info_offset + edge_info.len();
+    let tile_size = text_list_offset + text_list.len();
+    // Edge 0 is the only bin entry, in the center bin.
+    let bin_offsets: [u32; 25] = std::array::from_fn(|bin| if bin < 12 { 0 } else { 1 });
+
+    let mut bytes = Vec::with_capacity(tile_size);
+    let metadata = ValhallaTileHeaderMetadata::new().with_graphid(tile);
+    bytes.extend_from_slice(&metadata.into_bits().to_le_bytes());
+    bytes.extend_from_slice(&(-122.5f32).to_le_bytes());
+    bytes.extend_from_slice(&47.5f32.to_le_bytes());
+    let mut version = [0u8; 16];
+    version[..5].copy_from_slice(b"3.5.0");
+    bytes.extend_from_slice(&version);
+    bytes.extend_from_slice(&7u64.to_le_bytes());
+    let counts1 = ValhallaTileHeaderCounts1::new()
+        .with_node_count(2)
+        .with_directed_edges_count(2);
+    bytes.extend_from_slice(&counts1.into_bits().to_le_bytes());
+    // Counts 2 through 5 and the two spare words.
+    bytes.extend_from_slice(&[0; 6 * 8]);
+    for offset in [
+        edge_info_offset,
+        edge_info_offset,
+        edge_info_offset,
+        text_list_offset,
+    ] {
+        bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+    }
+    // Date created.
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    for offset in bin_offsets {
+        bytes.extend_from_slice(&offset.to_le_bytes());
+    }
+    // Lane connectivity and predicted speeds offsets.
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.extend_from_slice(&(tile_size as u32).to_le_bytes());
+    bytes.resize(HEADER_SIZE, 0);
+
+    bytes.extend_from_slice(&nodes);
+    bytes.extend_from_slice(&edges);
+    bytes.extend_from_slice(&tile.to_le_bytes());
+    bytes.extend_from_slice(&edge_info);
+    bytes.extend_from_slice(text_list);
+    assert_eq!(bytes.len(), tile_size);
+    assert_eq!(nodes.len(), 2 * NODE_SIZE);
+    bytes
+}
+
+/// Returns the road of `sample_tile_bytes`, made with the synthetic graph builder: "Main Street"
+/// between two nodes, with the builder's default speed and access.
+pub(crate) fn sample_tile() -> InfernoTile {
+    let mut builder = GraphBuilder::new();
+    builder.dataset_id(7);
+    let start = builder.add_node(LatLng::new(47.6, -122.4));
+    let end = builder.add_node(LatLng::new(47.61, -122.4));
+    builder.add_edge(
+        start,
+        end,
+        EdgeAttributes {
+            way_id: 123_456,
+            names: vec!["Main Street".to_string()],
+            ..Default::default()
+        },
+    );
+    let mut tiles = builder.build().expect("Sample graph is valid").into_tiles();
+    assert_eq!(tiles.len(), 1);
+    tiles.remove(0)
+}
+
+/// Applies `edit` to `tile`, then writes it as a Valhalla tile and parses it back, so the edited
+/// tile gets the same checks and recomputed header as any other.
+pub(crate) fn edit_tile(mut tile: InfernoTile, edit: impl FnOnce(&mut InfernoTile)) -> InfernoTile {
+    edit(&mut tile);
+    let bytes = tile.to_valhalla().expect("Edited tile can be written");
+    InfernoTile::from_valhalla(&bytes).expect("Edited tile is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_tile_matches_its_layout() {
+        // To regenerate the fixture after changing the layout:
+        // std::fs::write("fixtures/sample_tile.gph", sample_tile_layout()).unwrap();
+        assert_eq!(sample_tile_bytes(), sample_tile_layout());
+    }
+}
@@ -0,0 +1,64 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// The tile's text list: a blob of NUL-terminated strings referenced by byte offset from names,
+/// signs, admins and transit records.
+#[derive(Debug, Clone, PartialEq, Default, Archive, Serialize, Deserialize)]
+pub struct TextList {
+    inner: Vec<u8>,
+}
+
+impl TextList {
+    pub fn new(inner: Vec<u8>) -> Self {
+        Self { inner }
+    }
+
+    pub fn borrowed(&self) -> TextListRef<'_> {
+        TextListRef(&self.inner)
+    }
+
+    /// The raw bytes of the text list, as stored in the tile.
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl ArchivedTextList {
+    pub fn borrowed(&self) -> TextListRef<'_> {
+        TextListRef(&self.inner)
+    }
+}
+
+/// A text list borrowed from a `TextList` or read in place from an archived one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextListRef<'a>(&'a [u8]);
+
+impl<'a> TextListRef<'a> {
+    /// Returns the raw bytes of the string starting at `offset`, without the NUL terminator.
+    pub fn get_bytes(&self, offset: usize) -> Option<&'a [u8]> {
+        let bytes = self.0.get(offset..)?;
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        Some(&bytes[..end])
+    }
+
+    /// Returns the string starting at `offset`, or `None` if the offset is out of bounds or the
+    /// string isn't valid UTF-8.
+    pub fn get(&self, offset: usize) -> Option<&'a str> {
+        std::str::from_utf8(self.get_bytes(offset)?).ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
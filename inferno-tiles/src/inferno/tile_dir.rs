@@ -0,0 +1,209 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use tracing::instrument;
+
+use crate::valhalla::graph_id::TileId;
+
+use super::{archive::TILE_ARCHIVE_EXTENSION, InfernoTile};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Number of 3 digit directory and file name groups in the path of a tile at each hierarchy
+/// level, e.g. `2/000/756/425.gph` for tile 756425 on level 2.
+const LEVEL_DIGIT_GROUPS: [(u8, usize); 4] = [(0, 2), (1, 2), (2, 3), (3, 3)];
+
+/// Returns the tile ID encoded in a Valhalla tile path such as
+/// `valhalla_tiles/2/000/756/425.gph` or `.../425.gph.gz`, or a tile archive path such as
+/// `.../425.inferno`, or `None` if the path doesn't follow Valhalla's layout.
+pub fn tile_id_from_path(path: &Path) -> Option<TileId> {
+    let file_name = path.file_name()?.to_str()?;
+    let stem = file_name
+        .strip_suffix(".gph.gz")
+        .or_else(|| file_name.strip_suffix(".gph"))
+        .or_else(|| {
+            file_name
+                .strip_suffix(TILE_ARCHIVE_EXTENSION)
+                .and_then(|stem| stem.strip_suffix('.'))
+        })?;
+    let mut components: Vec<&str> = path
+        .parent()?
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+    components.push(stem);
+
+    let is_group = |group: &&str| group.len() == 3 && group.bytes().all(|b| b.is_ascii_digit());
+    for (level, groups) in LEVEL_DIGIT_GROUPS {
+        if components.len() <= groups {
+            continue;
+        }
+        let (prefix, digits) = components.split_at(components.len() - groups);
+        if prefix.last() != Some(&level.to_string().as_str()) || !digits.iter().all(is_group) {
+            continue;
+        }
+        let index: u64 = digits.concat().parse().ok()?;
+        return Some(TileId::new((index << 3) | level as u64));
+    }
+    None
+}
+
+/// Returns the path of a tile relative to the root of a tile directory, following Valhalla's
+/// layout, e.g. `2/000/756/425.<extension>` for tile 756425 on level 2.
+pub fn tile_path(tile_id: &TileId, extension: &str) -> PathBuf {
+    let level = tile_id.hierarchy_level();
+    let groups = LEVEL_DIGIT_GROUPS
+        .iter()
+        .find(|(group_level, _)| *group_level == level)
+        .map_or(3, |(_, groups)| *groups);
+    let digits = format!("{:0width$}", tile_id.tile_index(), width = groups * 3);
+    let mut path = PathBuf::from(level.to_string());
+    for group in 0..groups - 1 {
+        path.push(&digits[group * 3..group * 3 + 3]);
+    }
+    path.push(format!("{}.{}", &digits[(groups - 1) * 3..], extension));
+    path
+}
+
+/// Reads a tile file, decompressing it if it's gzipped.
+pub fn read_tile_file(path: &Path) -> Result<Vec<u8>, anyhow::Error> {
+    decompress_tile_file(path, fs::read(path)?)
+}
+
+/// Decompresses the contents of the tile file at `path` if they're gzipped.
+fn decompress_tile_file(path: &Path, bytes: Vec<u8>) -> Result<Vec<u8>, anyhow::Error> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut decompressed)
+        .map_err(|err| anyhow::anyhow!("Failed to decompress {:?}: {}", path, err))?;
+    Ok(decompressed)
+}
+
+/// Reads and converts a tile file, checking that the tile ID in its header matches its path.
+#[instrument]
+pub fn load_tile_file(path: &Path) -> Result<InfernoTile, anyhow::Error> {
+    convert_tile_file(path, fs::read(path)?)
+}
+
+/// Converts the contents of the tile file at `path`, already read by the caller, like
+/// `load_tile_file`.
+#[instrument(skip(bytes))]
+pub fn convert_tile_file(path: &Path, bytes: Vec<u8>) -> Result<InfernoTile, anyhow::Error> {
+    let tile = InfernoTile::from_valhalla(&decompress_tile_file(path, bytes)?)?;
+    let Some(path_tile_id) = tile_id_from_path(path) else {
+        return Err(anyhow::anyhow!(
+            "Unable to determine tile ID from path {:?}",
+            path
+        ));
+    };
+    if path_tile_id != tile.tile_id() {
+        return Err(anyhow::anyhow!(
+            "Tile ID {} from path {:?} doesn't match tile ID {} in its header",
+            path_tile_id,
+            path,
+            tile.tile_id()
+        ));
+    }
+    Ok(tile)
+}
+
+/// Returns the paths of every `.gph` and `.gph.gz` file under `dir`, sorted.
+pub fn tile_files_in_dir(dir: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+    files_with_suffixes(dir, &[".gph", ".gph.gz"])
+}
+
+/// Returns the paths of every file under `dir` whose name ends with one of `suffixes`, sorted.
+pub(crate) fn files_with_suffixes(
+    dir: &Path,
+    suffixes: &[&str],
+) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| suffixes.iter().any(|suffix| name.ends_with(suffix)))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn tile_ids_from_paths() {
+        assert_eq!(
+            tile_id_from_path(Path::new("valhalla_tiles/2/000/756/425.gph")),
+            Some(TileId::new((756_425 << 3) | 2))
+        );
+        assert_eq!(
+            tile_id_from_path(Path::new("/data/2024/0/003/015.gph.gz")),
+            Some(TileId::new(3_015 << 3))
+        );
+        assert_eq!(
+            tile_id_from_path(Path::new("1/012/345.gph")),
+            Some(TileId::new((12_345 << 3) | 1))
+        );
+        assert_eq!(
+            tile_id_from_path(Path::new("tiles/2/000/756/425.inferno")),
+            Some(TileId::new((756_425 << 3) | 2))
+        );
+        assert_eq!(tile_id_from_path(Path::new("2/000/756.gph")), None);
+        assert_eq!(tile_id_from_path(Path::new("2/000/756/425.bin")), None);
+    }
+
+    #[test]
+    fn tile_paths_round_trip() {
+        for tile_id in [
+            TileId::from_level_index(0, 3_015),
+            TileId::from_level_index(1, 12_345),
+            TileId::from_level_index(2, 756_425),
+        ] {
+            let path = tile_path(&tile_id, "gph");
+            assert_eq!(tile_id_from_path(&path), Some(tile_id));
+        }
+        assert_eq!(
+            tile_path(&TileId::from_level_index(2, 756_425), "gph"),
+            PathBuf::from("2/000/756/425.gph")
+        );
+    }
+
+    #[test]
+    fn reads_gzipped_tiles() {
+        let dir = std::env::temp_dir().join(format!("inferno-tile-dir-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("425.gph");
+        let gzipped = dir.join("426.gph.gz");
+        fs::write(&plain, b"tile bytes").unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"tile bytes").unwrap();
+        fs::write(&gzipped, encoder.finish().unwrap()).unwrap();
+
+        assert_eq!(read_tile_file(&plain).unwrap(), b"tile bytes");
+        assert_eq!(read_tile_file(&gzipped).unwrap(), b"tile bytes");
+        assert_eq!(tile_files_in_dir(&dir).unwrap(), vec![plain, gzipped]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+use tracing::warn;
+
+use crate::{
+    geomath::LatLng,
+    valhalla::{
+        access_restrictions::ValhallaAccessRestriction,
+        admin::ValhallaAdmin,
+        directed_edge::ValhallaDirectedEdge,
+        graph_id::{GraphEntityId, TileId},
+        lane_connectivity::ValhallaLaneConnectivity,
+        node_info::ValhallaNodeInfo,
+        sign::ValhallaSign,
+        tile_header::ValhallaTileHeader,
+        transit_departure::ValhallaTransitDeparture,
+        transit_route::ValhallaTransitRoute,
+        transit_schedule::ValhallaTransitSchedule,
+        transit_stop::ValhallaTransitStop,
+        transit_transfer::ValhallaTransitTransfer,
+        turn_lanes::ValhallaTurnLanes,
+        HasEntityPointer,
+    },
+};
+
+use super::{
+    admin::Admin,
+    attributes::{access_restrictions_at, AccessRestriction, Use},
+    bins::EdgeBinsRef,
+    checked_vec::{CheckedSlice, RecordList},
+    complex_restriction::{ComplexRestrictionRef, InfernoComplexRestriction},
+    edge_info::{EdgeInfoRef, EdgeName, InfernoEdgeInfo},
+    lanes::{lane_connectivity_at, turn_lanes_at, LaneConnectivity, TurnLaneMask},
+    predicted_speeds::PredictedSpeedsRef,
+    sign::{signs_at, Sign},
+    text_list::TextListRef,
+    transit::{
+        ServiceDay, TransitDeparture, TransitRoute, TransitSchedule, TransitStop, TransitTransfer,
+    },
+    version::TileVersion,
+    zero_copy::in_place,
+    ArchivedInfernoTile, InfernoTile, TRANSIT_LEVEL,
+};
+
+/// Anything a tile can be read from: an `InfernoTile`, or an archived one read in place.
+pub trait AsTileRef {
+    fn tile_ref(&self) -> TileRef<'_>;
+}
+
+/// A tile borrowed from an `InfernoTile` or read in place from an `ArchivedInfernoTile`, with
+/// every query the tile supports. Creating one doesn't copy any of the tile's records.
+#[derive(Clone, Copy)]
+pub struct TileRef<'a> {
+    tile_id: TileId,
+    header: &'a ValhallaTileHeader,
+    version: TileVersion,
+    pub(crate) nodes: CheckedSlice<'a, ValhallaNodeInfo>,
+    pub(crate) directed_edges: CheckedSlice<'a, ValhallaDirectedEdge>,
+    access_restrictions: CheckedSlice<'a, ValhallaAccessRestriction>,
+    edge_infos: RecordList<'a, InfernoEdgeInfo>,
+    transit_departures: CheckedSlice<'a, ValhallaTransitDeparture>,
+    transit_stops: CheckedSlice<'a, ValhallaTransitStop>,
+    transit_routes: CheckedSlice<'a, ValhallaTransitRoute>,
+    transit_schedules: CheckedSlice<'a, ValhallaTransitSchedule>,
+    transit_transfers: CheckedSlice<'a, ValhallaTransitTransfer>,
+    signs: CheckedSlice<'a, ValhallaSign>,
+    turn_lanes: CheckedSlice<'a, ValhallaTurnLanes>,
+    admins: CheckedSlice<'a, ValhallaAdmin>,
+    edge_bins: EdgeBinsRef<'a>,
+    complex_restrictions_forward: RecordList<'a, InfernoComplexRestriction>,
+    complex_restrictions_reverse: RecordList<'a, InfernoComplexRestriction>,
+    text_list: TextListRef<'a>,
+    lane_connectivity: CheckedSlice<'a, ValhallaLaneConnectivity>,
+    predicted_speeds: PredictedSpeedsRef<'a>,
+}
+
+impl AsTileRef for TileRef<'_> {
+    fn tile_ref(&self) -> TileRef<'_> {
+        *self
+    }
+}
+
+impl AsTileRef for InfernoTile {
+    fn tile_ref(&self) -> TileRef<'_> {
+        TileRef {
+            tile_id: self.tile_id,
+            header: &self.header,
+            version: self.version,
+            nodes: self.nodes.borrowed(),
+            directed_edges: self.directed_edges.borrowed(),
+            access_restrictions: self.access_restrictions.borrowed(),
+            edge_infos: RecordList::owned(&self.edge_infos),
+            transit_departures: self.transit_departures.borrowed(),
+            transit_stops: self.transit_stops.borrowed(),
+            transit_routes: self.transit_routes.borrowed(),
+            transit_schedules: self.transit_schedules.borrowed(),
+            transit_transfers: self.transit_transfers.borrowed(),
+            signs: self.signs.borrowed(),
+            turn_lanes: self.turn_lanes.borrowed(),
+            admins: self.admins.borrowed(),
+            edge_bins: self.edge_bins.borrowed(),
+            complex_restrictions_forward: RecordList::owned(&self.complex_restrictions_forward),
+            complex_restrictions_reverse: RecordList::owned(&self.complex_restrictions_reverse),
+            text_list: self.text_list.borrowed(),
+            lane_connectivity: self.lane_connectivity.borrowed(),
+            predicted_speeds: self.predicted_speeds.borrowed(),
+        }
+    }
+}
+
+impl AsTileRef for ArchivedInfernoTile {
+    fn tile_ref(&self) -> TileRef<'_> {
+        TileRef {
+            tile_id: *in_place::<TileId>(&self.tile_id),
+            header: in_place(&self.header),
+            version: self.version.to_native(),
+            nodes: self.nodes.borrowed(),
+            directed_edges: self.directed_edges.borrowed(),
+            access_restrictions: self.access_restrictions.borrowed(),
+            edge_infos: RecordList::archived(&self.edge_infos),
+            transit_departures: self.transit_departures.borrowed(),
+            transit_stops: self.transit_stops.borrowed(),
+            transit_routes: self.transit_routes.borrowed(),
+            transit_schedules: self.transit_schedules.borrowed(),
+            transit_transfers: self.transit_transfers.borrowed(),
+            signs: self.signs.borrowed(),
+            turn_lanes: self.turn_lanes.borrowed(),
+            admins: self.admins.borrowed(),
+            edge_bins: self.edge_bins.borrowed(),
+            complex_restrictions_forward: RecordList::archived(&self.complex_restrictions_forward),
+            complex_restrictions_reverse: RecordList::archived(&self.complex_restrictions_reverse),
+            text_list: self.text_list.borrowed(),
+            lane_connectivity: self.lane_connectivity.borrowed(),
+            predicted_speeds: self.predicted_speeds.borrowed(),
+        }
+    }
+}
+
+impl<'a> TileRef<'a> {
+    pub fn base_lat_lng(&self) -> LatLng {
+        LatLng::new(self.header.base_ll[1] as f64, self.header.base_ll[0] as f64)
+    }
+
+    pub fn tile_id(&self) -> TileId {
+        self.tile_id
+    }
+
+    /// Version of Valhalla that wrote this tile.
+    pub fn version(&self) -> TileVersion {
+        self.version
+    }
+
+    /// ID of the OSM dataset this tile was built from, e.g. the latest OSM changeset ID.
+    pub fn dataset_id(&self) -> u64 {
+        self.header.dataset_id
+    }
+
+    /// The tile's spatial index of directed edges.
+    pub fn edge_bins(&self) -> EdgeBinsRef<'a> {
+        self.edge_bins
+    }
+
+    /// Does this tile have elevation data for its nodes and edges?
+    pub fn has_elevation(&self) -> bool {
+        self.header.metadata.has_elevation()
+    }
+
+    pub(crate) fn edge_slice(
+        &self,
+        start: GraphEntityId<ValhallaDirectedEdge>,
+        count: usize,
+    ) -> &'a [ValhallaDirectedEdge] {
+        self.directed_edges.slice(start, count)
+    }
+
+    /// Checks the invariants that queries rely on to index into the tile's records.
+    pub(crate) fn check_consistency(&self) -> Result<(), String> {
+        for (index, node) in self.nodes.iter().enumerate() {
+            let start = node.data1.edge_index();
+            let end = start + node.data1.edge_count();
+            if end > self.directed_edges.len() {
+                return Err(format!(
+                    "node {} has edges {}..{}, but the tile has {} edges",
+                    index,
+                    start,
+                    end,
+                    self.directed_edges.len()
+                ));
+            }
+        }
+        for (index, edge) in self.directed_edges.iter().enumerate() {
+            let edge_info = edge.restrictions2.edge_info_offset();
+            if edge_info >= self.edge_infos.len() {
+                return Err(format!(
+                    "edge {} has edge info {}, but the tile has {} edge infos",
+                    index,
+                    edge_info,
+                    self.edge_infos.len()
+                ));
+            }
+        }
+        self.edge_bins.check_consistency()
+    }
+
+    /// Returns an edge info in this tile.
+    pub(crate) fn edge_info(
+        &self,
+        index: &GraphEntityId<InfernoEdgeInfo>,
+    ) -> Option<EdgeInfoRef<'a>> {
+        self.edge_infos.get(index.graph_index())
+    }
+
+    /// Returns the edge info of a directed edge, or `None` if the edge isn't in this tile.
+    fn edge_info_for(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<EdgeInfoRef<'a>> {
+        let edge = self.directed_edges.get(edge)?;
+        self.edge_info(&edge.get_entity())
+    }
+
+    /// Returns the names of a directed edge in this tile, or `None` if the edge isn't in this
+    /// tile.
+    pub fn edge_names(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<EdgeName<'a>>> {
+        Some(self.edge_info_for(edge)?.names(self.text_list))
+    }
+
+    /// Returns the OSM way ID of a directed edge, or `None` if the edge isn't in this tile.
+    pub fn way_id(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<u64> {
+        Some(self.edge_info_for(edge)?.way_id())
+    }
+
+    /// Returns the signs on a directed edge, or `None` if the edge isn't in this tile.
+    pub fn edge_signs(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<Vec<Sign<'a>>> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        if !directed_edge.data2.has_signs() {
+            return Some(Vec::new());
+        }
+        Some(signs_at(
+            self.signs.as_slice(),
+            edge.graph_index(),
+            false,
+            self.text_list,
+        ))
+    }
+
+    /// Returns the signs on a node, such as junction names, or `None` if the node isn't in this
+    /// tile.
+    pub fn node_signs(&self, node: &GraphEntityId<ValhallaNodeInfo>) -> Option<Vec<Sign<'a>>> {
+        self.nodes.get(node)?;
+        Some(signs_at(
+            self.signs.as_slice(),
+            node.graph_index(),
+            true,
+            self.text_list,
+        ))
+    }
+
+    /// Returns the transit stop at `index` in this tile.
+    pub fn transit_stop(&self, index: usize) -> Option<TransitStop<'a>> {
+        let stop = self.transit_stops.as_slice().get(index)?;
+        Some(TransitStop::new(stop, self.text_list))
+    }
+
+    /// Returns the index of the transit stop at a transit node, or `None` if the node isn't in
+    /// this tile or isn't a transit stop.
+    pub fn transit_stop_index(&self, node: &GraphEntityId<ValhallaNodeInfo>) -> Option<usize> {
+        let node = self.nodes.get(node)?;
+        // On the transit level the transition index holds the stop index instead.
+        let index = node.data2.transition_index() as usize;
+        (self.tile_id.hierarchy_level() == TRANSIT_LEVEL && index < self.transit_stops.len())
+            .then_some(index)
+    }
+
+    /// Returns the transit route at `index` in this tile.
+    pub fn transit_route(&self, index: usize) -> Option<TransitRoute<'a>> {
+        let route = self.transit_routes.as_slice().get(index)?;
+        Some(TransitRoute::new(route, self.text_list))
+    }
+
+    /// Returns the transit schedule at `index` in this tile.
+    pub fn transit_schedule(&self, index: usize) -> Option<TransitSchedule> {
+        let schedule = self.transit_schedules.as_slice().get(index)?;
+        Some(TransitSchedule::new(schedule))
+    }
+
+    /// Returns the transfers from the transit stop at `stop_index`.
+    pub fn transit_transfers_from_stop(&self, stop_index: usize) -> Vec<TransitTransfer> {
+        self.transit_transfers
+            .iter()
+            .filter(|transfer| transfer.from_stopid as usize == stop_index)
+            .map(TransitTransfer::new)
+            .collect()
+    }
+
+    /// Returns the departures along transit edges leaving a stop node on `day` at or after `time`
+    /// (seconds from midnight), paired with their next departure time and sorted by it. Returns
+    /// `None` if the node isn't in this tile.
+    pub fn departures_from_stop(
+        &self,
+        node: &GraphEntityId<ValhallaNodeInfo>,
+        day: ServiceDay,
+        time: u32,
+    ) -> Option<Vec<(u32, TransitDeparture<'a>)>> {
+        let node = self.nodes.get(node)?;
+        let departures = self.transit_departures.as_slice();
+        let mut result = Vec::new();
+        for edge in node.edges(self) {
+            // Only transit lines store a line ID in place of the stop impact.
+            if !matches!(edge.edge_use(), Use::Rail | Use::Bus) {
+                continue;
+            }
+            let line_id = edge.stop_impact_union_line_id as u64;
+            // Departures are sorted by line ID, then by departure time.
+            let start = departures.partition_point(|departure| departure.data1.line_id() < line_id);
+            for departure in departures[start..]
+                .iter()
+                .take_while(|departure| departure.data1.line_id() == line_id)
+            {
+                let departure = TransitDeparture::new(
+                    departure,
+                    self.transit_schedules.as_slice(),
+                    self.header.date_created,
+                    self.text_list,
+                );
+                if let Some(next) = departure.next_departure_after(day, time) {
+                    result.push((next, departure));
+                }
+            }
+        }
+        result.sort_by_key(|(next, departure)| (*next, departure.line_id(), departure.trip_id()));
+        Some(result)
+    }
+
+    /// Returns the complex restrictions that end on a directed edge, or `None` if the edge isn't
+    /// in this tile.
+    pub fn complex_restrictions_ending_on(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<ComplexRestrictionRef<'a>>> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        if directed_edge.restrictions2.end_restriction() == 0 {
+            return Some(Vec::new());
+        }
+        Some(
+            self.complex_restrictions_forward
+                .iter()
+                .filter(|restriction| restriction.to_edge() == *edge)
+                .collect(),
+        )
+    }
+
+    /// Returns the complex restrictions that start on a directed edge, or `None` if the edge
+    /// isn't in this tile.
+    pub fn complex_restrictions_starting_on(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<ComplexRestrictionRef<'a>>> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        if directed_edge.restrictions2.start_restriction() == 0 {
+            return Some(Vec::new());
+        }
+        Some(
+            self.complex_restrictions_reverse
+                .iter()
+                .filter(|restriction| restriction.from_edge() == *edge)
+                .collect(),
+        )
+    }
+
+    /// Returns the general access restrictions on a directed edge, or `None` if the edge isn't in
+    /// this tile.
+    pub fn access_restrictions(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<AccessRestriction>> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        if directed_edge.access_restrictions().is_empty() {
+            return Some(Vec::new());
+        }
+        Some(access_restrictions_at(
+            self.access_restrictions.as_slice(),
+            edge.graph_index(),
+        ))
+    }
+
+    /// Returns the turn lane masks at the end of a directed edge, from left to right, or `None`
+    /// if the edge isn't in this tile. The `Vec` is empty if the edge has no turn lanes.
+    pub fn turn_lanes(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<TurnLaneMask>> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        if !directed_edge.data2.turn_lanes() {
+            return Some(Vec::new());
+        }
+        Some(turn_lanes_at(
+            self.turn_lanes.as_slice(),
+            edge.graph_index(),
+            self.text_list,
+        ))
+    }
+
+    /// Returns the lane connectivity records whose to edge is the given directed edge, or `None`
+    /// if the edge isn't in this tile.
+    pub fn lane_connectivity(
+        &self,
+        to_edge: &GraphEntityId<ValhallaDirectedEdge>,
+    ) -> Option<Vec<LaneConnectivity>> {
+        let directed_edge = self.directed_edges.get(to_edge)?;
+        if !directed_edge.data2.lane_connectivity() {
+            return Some(Vec::new());
+        }
+        Some(lane_connectivity_at(
+            self.lane_connectivity.as_slice(),
+            to_edge.graph_index(),
+        ))
+    }
+
+    /// Returns the predicted speed in kph of a directed edge at `seconds_of_week` (seconds since
+    /// midnight on Sunday), falling back to the edge's default speed if it has no predicted
+    /// speeds. Returns `None` if the edge isn't in this tile.
+    pub fn speed_at(
+        &self,
+        edge: &GraphEntityId<ValhallaDirectedEdge>,
+        seconds_of_week: u32,
+    ) -> Option<f32> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        if directed_edge.data1.predicted_speed() {
+            if let Some(speed) = self
+                .predicted_speeds
+                .speed(edge.graph_index(), seconds_of_week)
+            {
+                return Some(speed);
+            }
+            warn!("Missing predicted speed profile for edge {}", edge);
+        }
+        Some(directed_edge.data1.speed() as f32)
+    }
+
+    /// Returns the administrative area a node is in, or `None` if the node isn't in this tile or
+    /// its admin index is invalid.
+    pub fn admin_for_node(&self, node: &GraphEntityId<ValhallaNodeInfo>) -> Option<Admin<'a>> {
+        let node = self.nodes.get(node)?;
+        let admin = self
+            .admins
+            .as_slice()
+            .get(node.data1.admin_index() as usize)?;
+        Some(Admin::new(admin, self.text_list))
+    }
+
+    /// Returns whether a directed edge crosses into a different country, or `None` if the edge
+    /// isn't in this tile.
+    pub fn edge_crosses_country(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<bool> {
+        let edge = self.directed_edges.get(edge)?;
+        Some(edge.restrictions1.country_crossing())
+    }
+
+    /// Returns the elevation in meters at each point of a directed edge's shape, in its direction
+    /// of travel. The `Vec` is empty if the edge has no elevation data. Returns `None` if the edge
+    /// isn't in this tile.
+    pub fn edge_elevation(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<Vec<f64>> {
+        if !self.has_elevation() {
+            return Some(Vec::new());
+        }
+        let directed_edge = self.directed_edges.get(edge)?;
+        let mut elevation = self.edge_info(&directed_edge.get_entity())?.elevation();
+        if !directed_edge.restrictions1.is_forward() {
+            elevation.reverse();
+        }
+        Some(elevation)
+    }
+
+    /// Returns the shape of a directed edge in its direction of travel, or `None` if the edge
+    /// isn't in this tile.
+    pub fn edge_shape(&self, edge: &GraphEntityId<ValhallaDirectedEdge>) -> Option<Vec<LatLng>> {
+        let directed_edge = self.directed_edges.get(edge)?;
+        let mut shape = self
+            .edge_info(&directed_edge.get_entity())?
+            .shape()
+            .to_vec();
+        if !directed_edge.restrictions1.is_forward() {
+            shape.reverse();
+        }
+        Some(shape)
+    }
+}
@@ -0,0 +1,644 @@
+use crate::valhalla::{
+    transit_departure::ValhallaTransitDeparture, transit_route::ValhallaTransitRoute,
+    transit_schedule::ValhallaTransitSchedule, transit_stop::ValhallaTransitStop,
+    transit_transfer::ValhallaTransitTransfer,
+};
+
+use super::text_list::TextListRef;
+
+/// Valhalla departure type for departures at a single fixed time.
+const DEPARTURE_TYPE_FIXED: u64 = 0;
+
+/// Valhalla's pivot date, 2014-01-01, as days since 1970-01-01. Tile headers store their creation
+/// date as days since the pivot date (`kPivotDate` in baldr/datetime.h).
+const PIVOT_DATE_UNIX_DAYS: i64 = 16_071;
+/// Day of the week of the pivot date, a Wednesday, counting from 0 for Sunday.
+const PIVOT_DAY_OF_WEEK: i64 = 3;
+
+fn text<'a>(text_list: TextListRef<'a>, offset: usize) -> &'a str {
+    text_list.get(offset).unwrap_or("")
+}
+
+/// A transit stop, resolved from the tile's text list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitStop<'a> {
+    onestop_id: &'a str,
+    name: &'a str,
+    generated: bool,
+    traversability: u8,
+}
+
+impl<'a> TransitStop<'a> {
+    pub(crate) fn new(stop: &ValhallaTransitStop, text_list: TextListRef<'a>) -> TransitStop<'a> {
+        TransitStop {
+            onestop_id: text(text_list, stop.one_stop_offset() as usize),
+            name: text(text_list, stop.name_offset() as usize),
+            generated: stop.generated(),
+            traversability: stop.traversability(),
+        }
+    }
+
+    pub fn onestop_id(&self) -> &'a str {
+        self.onestop_id
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Was this stop generated during the tile build rather than coming from the feed?
+    pub fn generated(&self) -> bool {
+        self.generated
+    }
+
+    /// Raw Valhalla traversability of the stop (see `Traversability` in graphconstants.h).
+    pub fn traversability(&self) -> u8 {
+        self.traversability
+    }
+}
+
+/// A transit route, resolved from the tile's text list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitRoute<'a> {
+    route_type: u8,
+    color: u32,
+    text_color: u32,
+    onestop_id: &'a str,
+    operator_onestop_id: &'a str,
+    operator_name: &'a str,
+    operator_website: &'a str,
+    short_name: &'a str,
+    long_name: &'a str,
+    description: &'a str,
+}
+
+impl<'a> TransitRoute<'a> {
+    pub(crate) fn new(
+        route: &ValhallaTransitRoute,
+        text_list: TextListRef<'a>,
+    ) -> TransitRoute<'a> {
+        TransitRoute {
+            route_type: route.data1.route_type(),
+            color: route.route_color,
+            text_color: route.route_text_color,
+            onestop_id: text(text_list, route.data1.one_stop_offset() as usize),
+            operator_onestop_id: text(text_list, route.data2.op_by_onestop_id_offset()),
+            operator_name: text(text_list, route.data2.op_by_name_offset()),
+            operator_website: text(text_list, route.data3.op_by_website_offset()),
+            short_name: text(text_list, route.data3.short_name_offset()),
+            long_name: text(text_list, route.data4.long_name_offset()),
+            description: text(text_list, route.data4.desc_offset()),
+        }
+    }
+
+    /// Raw Valhalla route type (GTFS route types, see `TransitType` in graphconstants.h).
+    pub fn route_type(&self) -> u8 {
+        self.route_type
+    }
+
+    /// Route color as `0xRRGGBB`.
+    pub fn color(&self) -> u32 {
+        self.color
+    }
+
+    /// Route text color as `0xRRGGBB`.
+    pub fn text_color(&self) -> u32 {
+        self.text_color
+    }
+
+    pub fn onestop_id(&self) -> &'a str {
+        self.onestop_id
+    }
+
+    pub fn operator_onestop_id(&self) -> &'a str {
+        self.operator_onestop_id
+    }
+
+    pub fn operator_name(&self) -> &'a str {
+        self.operator_name
+    }
+
+    pub fn operator_website(&self) -> &'a str {
+        self.operator_website
+    }
+
+    pub fn short_name(&self) -> &'a str {
+        self.short_name
+    }
+
+    pub fn long_name(&self) -> &'a str {
+        self.long_name
+    }
+
+    pub fn description(&self) -> &'a str {
+        self.description
+    }
+}
+
+/// A date to look up transit departures on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServiceDay {
+    /// Days since Valhalla's pivot date.
+    date: i64,
+}
+
+impl ServiceDay {
+    /// Returns the day for a date in the proleptic Gregorian calendar, or `None` if it isn't a
+    /// valid date.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<ServiceDay> {
+        let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap_year => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > days_in_month {
+            return None;
+        }
+        Some(ServiceDay {
+            date: days_from_civil(year, month, day) - PIVOT_DATE_UNIX_DAYS,
+        })
+    }
+
+    /// Returns the day `days` days after Valhalla's pivot date, 2014-01-01, which is how tile
+    /// headers store their creation date.
+    pub fn from_days_since_pivot(days: i64) -> ServiceDay {
+        ServiceDay { date: days }
+    }
+
+    /// Days since Valhalla's pivot date, 2014-01-01.
+    pub fn days_since_pivot(&self) -> i64 {
+        self.date
+    }
+
+    /// Day of the week, from 0 for Sunday to 6 for Saturday.
+    pub fn day_of_week(&self) -> u8 {
+        (self.date + PIVOT_DAY_OF_WEEK).rem_euclid(7) as u8
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar (Howard Hinnant's
+/// `days_from_civil`).
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Days on which a departure runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitSchedule {
+    days: u64,
+    days_of_week: u8,
+    end_day: u8,
+}
+
+impl TransitSchedule {
+    pub(crate) fn new(schedule: &ValhallaTransitSchedule) -> TransitSchedule {
+        TransitSchedule {
+            days: schedule.days,
+            days_of_week: schedule.data1.days_of_week() as u8,
+            end_day: schedule.data1.end_day() as u8,
+        }
+    }
+
+    /// Bit mask of the days, relative to the tile's creation date, on which the departure runs.
+    pub fn days(&self) -> u64 {
+        self.days
+    }
+
+    /// Bit mask of the days of the week on which the departure runs, starting with Sunday.
+    pub fn days_of_week(&self) -> u8 {
+        self.days_of_week
+    }
+
+    /// Last valid day in `days`.
+    pub fn end_day(&self) -> u8 {
+        self.end_day
+    }
+
+    /// Does the departure run on `day`, in a tile created `tile_created` days after the pivot
+    /// date? As in Valhalla's `TransitSchedule::IsValid`, the day must be one of `days_of_week`,
+    /// and days from the tile's creation on must also be set in `days`, up to `end_day`.
+    pub(crate) fn runs_on(&self, day: ServiceDay, tile_created: u32) -> bool {
+        if self.days_of_week & (1 << day.day_of_week()) == 0 {
+            return false;
+        }
+        match u64::try_from(day.date - tile_created as i64) {
+            Ok(day) => day <= self.end_day as u64 && self.days & (1 << day) != 0,
+            // Schedules don't cover days before the tile was created.
+            Err(_) => true,
+        }
+    }
+}
+
+/// When a departure leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepartureTimes {
+    /// A single departure at a fixed time.
+    Fixed { departure_time: u32 },
+    /// Departures every `frequency` seconds from `departure_time` until `end_time`.
+    Frequency {
+        departure_time: u32,
+        end_time: u32,
+        frequency: u32,
+    },
+}
+
+/// A transit departure, resolved from its tile's schedules and text list. Times are in seconds
+/// from midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitDeparture<'a> {
+    line_id: u32,
+    route_index: usize,
+    trip_id: u32,
+    block_id: u32,
+    schedule_index: usize,
+    schedule: Option<TransitSchedule>,
+    /// Creation date of the tile, in days since the pivot date, which the schedule's days count
+    /// from.
+    tile_created: u32,
+    headsign: &'a str,
+    times: DepartureTimes,
+    elapsed_time: u32,
+    wheelchair_accessible: bool,
+    bicycle_accessible: bool,
+}
+
+impl<'a> TransitDeparture<'a> {
+    pub(crate) fn new(
+        departure: &ValhallaTransitDeparture,
+        schedules: &[ValhallaTransitSchedule],
+        tile_created: u32,
+        text_list: TextListRef<'a>,
+    ) -> TransitDeparture<'a> {
+        let schedule_index = departure.data2.schedule_index() as usize;
+        let raw_times = &departure.data3_union;
+        let times = if departure.data2.departure_type() == DEPARTURE_TYPE_FIXED {
+            DepartureTimes::Fixed {
+                departure_time: raw_times.departure_time(),
+            }
+        } else {
+            DepartureTimes::Frequency {
+                departure_time: raw_times.departure_time(),
+                end_time: raw_times.end_time(),
+                frequency: raw_times.frequency(),
+            }
+        };
+        TransitDeparture {
+            line_id: departure.data1.line_id() as u32,
+            route_index: departure.data1.route_index() as usize,
+            trip_id: departure.data1.trip_id() as u32,
+            block_id: departure.data2.block_id() as u32,
+            schedule_index,
+            schedule: schedules.get(schedule_index).map(TransitSchedule::new),
+            tile_created,
+            headsign: text(text_list, departure.data2.headsign_offset() as usize),
+            times,
+            elapsed_time: raw_times.elapsed_time(),
+            wheelchair_accessible: departure.data2.wheelchair_accessible(),
+            bicycle_accessible: departure.data2.bicycle_accessible(),
+        }
+    }
+
+    /// Line ID, unique to a pair of departure and arrival stops. Transit edges carry the line ID
+    /// of the departures that run along them.
+    pub fn line_id(&self) -> u32 {
+        self.line_id
+    }
+
+    /// Index of the departure's route in its tile.
+    pub fn route_index(&self) -> usize {
+        self.route_index
+    }
+
+    pub fn trip_id(&self) -> u32 {
+        self.trip_id
+    }
+
+    pub fn block_id(&self) -> u32 {
+        self.block_id
+    }
+
+    /// Index of the departure's schedule in its tile.
+    pub fn schedule_index(&self) -> usize {
+        self.schedule_index
+    }
+
+    /// The departure's schedule, or `None` if its tile has no schedule at `schedule_index`.
+    pub fn schedule(&self) -> Option<TransitSchedule> {
+        self.schedule
+    }
+
+    /// Does the departure run on `day`? Departures without a schedule never run.
+    pub fn runs_on(&self, day: ServiceDay) -> bool {
+        self.schedule
+            .is_some_and(|schedule| schedule.runs_on(day, self.tile_created))
+    }
+
+    pub fn headsign(&self) -> &'a str {
+        self.headsign
+    }
+
+    pub fn times(&self) -> DepartureTimes {
+        self.times
+    }
+
+    /// Time in seconds from departure until arrival at the next stop.
+    pub fn elapsed_time(&self) -> u32 {
+        self.elapsed_time
+    }
+
+    pub fn wheelchair_accessible(&self) -> bool {
+        self.wheelchair_accessible
+    }
+
+    pub fn bicycle_accessible(&self) -> bool {
+        self.bicycle_accessible
+    }
+
+    /// Returns the first time this departure leaves on `day` at or after `time`, if any.
+    pub fn next_departure_after(&self, day: ServiceDay, time: u32) -> Option<u32> {
+        if !self.runs_on(day) {
+            return None;
+        }
+        match self.times {
+            DepartureTimes::Fixed { departure_time } => {
+                (departure_time >= time).then_some(departure_time)
+            }
+            DepartureTimes::Frequency {
+                departure_time,
+                end_time,
+                frequency,
+            } => {
+                if departure_time >= time {
+                    return Some(departure_time);
+                }
+                if frequency == 0 {
+                    return None;
+                }
+                let next = departure_time + (time - departure_time).div_ceil(frequency) * frequency;
+                (next <= end_time).then_some(next)
+            }
+        }
+    }
+}
+
+/// A transfer between two stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitTransfer {
+    from_stop: u32,
+    to_stop: u32,
+    transfer_type: u8,
+    min_transfer_time: u16,
+}
+
+impl TransitTransfer {
+    pub(crate) fn new(transfer: &ValhallaTransitTransfer) -> TransitTransfer {
+        TransitTransfer {
+            from_stop: transfer.from_stopid,
+            to_stop: transfer.to_stopid,
+            transfer_type: transfer.data.transfer_type(),
+            min_transfer_time: transfer.data.min_transfer_time(),
+        }
+    }
+
+    /// Index of the stop the transfer starts at.
+    pub fn from_stop(&self) -> u32 {
+        self.from_stop
+    }
+
+    /// Index of the stop the transfer ends at.
+    pub fn to_stop(&self) -> u32 {
+        self.to_stop
+    }
+
+    /// Raw GTFS transfer type.
+    pub fn transfer_type(&self) -> u8 {
+        self.transfer_type
+    }
+
+    /// Minimum transfer time in seconds.
+    pub fn min_transfer_time(&self) -> u16 {
+        self.min_transfer_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geomath::LatLng,
+        inferno::{
+            attributes::Use,
+            builder::{EdgeAttributes, GraphBuilder},
+            test_tiles::edit_tile,
+            text_list::TextList,
+        },
+        valhalla::{
+            graph_id::GraphEntityId,
+            node_info::ValhallaNodeInfo,
+            transit_departure::{
+                ValhallaTransitDepartureBitfield1, ValhallaTransitDepartureBitfield2,
+                ValhallaTransitDepartureTimes,
+            },
+            transit_schedule::ValhallaTransitScheduleData1,
+        },
+    };
+
+    use super::*;
+
+    /// Valhalla departure type for departures repeating at a frequency.
+    const DEPARTURE_TYPE_FREQUENCY: u64 = 1;
+    /// Valhalla's days of the week bit mask for every day.
+    const EVERY_DAY: u64 = 0b111_1111;
+    /// Valhalla's days of the week bit mask for Saturday and Sunday.
+    const WEEKENDS: u64 = 0b100_0001;
+
+    fn departure(
+        line_id: u64,
+        trip_id: u64,
+        schedule_index: u64,
+        departure_time: u32,
+        end_time_and_frequency: Option<(u32, u32)>,
+    ) -> ValhallaTransitDeparture {
+        let mut times = ValhallaTransitDepartureTimes::new()
+            .with_departure_time(departure_time)
+            .with_elapsed_time(120);
+        let mut departure_type = DEPARTURE_TYPE_FIXED;
+        if let Some((end_time, frequency)) = end_time_and_frequency {
+            times = times.with_end_time(end_time).with_frequency(frequency);
+            departure_type = DEPARTURE_TYPE_FREQUENCY;
+        }
+        ValhallaTransitDeparture {
+            data1: ValhallaTransitDepartureBitfield1::new()
+                .with_line_id(line_id)
+                .with_trip_id(trip_id),
+            data2: ValhallaTransitDepartureBitfield2::new()
+                .with_schedule_index(schedule_index)
+                .with_departure_type(departure_type),
+            data3_union: times,
+        }
+    }
+
+    fn schedule(days: u64, days_of_week: u64, end_day: u64) -> ValhallaTransitSchedule {
+        ValhallaTransitSchedule {
+            days,
+            data1: ValhallaTransitScheduleData1::new()
+                .with_days_of_week(days_of_week)
+                .with_end_day(end_day),
+        }
+    }
+
+    #[test]
+    fn finds_next_departures() {
+        let text_list = TextList::default();
+        let schedules = [schedule(u64::MAX, EVERY_DAY, 63)];
+        let resolved =
+            |departure| TransitDeparture::new(&departure, &schedules, 0, text_list.borrowed());
+        let day = ServiceDay::from_days_since_pivot(0);
+
+        // A fixed departure at 8:00.
+        let fixed = resolved(departure(1, 1, 0, 28_800, None));
+        assert_eq!(
+            fixed.times(),
+            DepartureTimes::Fixed {
+                departure_time: 28_800
+            }
+        );
+        assert_eq!(fixed.next_departure_after(day, 0), Some(28_800));
+        assert_eq!(fixed.next_departure_after(day, 28_800), Some(28_800));
+        assert_eq!(fixed.next_departure_after(day, 28_801), None);
+
+        // Every 30 minutes from 7:00 until the last departure at 9:00.
+        let frequency = resolved(departure(1, 2, 0, 25_200, Some((32_400, 1_800))));
+        assert_eq!(frequency.elapsed_time(), 120);
+        assert_eq!(frequency.next_departure_after(day, 0), Some(25_200));
+        assert_eq!(frequency.next_departure_after(day, 25_201), Some(27_000));
+        assert_eq!(frequency.next_departure_after(day, 27_000), Some(27_000));
+        assert_eq!(frequency.next_departure_after(day, 32_400), Some(32_400));
+        assert_eq!(frequency.next_departure_after(day, 32_401), None);
+
+        // Service ending between two departures has no departure after the last one.
+        let short = resolved(departure(1, 3, 0, 25_200, Some((26_000, 1_800))));
+        assert_eq!(short.next_departure_after(day, 25_201), None);
+        let no_frequency = resolved(departure(1, 4, 0, 25_200, Some((32_400, 0))));
+        assert_eq!(no_frequency.next_departure_after(day, 25_201), None);
+
+        // A departure whose schedule is missing never runs.
+        let unscheduled = resolved(departure(1, 5, 1, 28_800, None));
+        assert_eq!(unscheduled.schedule(), None);
+        assert_eq!(unscheduled.next_departure_after(day, 0), None);
+    }
+
+    #[test]
+    fn converts_dates_to_service_days() {
+        let pivot = ServiceDay::from_ymd(2014, 1, 1).unwrap();
+        assert_eq!(pivot.days_since_pivot(), 0);
+        assert_eq!(pivot.day_of_week(), 3);
+        let leap_day = ServiceDay::from_ymd(2024, 2, 29).unwrap();
+        assert_eq!(leap_day.days_since_pivot(), 3_711);
+        assert_eq!(leap_day.day_of_week(), 4);
+        let before_pivot = ServiceDay::from_ymd(2013, 12, 29).unwrap();
+        assert_eq!(before_pivot.days_since_pivot(), -3);
+        assert_eq!(before_pivot.day_of_week(), 0);
+
+        assert_eq!(ServiceDay::from_ymd(2023, 2, 29), None);
+        assert_eq!(ServiceDay::from_ymd(2024, 13, 1), None);
+        assert_eq!(ServiceDay::from_ymd(2024, 4, 31), None);
+    }
+
+    #[test]
+    fn runs_on_scheduled_days() {
+        // Weekdays for the 14 days from the tile's creation on day 100, a Friday, except day 104.
+        let weekdays = 0b011_1110;
+        let days = 0b11_1111_1111_1111 & !(1 << 4);
+        let schedule = TransitSchedule::new(&schedule(days, weekdays, 13));
+        let runs_on = |day| schedule.runs_on(ServiceDay::from_days_since_pivot(day), 100);
+
+        assert!(runs_on(100));
+        // Saturday and Sunday.
+        assert!(!runs_on(101));
+        assert!(!runs_on(102));
+        assert!(runs_on(103));
+        assert!(!runs_on(104));
+        assert!(runs_on(113));
+        // Past the end day.
+        assert!(!runs_on(114));
+        // Days before the tile was created only check the day of the week.
+        assert!(runs_on(93));
+        assert!(!runs_on(95));
+    }
+
+    #[test]
+    fn lists_departures_from_stops() {
+        // A rail line and a road leaving the same stop.
+        let mut builder = GraphBuilder::new();
+        let stop = builder.add_node(LatLng::new(47.6, -122.4));
+        let next_stop = builder.add_node(LatLng::new(47.61, -122.4));
+        let street = builder.add_node(LatLng::new(47.6, -122.39));
+        let line = builder.add_edge(
+            stop,
+            next_stop,
+            EdgeAttributes {
+                edge_use: Use::Rail,
+                ..Default::default()
+            },
+        );
+        let road = builder.add_edge(stop, street, EdgeAttributes::default());
+        let graph = builder.build().unwrap();
+        let (line, _) = graph.edge_ids(line);
+        let (road, _) = graph.edge_ids(road);
+        let (stop, next_stop) = (graph.node_id(stop), graph.node_id(next_stop));
+
+        // The rail edge carries line 1. The road's stop impact happens to equal a line ID too, but
+        // roads don't carry lines. The reverse rail edge has no line, so its stop has no
+        // departures.
+        let tile = edit_tile(graph.into_tiles().remove(0), |tile| {
+            let edges = tile.directed_edges.as_mut_slice();
+            edges[line.graph_index()].stop_impact_union_line_id = 1;
+            edges[road.graph_index()].stop_impact_union_line_id = 1;
+            for departure in [
+                departure(1, 1, 0, 28_800, None),
+                departure(1, 2, 0, 25_200, Some((32_400, 1_800))),
+                departure(1, 3, 1, 29_000, None),
+                departure(2, 4, 0, 28_000, None),
+            ] {
+                tile.transit_departures.push(departure);
+            }
+            tile.transit_schedules.push(schedule(0, EVERY_DAY, 0));
+            tile.transit_schedules.push(schedule(0, WEEKENDS, 0));
+        });
+
+        // The tile was created on the pivot date, a Wednesday, and its schedules only cover days
+        // of the week from then on.
+        let wednesday = ServiceDay::from_days_since_pivot(-7);
+        let saturday = ServiceDay::from_days_since_pivot(-4);
+        let trips = |day, time| -> Vec<(u32, u32)> {
+            tile.departures_from_stop(&stop, day, time)
+                .unwrap()
+                .iter()
+                .map(|(next, departure)| (*next, departure.trip_id()))
+                .collect()
+        };
+        assert_eq!(trips(wednesday, 27_500), vec![(28_800, 1), (28_800, 2)]);
+        assert_eq!(
+            trips(saturday, 27_500),
+            vec![(28_800, 1), (28_800, 2), (29_000, 3)]
+        );
+        assert_eq!(trips(wednesday, 28_801), vec![(30_600, 2)]);
+        assert!(trips(wednesday, 32_401).is_empty());
+
+        assert!(tile
+            .departures_from_stop(&next_stop, wednesday, 0)
+            .unwrap()
+            .is_empty());
+        let missing = GraphEntityId::<ValhallaNodeInfo>::from_tile_index(&tile.tile_id(), 3);
+        assert_eq!(tile.departures_from_stop(&missing, wednesday, 0), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Writes an `InfernoTile` back out as a Valhalla tile, the inverse of
+//! `InfernoTile::from_valhalla`. The sections are laid out in the order Valhalla writes them and
+//! the header's counts and offsets are recomputed from the records, so a tile whose records have
+//! been edited is written with a consistent header.
+//!
+//! A tile converted from a Valhalla tile is written back byte for byte.
+
+use tracing::instrument;
+use zerocopy::IntoBytes;
+
+use super::{InfernoTile, HEADER_SIZE};
+
+impl InfernoTile {
+    /// Serializes the tile as a Valhalla `.gph` tile.
+    #[instrument(skip(self), fields(tile_id = %self.tile_id))]
+    pub fn to_valhalla(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let mut header = self.header;
+        // The header is written last, once its offsets are known.
+        let mut bytes = vec![0; HEADER_SIZE];
+
+        bytes.extend_from_slice(self.nodes.as_slice().as_bytes());
+        bytes.extend_from_slice(self.node_transitions.as_slice().as_bytes());
+
+        // Edge infos are laid out in the order they were parsed, which is the order edges first
+        // refer to them, and edges refer to them by index until they're written.
+        let mut edge_info_bytes = Vec::new();
+        let mut edge_info_offsets = Vec::with_capacity(self.edge_infos.len());
+        for edge_info in self.edge_infos.iter() {
+            edge_info_offsets.push(edge_info_bytes.len());
+            edge_info.write_valhalla(&mut edge_info_bytes);
+        }
+        for edge in self.directed_edges.iter() {
+            let mut edge = edge.clone();
+            let index = edge.restrictions2.edge_info_offset();
+            let offset = *edge_info_offsets.get(index).ok_or_else(|| {
+                anyhow::anyhow!("Directed edge refers to missing edge info {}", index)
+            })?;
+            edge.restrictions2
+                .set_edge_info_offset_checked(offset)
+                .map_err(|err| {
+                    anyhow::anyhow!(
+                        "Failed to write ValhallaDirectedEdge edge_info_offset: {:?}",
+                        err
+                    )
+                })?;
+            bytes.extend_from_slice(edge.as_bytes());
+        }
+        if header.metadata.has_ext_directededge() {
+            if self.directed_edge_exts.len() != self.directed_edges.len() {
+                return Err(anyhow::anyhow!(
+                    "Tile has {} directed edge extensions for {} directed edges",
+                    self.directed_edge_exts.len(),
+                    self.directed_edges.len()
+                ));
+            }
+            bytes.extend_from_slice(self.directed_edge_exts.as_slice().as_bytes());
+        }
+
+        bytes.extend_from_slice(self.access_restrictions.as_slice().as_bytes());
+        bytes.extend_from_slice(self.transit_departures.as_slice().as_bytes());
+        bytes.extend_from_slice(self.transit_stops.as_slice().as_bytes());
+        bytes.extend_from_slice(self.transit_routes.as_slice().as_bytes());
+        bytes.extend_from_slice(self.transit_schedules.as_slice().as_bytes());
+        bytes.extend_from_slice(self.transit_transfers.as_slice().as_bytes());
+        bytes.extend_from_slice(self.signs.as_slice().as_bytes());
+        bytes.extend_from_slice(self.turn_lanes.as_slice().as_bytes());
+        bytes.extend_from_slice(self.admins.as_slice().as_bytes());
+        header.bin_offsets = self.edge_bins.offsets();
+        self.edge_bins.write_valhalla(&mut bytes);
+
+        header.complex_restriction_forward_offset = offset(&bytes)?;
+        for restriction in self.complex_restrictions_forward.iter() {
+            restriction.write_valhalla(&mut bytes);
+        }
+        header.complex_restriction_reverse_offset = offset(&bytes)?;
+        for restriction in self.complex_restrictions_reverse.iter() {
+            restriction.write_valhalla(&mut bytes);
+        }
+        header.edge_info_offset = offset(&bytes)?;
+        bytes.extend_from_slice(&edge_info_bytes);
+        header.text_list_offset = offset(&bytes)?;
+        bytes.extend_from_slice(self.text_list.as_bytes());
+
+        // Valhalla leaves these offsets at zero in tiles without the sections, so only set them
+        // if the tile has the section or the original tile set them.
+        if !self.lane_connectivity.is_empty() || self.header.late_connectivity_offset != 0 {
+            header.late_connectivity_offset = offset(&bytes)?;
+        }
+        bytes.extend_from_slice(self.lane_connectivity.as_slice().as_bytes());
+        if !self.predicted_speeds.is_empty() || self.header.predicted_speeds_offset != 0 {
+            header.predicted_speeds_offset = offset(&bytes)?;
+        }
+        self.predicted_speeds.write_valhalla(&mut bytes);
+
+        set_count(
+            "nodes",
+            self.nodes.len(),
+            header.counts1.set_node_count_checked(self.nodes.len()),
+        )?;
+        set_count(
+            "directed edges",
+            self.directed_edges.len(),
+            header
+                .counts1
+                .set_directed_edges_count_checked(self.directed_edges.len()),
+        )?;
+        let profile_count = self.predicted_speeds.profile_count();
+        set_count(
+            "predicted speed profiles",
+            profile_count,
+            header
+                .counts1
+                .set_predicted_speeds_count_checked(profile_count),
+        )?;
+        set_count(
+            "node transitions",
+            self.node_transitions.len(),
+            header
+                .counts2
+                .set_transition_count_checked(self.node_transitions.len()),
+        )?;
+        set_count(
+            "turn lanes",
+            self.turn_lanes.len(),
+            header
+                .counts2
+                .set_turn_lane_count_checked(self.turn_lanes.len()),
+        )?;
+        set_count(
+            "transit transfers",
+            self.transit_transfers.len(),
+            header
+                .counts3
+                .set_transfer_count_checked(self.transit_transfers.len()),
+        )?;
+        set_count(
+            "transit departures",
+            self.transit_departures.len(),
+            header
+                .counts3
+                .set_departure_count_checked(self.transit_departures.len()),
+        )?;
+        set_count(
+            "transit stops",
+            self.transit_stops.len(),
+            header
+                .counts3
+                .set_stop_count_checked(self.transit_stops.len()),
+        )?;
+        set_count(
+            "transit routes",
+            self.transit_routes.len(),
+            header
+                .counts4
+                .set_route_count_checked(self.transit_routes.len()),
+        )?;
+        set_count(
+            "transit schedules",
+            self.transit_schedules.len(),
+            header
+                .counts4
+                .set_schedule_count_checked(self.transit_schedules.len()),
+        )?;
+        set_count(
+            "signs",
+            self.signs.len(),
+            header.counts4.set_sign_count_checked(self.signs.len()),
+        )?;
+        set_count(
+            "access restrictions",
+            self.access_restrictions.len(),
+            header
+                .counts5
+                .set_access_restriction_count_checked(self.access_restrictions.len()),
+        )?;
+        set_count(
+            "admins",
+            self.admins.len(),
+            header.counts5.set_admin_count_checked(self.admins.len()),
+        )?;
+
+        header.tile_size = offset(&bytes)?;
+        bytes[..HEADER_SIZE].copy_from_slice(header.as_bytes());
+        Ok(bytes)
+    }
+}
+
+/// Returns the offset of the end of `bytes`, for a header field.
+fn offset(bytes: &[u8]) -> Result<u32, anyhow::Error> {
+    u32::try_from(bytes.len())
+        .map_err(|_| anyhow::anyhow!("Tile is too large for Valhalla: {} bytes", bytes.len()))
+}
+
+/// Reports a count that doesn't fit in its header field.
+fn set_count<E>(name: &str, count: usize, result: Result<(), E>) -> Result<(), anyhow::Error> {
+    result.map_err(|_| anyhow::anyhow!("Too many {} for a Valhalla tile: {}", name, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use zerocopy::FromBytes;
+
+    use crate::{
+        geomath::LatLng,
+        inferno::{
+            builder::{EdgeAttributes, GraphBuilder},
+            edge_info::{encode_shape, InfernoEdgeInfo},
+            test_tiles::sample_tile_bytes,
+            text_list::TextList,
+            InfernoTile,
+        },
+        valhalla::{
+            directed_edge_ext::ValhallaDirectedEdgeExt,
+            edge_info::{ValhallaEdgeInfo, ValhallaEdgeInfoBitfield1, ValhallaEdgeInfoBitfield2},
+            graph_id::GraphEntityId,
+        },
+    };
+
+    #[test]
+    fn round_trips_valhalla_tiles() {
+        // The sample tile is laid out by hand, independently of the writer.
+        let bytes = sample_tile_bytes();
+        let tile = InfernoTile::from_valhalla(&bytes).unwrap();
+        let written = tile.to_valhalla().unwrap();
+        assert_eq!(written, bytes);
+        assert_eq!(InfernoTile::from_valhalla(&written).unwrap(), tile);
+    }
+
+    #[test]
+    fn round_trips_directed_edge_extensions() {
+        let mut tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        tile.header.metadata.set_has_ext_directededge(true);
+        for ext in [0x0102_0304_0506_0708u64, u64::MAX] {
+            tile.directed_edge_exts
+                .push(ValhallaDirectedEdgeExt::read_from_bytes(&ext.to_le_bytes()).unwrap());
+        }
+
+        let bytes = tile.to_valhalla().unwrap();
+        // The extensions follow the header, two nodes and two directed edges.
+        let exts = 272 + 2 * 32 + 2 * 48;
+        assert_eq!(
+            bytes[exts..exts + 8],
+            0x0102_0304_0506_0708u64.to_le_bytes()
+        );
+        assert_eq!(bytes[exts + 8..exts + 16], [0xff; 8]);
+        let mut written = InfernoTile::from_valhalla(&bytes).unwrap();
+        // Only the offsets past the extensions move.
+        written.header = tile.header;
+        assert_eq!(written, tile);
+
+        tile.directed_edge_exts.clear();
+        assert!(tile.to_valhalla().is_err());
+    }
+
+    #[test]
+    fn writes_edited_tiles() {
+        let mut tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        let edges = tile.directed_edges.as_mut_slice();
+        edges[0].data1.set_speed(88);
+        edges[1].data2.set_forward_access_mask(0);
+        tile.text_list = TextList::new(b"\0Main Street\0Main St\0".to_vec());
+
+        let bytes = tile.to_valhalla().unwrap();
+        let mut written = InfernoTile::from_valhalla(&bytes).unwrap();
+        assert_eq!(written.header.tile_size as usize, bytes.len());
+        let edge = GraphEntityId::from_tile_index(&tile.tile_id(), 0);
+        assert_eq!(written.directed_edges.get(&edge).unwrap().data1.speed(), 88);
+        // The text list grew, so only the tile size changes.
+        written.header.tile_size = tile.header.tile_size;
+        assert_eq!(written, tile);
+    }
+
+    #[test]
+    fn packs_edge_infos_without_padding() {
+        // Three edges whose records have odd sizes, so most of them start unaligned, with
+        // elevation on the first and last.
+        let mut builder = GraphBuilder::new();
+        let nodes: Vec<_> = (0..4)
+            .map(|index| builder.add_node(LatLng::new(47.6, -122.4 + index as f64 * 0.001)))
+            .collect();
+        let elevations = [vec![100.0, 101.0, 103.0], Vec::new(), vec![90.0, 89.25]];
+        let edges: Vec<_> = elevations
+            .iter()
+            .enumerate()
+            .map(|(index, elevation)| {
+                builder.add_edge(
+                    nodes[index],
+                    nodes[index + 1],
+                    EdgeAttributes {
+                        way_id: index as u64,
+                        elevation: elevation.clone(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        let graph = builder.build().unwrap();
+        let tile = &graph.tiles()[0];
+
+        let mut record_sizes = 0;
+        for edge_info in tile.edge_infos.iter() {
+            let mut bytes = Vec::new();
+            edge_info.write_valhalla(&mut bytes);
+            record_sizes += bytes.len();
+        }
+        assert!(record_sizes % 4 != 0);
+        let header = tile.header;
+        assert_eq!(
+            (header.text_list_offset - header.edge_info_offset) as usize,
+            record_sizes
+        );
+
+        for (edge, elevation) in edges.iter().zip(&elevations) {
+            let (forward, _) = graph.edge_ids(*edge);
+            let decoded = tile.edge_elevation(&forward).unwrap();
+            if elevation.is_empty() {
+                assert!(decoded.is_empty());
+            } else {
+                assert_eq!(decoded.len(), 2);
+                assert!((decoded[0] - elevation[0]).abs() < 1.0, "{:?}", decoded);
+                assert!((decoded[1] - elevation[elevation.len() - 1]).abs() < 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_edge_infos() {
+        let way_id = 0x0102_0304_0506_0708;
+        let shape = vec![
+            LatLng::new(47.600001, -122.400002),
+            LatLng::new(47.610003, -122.399999),
+        ];
+        let mut shape_bytes = Vec::new();
+        encode_shape(&mut shape_bytes, &shape);
+        let edge_info = InfernoEdgeInfo {
+            info: ValhallaEdgeInfo {
+                way_id: way_id as u32,
+                bitfield1: ValhallaEdgeInfoBitfield1::new()
+                    .with_mean_elevation(100)
+                    .with_extended_wayid0((way_id >> 32) as u8),
+                bitfield2: ValhallaEdgeInfoBitfield2::new()
+                    .with_encoded_shape_size(shape_bytes.len())
+                    .with_extended_wayid1((way_id >> 40) as u8)
+                    .with_extended_wayid_size(2)
+                    .with_has_elevation(true),
+            },
+            names: Vec::new(),
+            shape,
+            way_id,
+            encoded_elevation: vec![3, -2],
+        };
+        let mut bytes = Vec::new();
+        edge_info.write_valhalla(&mut bytes);
+        assert_eq!(InfernoEdgeInfo::from_valhalla(&bytes).unwrap(), edge_info);
+    }
+}
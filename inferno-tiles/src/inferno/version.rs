@@ -0,0 +1,205 @@
+use std::fmt::Display;
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Version of Valhalla that wrote a tile, from the tile header's version string.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Archive, Serialize, Deserialize,
+)]
+pub struct TileVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl TileVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> TileVersion {
+        TileVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses the NUL padded version string from a tile header, e.g. "3.4.0". Anything after
+    /// the numeric part (e.g. a "-dev" suffix) is ignored, and a missing patch number is 0.
+    pub(crate) fn from_valhalla(version: &[u8]) -> Result<TileVersion, TileVersionError> {
+        let end = version
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(version.len());
+        let text = std::str::from_utf8(&version[..end])
+            .map_err(|_| TileVersionError::Unparseable(version_lossy(version)))?;
+        let numeric_end = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let mut parts = text[..numeric_end].split('.').map(str::parse::<u16>);
+        let unparseable = || TileVersionError::Unparseable(text.to_string());
+        let major = parts.next().and_then(Result::ok).ok_or_else(unparseable)?;
+        let minor = parts.next().and_then(Result::ok).ok_or_else(unparseable)?;
+        let patch = match parts.next() {
+            Some(patch) => patch.map_err(|_| unparseable())?,
+            None => 0,
+        };
+        Ok(TileVersion::new(major, minor, patch))
+    }
+
+    /// Formats the version as the NUL padded version string of a tile header.
+    pub(crate) fn to_valhalla(self) -> [u8; 16] {
+        let text = self.to_string();
+        let mut version = [0; 16];
+        version[..text.len()].copy_from_slice(text.as_bytes());
+        version
+    }
+
+    /// The version tiles built by inferno are written as.
+    pub(crate) fn newest_supported() -> TileVersion {
+        NEWEST_SUPPORTED_VERSION
+    }
+
+    /// Returns an error if inferno can't read tiles written by this version.
+    pub(crate) fn check_supported(&self) -> Result<(), TileVersionError> {
+        let newest = (
+            NEWEST_SUPPORTED_VERSION.major,
+            NEWEST_SUPPORTED_VERSION.minor,
+        );
+        if *self < OLDEST_SUPPORTED_VERSION || (self.major, self.minor) > newest {
+            return Err(TileVersionError::Unsupported(*self));
+        }
+        Ok(())
+    }
+}
+
+impl Display for TileVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl ArchivedTileVersion {
+    pub fn to_native(&self) -> TileVersion {
+        TileVersion::new(
+            self.major.to_native(),
+            self.minor.to_native(),
+            self.patch.to_native(),
+        )
+    }
+}
+
+fn version_lossy(version: &[u8]) -> String {
+    String::from_utf8_lossy(version)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+// The structs in `crate::valhalla` describe a single tile layout: the one in which nodes store
+// `elevation_` in what older releases left as spare bits, and which the sample tile fixture
+// (labelled 3.5.0) is laid out in. No tile written by Valhalla itself has been checked against
+// them, so tiles from any other release are rejected rather than read with a guessed layout.
+// Check a real tile from a release before widening this range to it.
+
+/// The oldest Valhalla release whose tiles inferno reads.
+const OLDEST_SUPPORTED_VERSION: TileVersion = TileVersion::new(3, 3, 0);
+/// The newest Valhalla release whose tiles inferno reads, along with its patch releases.
+const NEWEST_SUPPORTED_VERSION: TileVersion = TileVersion::new(3, 5, 0);
+
+/// Error returned (wrapped in an `anyhow::Error`) when a tile's version can't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileVersionError {
+    /// The version string in the tile header isn't a version number.
+    Unparseable(String),
+    /// The tile was written by a version of Valhalla whose layout inferno doesn't support.
+    Unsupported(TileVersion),
+}
+
+impl Display for TileVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileVersionError::Unparseable(version) => {
+                write!(f, "Unparseable tile version {:?}", version)
+            }
+            TileVersionError::Unsupported(version) => {
+                write!(f, "Unsupported tile version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TileVersionError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::inferno::{test_tiles::sample_tile_bytes, InfernoTile};
+
+    use super::*;
+
+    fn header_version(text: &str) -> [u8; 16] {
+        let mut version = [0; 16];
+        version[..text.len()].copy_from_slice(text.as_bytes());
+        version
+    }
+
+    #[test]
+    fn parses_version_strings() {
+        assert_eq!(
+            TileVersion::from_valhalla(&header_version("3.4.0")),
+            Ok(TileVersion::new(3, 4, 0))
+        );
+        assert_eq!(
+            TileVersion::from_valhalla(&header_version("3.5.1-dev")),
+            Ok(TileVersion::new(3, 5, 1))
+        );
+        assert_eq!(
+            TileVersion::from_valhalla(&header_version("3.2")),
+            Ok(TileVersion::new(3, 2, 0))
+        );
+        assert!(matches!(
+            TileVersion::from_valhalla(&header_version("")),
+            Err(TileVersionError::Unparseable(_))
+        ));
+        assert_eq!(
+            TileVersion::new(3, 4, 0).to_valhalla(),
+            header_version("3.4.0")
+        );
+    }
+
+    #[test]
+    fn checks_supported_versions() {
+        for (major, minor, patch) in [(3, 3, 0), (3, 4, 0), (3, 5, 0), (3, 5, 1)] {
+            let version = TileVersion::new(major, minor, patch);
+            assert_eq!(version.check_supported(), Ok(()), "{}", version);
+        }
+        for (major, minor, patch) in [(2, 7, 0), (3, 0, 0), (3, 2, 9), (3, 6, 0), (4, 0, 0)] {
+            let version = TileVersion::new(major, minor, patch);
+            assert_eq!(
+                version.check_supported(),
+                Err(TileVersionError::Unsupported(version))
+            );
+        }
+    }
+
+    #[test]
+    fn reads_versions_from_tile_headers() {
+        let tile = InfernoTile::from_valhalla(&sample_tile_bytes()).unwrap();
+        assert_eq!(tile.version(), TileVersion::new(3, 5, 0));
+        for (version, supported) in [
+            ("3.2.0", false),
+            ("3.3.0", true),
+            ("3.4.0", true),
+            ("3.5.1-dev", true),
+            ("3.6.0", false),
+        ] {
+            let mut tile = tile.clone();
+            tile.header.version = header_version(version);
+            let result = InfernoTile::from_valhalla(&tile.to_valhalla().unwrap());
+            if supported {
+                assert!(result.is_ok(), "{}", version);
+            } else {
+                assert!(matches!(
+                    result.unwrap_err().downcast_ref::<TileVersionError>(),
+                    Some(TileVersionError::Unsupported(_))
+                ));
+            }
+        }
+    }
+}
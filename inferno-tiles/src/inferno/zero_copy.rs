@@ -0,0 +1,100 @@
+//! Reading archived tiles in place.
+//!
+//! Most of a tile is made of Valhalla's fixed size records, which are plain little endian
+//! integers and bitfields. rkyv archives those as the same integers, so on the little endian
+//! targets inferno supports (like the rest of the crate, which casts tile bytes straight to
+//! records) an archived record can be read as the record itself, without deserializing it.
+
+use rkyv::{Archive, Archived};
+use zerocopy::FromBytes;
+
+use crate::{
+    geomath::LatLng,
+    valhalla::{
+        access_restrictions::ValhallaAccessRestriction,
+        admin::ValhallaAdmin,
+        complex_restriction::{ValhallaComplexRestriction, ValhallaComplexRestrictionVia},
+        directed_edge::ValhallaDirectedEdge,
+        edge_info::ValhallaEdgeInfo,
+        graph_id::TileId,
+        lane_connectivity::ValhallaLaneConnectivity,
+        name_info::ValhallaNameInfo,
+        node_info::ValhallaNodeInfo,
+        node_transition::ValhallaNodeTransition,
+        sign::ValhallaSign,
+        tile_header::ValhallaTileHeader,
+        transit_departure::ValhallaTransitDeparture,
+        transit_route::ValhallaTransitRoute,
+        transit_schedule::ValhallaTransitSchedule,
+        transit_stop::ValhallaTransitStop,
+        transit_transfer::ValhallaTransitTransfer,
+        turn_lanes::ValhallaTurnLanes,
+    },
+};
+
+/// Types whose archived form can be read as the type itself.
+///
+/// # Safety
+///
+/// `Archived<Self>` must have the same size and alignment as `Self`, and store each field as the
+/// little endian form of the same field of `Self`. `Self` being `FromBytes` makes any archived
+/// bytes a valid value.
+pub unsafe trait ArchivedInPlace: Archive + FromBytes {}
+
+/// Reads an archived value in place.
+pub(crate) fn in_place<T: ArchivedInPlace>(archived: &Archived<T>) -> &T {
+    // SAFETY: `ArchivedInPlace` guarantees `Archived<T>` has the layout of `T`, and any bytes are a
+    // valid `T`.
+    unsafe { &*(archived as *const Archived<T>).cast::<T>() }
+}
+
+/// Reads a slice of archived values in place.
+pub(crate) fn slice_in_place<T: ArchivedInPlace>(archived: &[Archived<T>]) -> &[T] {
+    // SAFETY: As for `in_place`, and equal sizes mean the slice covers the same bytes.
+    unsafe { std::slice::from_raw_parts(archived.as_ptr().cast::<T>(), archived.len()) }
+}
+
+// rkyv archives integers as little endian, so on a big endian target an archived record isn't
+// the record itself and the impls below would be unsound.
+#[cfg(target_endian = "big")]
+compile_error!("inferno reads archived tiles in place, which needs a little endian target");
+
+macro_rules! archived_in_place {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            const _: () = assert!(
+                size_of::<Archived<$ty>>() == size_of::<$ty>()
+                    && align_of::<Archived<$ty>>() == align_of::<$ty>()
+            );
+            // SAFETY: Checked by the assertion above. Each of these types is `repr(C)` or a
+            // bitfield over a single integer, and rkyv archives integers as little endian.
+            unsafe impl ArchivedInPlace for $ty {}
+        )*
+    };
+}
+
+archived_in_place!(
+    u32,
+    u64,
+    i16,
+    LatLng,
+    TileId,
+    ValhallaTileHeader,
+    ValhallaNodeInfo,
+    ValhallaNodeTransition,
+    ValhallaDirectedEdge,
+    ValhallaAccessRestriction,
+    ValhallaEdgeInfo,
+    ValhallaNameInfo,
+    ValhallaTransitDeparture,
+    ValhallaTransitStop,
+    ValhallaTransitRoute,
+    ValhallaTransitSchedule,
+    ValhallaTransitTransfer,
+    ValhallaSign,
+    ValhallaTurnLanes,
+    ValhallaAdmin,
+    ValhallaComplexRestriction,
+    ValhallaComplexRestrictionVia,
+    ValhallaLaneConnectivity,
+);
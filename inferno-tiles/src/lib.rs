@@ -1,3 +1,4 @@
 pub mod geomath;
 pub mod inferno;
+pub mod tiles;
 pub(crate) mod valhalla;
@@ -0,0 +1,250 @@
+//! Valhalla's fixed tiling of the world. Each hierarchy level divides the world into a grid of
+//! square tiles, numbered row by row from the south west corner at (-90, -180).
+
+use crate::{geomath::LatLng, valhalla::graph_id::TileId};
+
+const METERS_PER_DEGREE_LAT: f64 = 111_320.0;
+
+/// An axis aligned box of latitudes and longitudes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: LatLng,
+    pub max: LatLng,
+}
+
+impl BoundingBox {
+    pub fn new(min: LatLng, max: LatLng) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    /// Returns a box containing every point within `radius_meters` of `center`.
+    pub fn around(center: &LatLng, radius_meters: f64) -> BoundingBox {
+        let radius_lat = radius_meters / METERS_PER_DEGREE_LAT;
+        let radius_lng = radius_lat / center.lat().to_radians().cos().max(1e-6);
+        BoundingBox {
+            min: LatLng::new(center.lat() - radius_lat, center.lng() - radius_lng),
+            max: LatLng::new(center.lat() + radius_lat, center.lng() + radius_lng),
+        }
+    }
+
+    pub fn contains(&self, point: &LatLng) -> bool {
+        point.lat() >= self.min.lat()
+            && point.lat() <= self.max.lat()
+            && point.lng() >= self.min.lng()
+            && point.lng() <= self.max.lng()
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.lat() <= other.max.lat()
+            && self.max.lat() >= other.min.lat()
+            && self.min.lng() <= other.max.lng()
+            && self.max.lng() >= other.min.lng()
+    }
+}
+
+/// A hierarchy level of Valhalla's tiling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileLevel {
+    level: u8,
+}
+
+impl TileLevel {
+    /// Motorways and trunk roads, in 4 degree tiles.
+    pub const HIGHWAY: TileLevel = TileLevel { level: 0 };
+    /// Primary through tertiary roads, in 1 degree tiles.
+    pub const ARTERIAL: TileLevel = TileLevel { level: 1 };
+    /// Every other road and path, in 0.25 degree tiles.
+    pub const LOCAL: TileLevel = TileLevel { level: 2 };
+    /// Transit stops and lines, on the same grid as the local level.
+    pub const TRANSIT: TileLevel = TileLevel { level: 3 };
+    /// Every level, from the top of the hierarchy down.
+    pub const ALL: [TileLevel; 4] = [
+        TileLevel::HIGHWAY,
+        TileLevel::ARTERIAL,
+        TileLevel::LOCAL,
+        TileLevel::TRANSIT,
+    ];
+
+    /// Returns the level with the given number, or `None` if Valhalla doesn't have it.
+    pub fn new(level: u8) -> Option<TileLevel> {
+        (level <= TileLevel::TRANSIT.level).then_some(TileLevel { level })
+    }
+
+    /// Returns the level of a tile, or `None` if its level is invalid.
+    pub fn of(tile_id: &TileId) -> Option<TileLevel> {
+        TileLevel::new(tile_id.hierarchy_level())
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Width and height of the level's tiles in degrees.
+    pub fn tile_size(&self) -> f64 {
+        match self.level {
+            0 => 4.0,
+            1 => 1.0,
+            _ => 0.25,
+        }
+    }
+
+    pub fn columns(&self) -> u32 {
+        (360.0 / self.tile_size()) as u32
+    }
+
+    pub fn rows(&self) -> u32 {
+        (180.0 / self.tile_size()) as u32
+    }
+
+    pub fn tile_count(&self) -> u32 {
+        self.columns() * self.rows()
+    }
+
+    fn tile_at(&self, row: u32, column: u32) -> TileId {
+        TileId::from_level_index(self.level, row * self.columns() + column)
+    }
+
+    /// Returns the row and column of a tile on this level, or `None` if the tile is on another
+    /// level or out of range.
+    fn row_column(&self, tile_id: &TileId) -> Option<(u32, u32)> {
+        if tile_id.hierarchy_level() != self.level || tile_id.tile_index() >= self.tile_count() {
+            return None;
+        }
+        let index = tile_id.tile_index();
+        Some((index / self.columns(), index % self.columns()))
+    }
+
+    fn row_of(&self, lat: f64) -> u32 {
+        ((lat + 90.0) / self.tile_size())
+            .floor()
+            .clamp(0.0, (self.rows() - 1) as f64) as u32
+    }
+
+    fn column_of(&self, lng: f64) -> u32 {
+        ((lng + 180.0) / self.tile_size())
+            .floor()
+            .clamp(0.0, (self.columns() - 1) as f64) as u32
+    }
+
+    /// Returns the tile containing `point`, or `None` if the point isn't a valid position.
+    /// Points on the north or east edge of the world belong to the last row or column.
+    pub fn tile_id(&self, point: &LatLng) -> Option<TileId> {
+        if !(-90.0..=90.0).contains(&point.lat()) || !(-180.0..=180.0).contains(&point.lng()) {
+            return None;
+        }
+        Some(self.tile_at(self.row_of(point.lat()), self.column_of(point.lng())))
+    }
+
+    /// South west corner of a tile, matching `InfernoTile::base_lat_lng`.
+    pub fn tile_base(&self, tile_id: &TileId) -> Option<LatLng> {
+        let (row, column) = self.row_column(tile_id)?;
+        Some(LatLng::new(
+            -90.0 + row as f64 * self.tile_size(),
+            -180.0 + column as f64 * self.tile_size(),
+        ))
+    }
+
+    pub fn tile_bounds(&self, tile_id: &TileId) -> Option<BoundingBox> {
+        let min = self.tile_base(tile_id)?;
+        let max = LatLng::new(min.lat() + self.tile_size(), min.lng() + self.tile_size());
+        Some(BoundingBox::new(min, max))
+    }
+
+    /// Returns the up to 8 tiles surrounding a tile. Neighbors wrap around the antimeridian but
+    /// not the poles.
+    pub fn neighbors(&self, tile_id: &TileId) -> Vec<TileId> {
+        let Some((row, column)) = self.row_column(tile_id) else {
+            return Vec::new();
+        };
+        let columns = self.columns() as i64;
+        let mut neighbors = Vec::with_capacity(8);
+        for row_offset in -1i64..=1 {
+            let neighbor_row = row as i64 + row_offset;
+            if neighbor_row < 0 || neighbor_row >= self.rows() as i64 {
+                continue;
+            }
+            for column_offset in -1i64..=1 {
+                if row_offset == 0 && column_offset == 0 {
+                    continue;
+                }
+                let neighbor_column = (column as i64 + column_offset).rem_euclid(columns);
+                neighbors.push(self.tile_at(neighbor_row as u32, neighbor_column as u32));
+            }
+        }
+        neighbors
+    }
+
+    /// Returns the tiles that intersect `bbox`, row by row from the south west. Boxes crossing the
+    /// antimeridian should be split in two.
+    pub fn tiles_in_bbox(&self, bbox: &BoundingBox) -> Vec<TileId> {
+        if bbox.min.lat() > bbox.max.lat()
+            || bbox.min.lng() > bbox.max.lng()
+            || bbox.max.lat() < -90.0
+            || bbox.min.lat() > 90.0
+            || bbox.max.lng() < -180.0
+            || bbox.min.lng() > 180.0
+        {
+            return Vec::new();
+        }
+        let rows = self.row_of(bbox.min.lat())..=self.row_of(bbox.max.lat());
+        let columns = self.column_of(bbox.min.lng())..=self.column_of(bbox.max.lng());
+        rows.flat_map(|row| columns.clone().map(move |column| (row, column)))
+            .map(|(row, column)| self.tile_at(row, column))
+            .collect()
+    }
+
+    /// Returns the tiles with any point within `radius_meters` of `center`.
+    pub fn tiles_within_radius(&self, center: &LatLng, radius_meters: f64) -> Vec<TileId> {
+        self.tiles_in_bbox(&BoundingBox::around(center, radius_meters))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_to_tile() {
+        let seattle = LatLng::new(47.6062, -122.3321);
+        // Row (47.6062 + 90) / 0.25 = 550, column (-122.3321 + 180) / 0.25 = 230.
+        let tile = TileLevel::LOCAL.tile_id(&seattle).unwrap();
+        assert_eq!(tile, TileId::from_level_index(2, 550 * 1440 + 230));
+        assert_eq!(tile.hierarchy_level(), 2);
+        let bounds = TileLevel::LOCAL.tile_bounds(&tile).unwrap();
+        assert!(bounds.contains(&seattle));
+        assert_eq!(bounds.min, LatLng::new(47.5, -122.5));
+        assert_eq!(bounds.max, LatLng::new(47.75, -122.25));
+
+        let highway = TileLevel::HIGHWAY.tile_id(&seattle).unwrap();
+        assert_eq!(highway, TileId::from_level_index(0, 34 * 90 + 14));
+        assert_eq!(
+            TileLevel::HIGHWAY.tile_id(&LatLng::new(90.0, 180.0)),
+            Some(TileId::from_level_index(0, 44 * 90 + 89))
+        );
+        assert_eq!(TileLevel::HIGHWAY.tile_id(&LatLng::new(91.0, 0.0)), None);
+        assert_eq!(TileLevel::ARTERIAL.tile_base(&highway), None);
+    }
+
+    #[test]
+    fn neighbors_wrap_antimeridian() {
+        let level = TileLevel::HIGHWAY;
+        let tile = level.tile_id(&LatLng::new(0.0, -179.0)).unwrap();
+        let neighbors = level.neighbors(&tile);
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&level.tile_id(&LatLng::new(0.0, 179.0)).unwrap()));
+
+        let south_pole = level.tile_id(&LatLng::new(-90.0, 0.0)).unwrap();
+        assert_eq!(level.neighbors(&south_pole).len(), 5);
+    }
+
+    #[test]
+    fn tiles_in_bbox_and_radius() {
+        let level = TileLevel::ARTERIAL;
+        let bbox = BoundingBox::new(LatLng::new(47.5, -122.5), LatLng::new(48.5, -121.5));
+        assert_eq!(level.tiles_in_bbox(&bbox).len(), 4);
+        let center = LatLng::new(47.0, -122.0);
+        let tiles = level.tiles_within_radius(&center, 1_000.0);
+        assert_eq!(tiles.len(), 4);
+        assert!(tiles.contains(&level.tile_id(&center).unwrap()));
+    }
+}
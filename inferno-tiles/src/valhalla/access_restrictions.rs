@@ -1,9 +1,23 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+use crate::inferno::attributes::{Access, AccessType};
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub(crate) struct ValhallaAccessRestriction {
     pub(crate) bitfield: ValhallaAccessRestrictionBitField,
 
@@ -12,8 +26,21 @@ pub(crate) struct ValhallaAccessRestriction {
     pub(crate) value: u64,
 }
 
+impl ValhallaAccessRestriction {
+    pub(crate) fn access_type(&self) -> AccessType {
+        AccessType::from_valhalla(self.bitfield.access_type())
+    }
+
+    /// Modes the restriction applies to.
+    pub(crate) fn modes(&self) -> Access {
+        Access::from_bits(self.bitfield.modes())
+    }
+}
+
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaAccessRestrictionBitField {
     // uint64_t edgeindex_ : 22;
     /// Directed edge index. Max index is kMaxTileEdgeCount in nodeinfo.h: 22 bits.
@@ -1,8 +1,20 @@
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub(crate) struct ValhallaAdmin {
     // uint32_t country_offset_;
     // country name offset
@@ -0,0 +1,120 @@
+use bitfield_struct::bitfield;
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+/// Fixed-size head of a complex restriction record. It is followed by `via_count` via edge IDs,
+/// each stored as a `ValhallaComplexRestrictionVia`.
+#[repr(C)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
+pub(crate) struct ValhallaComplexRestriction {
+    pub(crate) from: ValhallaComplexRestrictionEdge,
+    pub(crate) to: ValhallaComplexRestrictionEdge,
+    pub(crate) data: ValhallaComplexRestrictionData,
+    // TimeDomain time_domain_;
+    /// Date and time range the restriction applies to, if `has_dt` is set
+    pub(crate) time_domain: ValhallaTimeDomain,
+}
+
+#[bitfield(u64)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaComplexRestrictionEdge {
+    // uint64_t from_graphid_ : 46; / uint64_t to_graphid_ : 46;
+    /// Directed edge graph ID
+    #[bits(46)]
+    pub(crate) graph_id: u64,
+    // uint64_t spare_ : 18;
+    #[bits(18)]
+    _spare: u32,
+}
+
+/// Via edges use the same layout as the from/to edges.
+pub(crate) type ValhallaComplexRestrictionVia = ValhallaComplexRestrictionEdge;
+
+#[bitfield(u64)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaComplexRestrictionData {
+    // uint64_t via_count_ : 5;
+    /// Size of the via list
+    #[bits(5)]
+    pub(crate) via_count: usize,
+    // uint64_t type_ : 4;
+    /// Restriction type (see RestrictionType in graphconstants.h)
+    #[bits(4)]
+    pub(crate) restriction_type: u8,
+    // uint64_t modes_ : 12;
+    /// Mode(s) this restriction applies to
+    #[bits(12)]
+    pub(crate) modes: u16,
+    // uint64_t has_dt_ : 1;
+    /// Does this restriction have a date/time?
+    #[bits(1)]
+    pub(crate) has_dt: bool,
+    // uint64_t spare_ : 42;
+    #[bits(42)]
+    _spare: u64,
+}
+
+#[bitfield(u64)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaTimeDomain {
+    // uint64_t type : 1;
+    /// 0 = year, month, day. 1 = nth day of week
+    #[bits(1)]
+    pub(crate) dt_type: bool,
+    // uint64_t dow : 7;
+    /// Day of week mask, starting with Sunday
+    #[bits(7)]
+    pub(crate) dow: u8,
+    // uint64_t begin_month : 4;
+    #[bits(4)]
+    pub(crate) begin_month: u8,
+    // uint64_t begin_day_dow : 5;
+    /// Begin day of month, or day of week for nth day of week ranges
+    #[bits(5)]
+    pub(crate) begin_day_dow: u8,
+    // uint64_t begin_week : 3;
+    #[bits(3)]
+    pub(crate) begin_week: u8,
+    // uint64_t begin_hrs : 5;
+    #[bits(5)]
+    pub(crate) begin_hrs: u8,
+    // uint64_t begin_mins : 6;
+    #[bits(6)]
+    pub(crate) begin_mins: u8,
+    // uint64_t end_month : 4;
+    #[bits(4)]
+    pub(crate) end_month: u8,
+    // uint64_t end_day_dow : 5;
+    #[bits(5)]
+    pub(crate) end_day_dow: u8,
+    // uint64_t end_week : 3;
+    #[bits(3)]
+    pub(crate) end_week: u8,
+    // uint64_t end_hrs : 5;
+    #[bits(5)]
+    pub(crate) end_hrs: u8,
+    // uint64_t end_mins : 6;
+    #[bits(6)]
+    pub(crate) end_mins: u8,
+    // uint64_t spare : 10;
+    #[bits(10)]
+    _spare: u16,
+}
@@ -1,16 +1,31 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+use crate::inferno::{
+    attributes::{Access, CycleLane, RoadClass, SacScale, SpeedType, Surface, Use},
+    edge_info::InfernoEdgeInfo,
+};
 
 use super::{
-    edge_info::ValhallaEdgeInfo,
     graph_id::{GraphEntityId, TileId},
     node_info::ValhallaNodeInfo,
     HasEntityPointerInner,
 };
 
 #[repr(C)]
-#[derive(Debug, Clone, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub struct ValhallaDirectedEdge {
     /// Mostly data related to edge restrictions.
     pub(crate) restrictions1: ValhallaDirectedEdgeRestrictions1,
@@ -50,16 +65,58 @@ impl ValhallaDirectedEdge {
     pub fn opposing_edge_index(&self) -> usize {
         self.restrictions1.opp_index()
     }
+
+    pub fn edge_use(&self) -> Use {
+        Use::from_valhalla(self.data1.use_type())
+    }
+
+    pub fn road_class(&self) -> RoadClass {
+        RoadClass::from_valhalla(self.data1.classification())
+    }
+
+    pub fn surface(&self) -> Surface {
+        Surface::from_valhalla(self.data1.surface())
+    }
+
+    /// Modes allowed to travel along the edge in its direction.
+    pub fn forward_access(&self) -> Access {
+        Access::from_bits(self.data2.forward_access_mask())
+    }
+
+    /// Modes allowed to travel along the edge against its direction.
+    pub fn reverse_access(&self) -> Access {
+        Access::from_bits(self.data2.reverse_access_mask())
+    }
+
+    /// Modes with a general access restriction on this edge, stored in the tile's access
+    /// restriction list.
+    pub fn access_restrictions(&self) -> Access {
+        Access::from_bits(self.restrictions2.access_restriction() as u16)
+    }
+
+    pub fn cycle_lane(&self) -> CycleLane {
+        CycleLane::from_valhalla(self.data2.cycle_lane())
+    }
+
+    pub fn sac_scale(&self) -> SacScale {
+        SacScale::from_valhalla(self.data2.sac_scale())
+    }
+
+    pub fn speed_type(&self) -> SpeedType {
+        SpeedType::from_valhalla(self.data4.speed_type())
+    }
 }
 
-impl HasEntityPointerInner<ValhallaEdgeInfo> for ValhallaDirectedEdge {
-    fn get_unchecked(&self, tile_id: &TileId) -> GraphEntityId<ValhallaEdgeInfo> {
+impl HasEntityPointerInner<InfernoEdgeInfo> for ValhallaDirectedEdge {
+    fn get_unchecked(&self, tile_id: &TileId) -> GraphEntityId<InfernoEdgeInfo> {
         GraphEntityId::from_tile_index(tile_id, self.restrictions2.edge_info_offset())
     }
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaDirectedEdgeRestrictions1 {
     // // 1st 8-byte word
     // uint64_t endnode_ : 46;
@@ -92,7 +149,9 @@ pub struct ValhallaDirectedEdgeRestrictions1 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaDirectedEdgeRestrictions2 {
     // // 2nd 8 byte word
     // uint64_t edgeinfo_offset_ : 25;
@@ -126,7 +185,9 @@ pub struct ValhallaDirectedEdgeRestrictions2 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaDirectedEdgeData1 {
     // // 3rd 8-byte word. Note: speed values above 250 for special cases (closures, construction)
     // uint64_t speed_ : 8;
@@ -189,7 +250,9 @@ pub struct ValhallaDirectedEdgeData1 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaDirectedEdgeData2 {
     // // 4th 8-byte word
     // uint64_t forwardaccess_ : 12;
@@ -310,7 +373,9 @@ pub(crate) struct ValhallaDirectedEdgeData2 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaDirectedEdgeData3 {
     // // 5th 8-byte word
     // uint64_t turntype_ : 24;
@@ -336,7 +401,9 @@ pub struct ValhallaDirectedEdgeData3 {
 }
 
 #[bitfield(u32)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaDirectedEdgeData4 {
     // uint32_t localedgeidx_ : 7;
     // Index of the edge on the local level
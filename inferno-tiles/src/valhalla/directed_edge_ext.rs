@@ -1,9 +1,21 @@
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[repr(C)]
-#[derive(Debug, Clone, Archive, FromBytes, KnownLayout, Immutable)]
-
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
+/// Extension record of a directed edge, which Valhalla reserves for future attributes. Tiles with
+/// `has_ext_directededge` set have one per directed edge, after the directed edges.
 pub(crate) struct ValhallaDirectedEdgeExt {
     _spare: u64,
 }
@@ -1,10 +1,29 @@
-use std::{fmt::Display, marker::PhantomData};
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
 
-use rkyv::Archive;
+use rkyv::{Archive, Deserialize, Serialize};
 use zerocopy::{FromBytes, Immutable, KnownLayout};
 
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Archive, KnownLayout, FromBytes, Immutable)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    KnownLayout,
+    FromBytes,
+    Immutable,
+)]
 pub struct TileId {
     pub(crate) id: u64,
 }
@@ -13,6 +32,24 @@ impl TileId {
     pub(crate) fn new(id: u64) -> Self {
         TileId { id }
     }
+
+    #[inline]
+    pub fn hierarchy_level(&self) -> u8 {
+        (self.id & 0x7) as u8
+    }
+
+    /// Returns the ID of the tile at `index` on hierarchy level `level`.
+    pub fn from_level_index(level: u8, index: u32) -> Self {
+        TileId {
+            id: ((index as u64) << 3) | (level as u64 & 0x7),
+        }
+    }
+
+    /// Index of the tile within its hierarchy level.
+    #[inline]
+    pub fn tile_index(&self) -> u32 {
+        ((self.id >> 3) & 0x3f_ffff) as u32
+    }
 }
 
 impl Display for TileId {
@@ -22,12 +59,36 @@ impl Display for TileId {
 }
 
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Debug)]
 pub struct GraphEntityId<Inner> {
     pub(crate) graph_entity_id: u64,
     _phantom: PhantomData<Inner>,
 }
 
+// These are implemented by hand because deriving them would require `Inner` to implement them
+// too, even though `Inner` is only a marker.
+impl<Inner> Clone for GraphEntityId<Inner> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Inner> Copy for GraphEntityId<Inner> {}
+
+impl<Inner> PartialEq for GraphEntityId<Inner> {
+    fn eq(&self, other: &Self) -> bool {
+        self.graph_entity_id == other.graph_entity_id
+    }
+}
+
+impl<Inner> Eq for GraphEntityId<Inner> {}
+
+impl<Inner> Hash for GraphEntityId<Inner> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.graph_entity_id.hash(state);
+    }
+}
+
 impl<Inner> GraphEntityId<Inner> {
     #[inline]
     pub fn new(graph_entity_id: u64) -> Self {
@@ -0,0 +1,47 @@
+use bitfield_struct::bitfield;
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+#[repr(C)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
+pub(crate) struct ValhallaLaneConnectivity {
+    pub(crate) to: ValhallaLaneConnectivityTo,
+
+    // uint64_t from_;
+    /// OSM way ID of the from edge
+    pub(crate) from_way_id: u64,
+
+    // LaneConnectivityLanes to_lanes_;
+    /// Lanes on the to edge, 4 bits per lane, 1-based with 0 meaning no lane
+    pub(crate) to_lanes: u64,
+
+    // LaneConnectivityLanes from_lanes_;
+    /// Lanes on the from edge, paired position by position with `to_lanes`
+    pub(crate) from_lanes: u64,
+}
+
+#[bitfield(u64)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaLaneConnectivityTo {
+    // uint64_t to_ : 22;
+    /// Index of the to edge in this tile
+    #[bits(22)]
+    pub(crate) edge_index: usize,
+    // uint64_t spare_ : 42;
+    #[bits(42)]
+    _spare: u64,
+}
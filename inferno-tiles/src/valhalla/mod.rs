@@ -4,19 +4,25 @@ use graph_id::{GraphEntityId, TileId};
 
 pub(crate) mod access_restrictions;
 pub(crate) mod admin;
+pub(crate) mod complex_restriction;
 pub(crate) mod directed_edge;
 pub(crate) mod directed_edge_ext;
 pub(crate) mod edge_info;
 pub(crate) mod graph_id;
+pub(crate) mod lane_connectivity;
+pub(crate) mod name_info;
 pub(crate) mod node_info;
 pub(crate) mod node_transition;
 pub(crate) mod sign;
 pub(crate) mod tile_header;
+pub(crate) mod tile_index;
 pub(crate) mod transit_departure;
 pub(crate) mod transit_route;
 pub(crate) mod transit_schedule;
 pub(crate) mod transit_stop;
 pub(crate) mod transit_transfer;
+pub(crate) mod turn_lanes;
+pub(crate) mod varint;
 
 pub trait HasEntityPointer<Inner> {
     fn get_entity(&self) -> GraphEntityId<Inner>;
@@ -43,7 +49,7 @@ impl<Inner> VEntity<Inner> {
         self.tile_id
     }
 
-    pub fn inner<'a>(&'a self) -> &'a Inner {
+    pub fn inner(&self) -> &Inner {
         &self.inner
     }
 }
@@ -0,0 +1,29 @@
+use bitfield_struct::bitfield;
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+#[bitfield(u32)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaNameInfo {
+    // uint32_t name_offset_ : 24;
+    /// Offset to the name in the text list
+    #[bits(24)]
+    pub(crate) name_offset: usize,
+    // uint32_t additional_fields_ : 4;
+    /// Additional text fields following the name (language, phonetic string, etc.)
+    #[bits(4)]
+    pub(crate) additional_fields: u8,
+    // uint32_t is_route_num_ : 1;
+    /// Is this a route number rather than a name?
+    #[bits(1)]
+    pub(crate) is_route_number: bool,
+    // uint32_t tagged_ : 1;
+    /// Is the name tagged? Tagged names carry their tag type in the first byte of the text
+    #[bits(1)]
+    pub(crate) tagged: bool,
+    // uint32_t spare_ : 2;
+    #[bits(2)]
+    _spare: u8,
+}
@@ -1,13 +1,30 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
-use crate::{geomath::LatLng, inferno::InfernoTile};
+use crate::{
+    geomath::LatLng,
+    inferno::{
+        attributes::{Access, IntersectionType, NodeType},
+        tile_ref::{AsTileRef, TileRef},
+    },
+};
 
 use super::{directed_edge::ValhallaDirectedEdge, graph_id::GraphEntityId};
 
 #[repr(C)]
-#[derive(Debug, Clone, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub struct ValhallaNodeInfo {
     pub(crate) position_info: ValhallaNodeInfoPositionInfo,
 
@@ -24,23 +41,62 @@ pub struct ValhallaNodeInfo {
     pub(crate) headings: u64,
 }
 
+/// Minimum node elevation Valhalla can store, in meters.
+const NODE_MIN_ELEVATION: f32 = -500.0;
+/// Precision of the encoded node elevation, in meters.
+const NODE_ELEVATION_PRECISION: f32 = 0.25;
+
 impl ValhallaNodeInfo {
-    pub fn position(&self, tile: &InfernoTile) -> LatLng {
-        let lat =
-            tile.base_lat_lng().lat() as f64 + (self.position_info.lat_offset() as f64 / 1000000.0);
-        let lng =
-            tile.base_lat_lng().lng() as f64 + (self.position_info.lon_offset() as f64 / 1000000.0);
+    pub fn position(&self, tile: &impl AsTileRef) -> LatLng {
+        self.position_from_base(&tile.tile_ref().base_lat_lng())
+    }
+
+    /// Position of the node relative to its tile's base position, with the full 7 digit precision
+    /// of Valhalla's `NodeInfo::latlng`.
+    pub(crate) fn position_from_base(&self, base: &LatLng) -> LatLng {
+        let lat = base.lat()
+            + (self.position_info.lat_offset() as f64 * 1e-6
+                + self.position_info.lat_offset7() as f64 * 1e-7);
+        let lng = base.lng()
+            + (self.position_info.lon_offset() as f64 * 1e-6
+                + self.position_info.lon_offset7() as f64 * 1e-7);
         LatLng::new(lat, lng)
     }
 
-    pub(crate) fn edges<'a>(&self, tile: &'a InfernoTile) -> &'a [ValhallaDirectedEdge] {
+    /// Elevation of the node in meters, or `None` if the tile has no elevation data.
+    pub fn elevation(&self, tile: &impl AsTileRef) -> Option<f32> {
+        tile.tile_ref()
+            .has_elevation()
+            .then(|| self.decoded_elevation())
+    }
+
+    fn decoded_elevation(&self) -> f32 {
+        NODE_MIN_ELEVATION + self.data2.elevation() as f32 * NODE_ELEVATION_PRECISION
+    }
+
+    pub fn node_type(&self) -> NodeType {
+        NodeType::from_valhalla(self.data1.node_type() as u8)
+    }
+
+    pub fn intersection_type(&self) -> IntersectionType {
+        IntersectionType::from_valhalla(self.data1.intersection() as u8)
+    }
+
+    /// Modes allowed to pass through the node.
+    pub fn access(&self) -> Access {
+        Access::from_bits(self.position_info.access())
+    }
+
+    pub(crate) fn edges<'a>(&self, tile: &TileRef<'a>) -> &'a [ValhallaDirectedEdge] {
         let edge_entity = GraphEntityId::from_tile_index(&tile.tile_id(), self.data1.edge_index());
         tile.edge_slice(edge_entity, self.data1.edge_count())
     }
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaNodeInfoPositionInfo {
     // // 26 bits for lat,lon offset allows 7 digits of precision even in 4 degree tiles
     // // to stay backwards compatible we have to break 6 digits and the 7th digit into two parts
@@ -69,7 +125,9 @@ pub struct ValhallaNodeInfoPositionInfo {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaNodeInfoData1 {
     // uint64_t edge_index_ : 21;
     /// Index within the node's tile of its first outbound directed edge
@@ -123,7 +181,9 @@ pub struct ValhallaNodeInfoData1 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaNodeInfoData2 {
     // uint64_t transition_index_ : 21;
     /// Index into the node transitions to the first transition (used to store transit stop index for transit level)
@@ -178,3 +238,102 @@ pub struct ValhallaNodeInfoData2 {
     #[bits(1)]
     _spare2: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{inferno::builder::GraphBuilder, valhalla::graph_id::TileId};
+
+    use super::*;
+
+    fn node(
+        lat_offset: u32,
+        lat_offset7: u8,
+        lon_offset: u32,
+        lon_offset7: u8,
+    ) -> ValhallaNodeInfo {
+        ValhallaNodeInfo {
+            position_info: ValhallaNodeInfoPositionInfo::new()
+                .with_lat_offset(lat_offset)
+                .with_lat_offset7(lat_offset7)
+                .with_lon_offset(lon_offset)
+                .with_lon_offset7(lon_offset7),
+            data1: ValhallaNodeInfoData1::new(),
+            data2: ValhallaNodeInfoData2::new(),
+            headings: 0,
+        }
+    }
+
+    fn assert_near(actual: LatLng, expected: LatLng) {
+        assert!(
+            (actual.lat() - expected.lat()).abs() < 1e-9
+                && (actual.lng() - expected.lng()).abs() < 1e-9,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn position_uses_seventh_digit() {
+        // The Space Needle's OSM node, at 47.6205063, -122.3492774, in local tile 792230 whose
+        // base is 47.5, -122.5. Valhalla's `NodeInfo::set_latlng` stores it as these offsets,
+        // and `NodeInfo::latlng` gives back the OSM coordinate.
+        let base = LatLng::new(47.5, -122.5);
+        let space_needle = LatLng::new(47.6205063, -122.3492774);
+        assert_near(
+            node(120_506, 3, 150_722, 6).position_from_base(&base),
+            space_needle,
+        );
+
+        // A tile written by the builder, like Valhalla's, stores the same offsets.
+        let mut builder = GraphBuilder::new();
+        let handle = builder.add_node(space_needle);
+        let graph = builder.build().unwrap();
+        let tile = &graph.tiles()[0];
+        assert_eq!(tile.tile_id(), TileId::new(792_230 << 3 | 2));
+        assert_eq!(tile.base_lat_lng(), base);
+        let node = tile.tile_ref().nodes.get(&graph.node_id(handle)).unwrap();
+        assert_eq!(
+            (
+                node.position_info.lat_offset(),
+                node.position_info.lat_offset7(),
+                node.position_info.lon_offset(),
+                node.position_info.lon_offset7()
+            ),
+            (120_506, 3, 150_722, 6)
+        );
+        assert_near(node.position(tile), space_needle);
+    }
+
+    #[test]
+    fn position_without_seventh_digit() {
+        // A point at 6 digit precision has no 7th digit, like this one in Cape Town, in the tile
+        // based at -34.0, 18.25.
+        let base = LatLng::new(-34.0, 18.25);
+        assert_near(
+            node(75_000, 0, 174_060, 0).position_from_base(&base),
+            LatLng::new(-33.925, 18.42406),
+        );
+        // With the 7th digit, an OSM node near the same point keeps its full precision.
+        assert_near(
+            node(75_131, 5, 174_055, 3).position_from_base(&base),
+            LatLng::new(-33.9248685, 18.4240553),
+        );
+    }
+
+    #[test]
+    fn elevation_decoding() {
+        // Elevations as Valhalla's `NodeInfo::elevation` decodes them: the lowest it can store,
+        // sea level, the summit of Mount Rainier at 4392 m, and the highest 15 bit value.
+        let mut node = node(0, 0, 0, 0);
+        for (encoded, elevation) in [
+            (0, -500.0),
+            (2_000, 0.0),
+            (19_568, 4_392.0),
+            (32_767, 7_691.75),
+        ] {
+            node.data2.set_elevation(encoded);
+            assert_eq!(node.decoded_elevation(), elevation);
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaNodeTransition {
     // uint64_t endnode_ : 46;
     #[bits(46)]
@@ -1,23 +1,31 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[bitfield(u64)]
-#[derive(Archive)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaSign {
     // kMaxTileEdgeCount in nodeinfo.h: 22 bits
     // uint32_t index_ : 22;
+    /// Directed edge index, or node index for node signs
     #[bits(22)]
-    index: u32,
+    pub(crate) index: usize,
     // uint32_t type_ : 8;
+    /// Sign type (see Sign::Type in sign.h)
     #[bits(8)]
-    sign_type: u8,
+    pub(crate) sign_type: u8,
     // uint32_t route_num_type_ : 1;
+    /// Is the sign text a route number?
     #[bits(1)]
-    route_num_type: u8,
+    pub(crate) route_num_type: bool,
     // uint32_t tagged_ : 1;
+    /// Is the sign text tagged? Tagged text carries its tag type in the first byte
     #[bits(1)]
-    tagged: bool,
+    pub(crate) tagged: bool,
     // uint32_t text_offset_;
+    /// Offset into the text list
     #[bits(32)]
-    text_offset: u32,
+    pub(crate) text_offset: usize,
 }
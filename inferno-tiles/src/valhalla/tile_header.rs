@@ -1,9 +1,21 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub struct ValhallaTileHeader {
     pub(crate) metadata: ValhallaTileHeaderMetadata,
 
@@ -63,7 +75,9 @@ pub struct ValhallaTileHeader {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaTileHeaderMetadata {
     // uint64_t graphid_ : 46;
     #[bits(46)]
@@ -95,7 +109,9 @@ pub struct ValhallaTileHeaderMetadata {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub struct ValhallaTileHeaderCounts1 {
     // uint64_t nodecount_ : 21;
     #[bits(21)]
@@ -115,7 +131,9 @@ pub struct ValhallaTileHeaderCounts1 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTileHeaderCounts2 {
     // uint32_t transitioncount_ : 22;
     #[bits(22)]
@@ -135,7 +153,9 @@ pub(crate) struct ValhallaTileHeaderCounts2 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTileHeaderCounts3 {
     // uint64_t transfercount_ : 16;
     #[bits(16)]
@@ -159,7 +179,9 @@ pub(crate) struct ValhallaTileHeaderCounts3 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTileHeaderCounts4 {
     // uint64_t routecount_ : 12;
     #[bits(12)]
@@ -179,7 +201,9 @@ pub(crate) struct ValhallaTileHeaderCounts4 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTileHeaderCounts5 {
     // uint64_t access_restriction_count_ : 24;
     #[bits(24)]
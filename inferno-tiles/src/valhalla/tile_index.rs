@@ -0,0 +1,16 @@
+use zerocopy::{FromBytes, Immutable, KnownLayout};
+
+/// An entry of the index.bin that valhalla_build_extract writes at the start of a tile tarball.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, KnownLayout, Immutable)]
+pub(crate) struct ValhallaTileIndexEntry {
+    // uint64_t offset;
+    /// Offset of the tile's data from the start of the tarball
+    pub(crate) offset: u64,
+    // uint32_t tile_id;
+    /// Graph ID of the tile
+    pub(crate) tile_id: u32,
+    // uint32_t size;
+    /// Size of the tile in bytes
+    pub(crate) size: u32,
+}
@@ -1,61 +1,105 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub(crate) struct ValhallaTransitDeparture {
     pub(crate) data1: ValhallaTransitDepartureBitfield1,
     pub(crate) data2: ValhallaTransitDepartureBitfield2,
 
-    pub(crate) data3_union: u64,
+    // union {
+    //   struct { uint64_t departure_time_ : 17; uint64_t elapsed_time_ : 17; uint64_t spare_ : 30; } fixed;
+    //   struct { uint64_t departure_time_ : 17; uint64_t elapsed_time_ : 17; uint64_t end_time_ : 17;
+    //            uint64_t frequency_ : 13; } frequency;
+    // };
+    pub(crate) data3_union: ValhallaTransitDepartureTimes,
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTransitDepartureBitfield1 {
     // uint64_t lineid_ : 20;
     /// Line Id - lookup departures by unique line id (which indicates a unique departure / arrival stop pair.
     #[bits(20)]
-    line_id: u64,
+    pub(crate) line_id: u64,
     // uint64_t routeindex_ : 12;
     /// Route index.
     #[bits(12)]
-    route_index: u64,
+    pub(crate) route_index: u64,
     // uint64_t tripid_ : 32;
     /// TripId (internal).
     #[bits(32)]
-    trip_id: u64,
+    pub(crate) trip_id: u64,
     // uint64_t blockid_ : 20;
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTransitDepartureBitfield2 {
     /// Block Id
     #[bits(20)]
-    block_id: u64,
+    pub(crate) block_id: u64,
     // uint64_t schedule_index_ : 12;
     /// Schedule validity index
     #[bits(12)]
-    schedule_index: u64,
+    pub(crate) schedule_index: u64,
     // uint64_t headsign_offset_ : 24;
     /// Headsign offset into the names/text list.
     #[bits(24)]
-    headsign_offset: u64,
+    pub(crate) headsign_offset: u64,
     // uint64_t type_ : 2;
     /// Departure type (fixed, frequency)
     #[bits(2)]
-    departure_type: u64,
+    pub(crate) departure_type: u64,
     // uint64_t wheelchair_accessible_ : 1;
     /// Is the vehicle wheelchair accessible?
     #[bits(1)]
-    wheelchair_accessible: bool,
+    pub(crate) wheelchair_accessible: bool,
     // uint64_t bicycle_accessible_ : 1;
     /// Is the vehicle bicycle accessible?
     #[bits(1)]
-    bicycle_accessible: bool,
+    pub(crate) bicycle_accessible: bool,
     // uint64_t spare_ : 4;
     #[bits(4)]
-    spare: u64,
+    _spare: u64,
+}
+
+#[bitfield(u64)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaTransitDepartureTimes {
+    // uint64_t departure_time_ : 17;
+    /// Departure time (seconds from midnight)
+    #[bits(17)]
+    pub(crate) departure_time: u32,
+    // uint64_t elapsed_time_ : 17;
+    /// Time (seconds) until arrival at the next stop
+    #[bits(17)]
+    pub(crate) elapsed_time: u32,
+    // uint64_t end_time_ : 17;
+    /// End time (seconds from midnight) of frequency-based departures. Spare for fixed departures
+    #[bits(17)]
+    pub(crate) end_time: u32,
+    // uint64_t frequency_ : 13;
+    /// Frequency (seconds) of frequency-based departures. Spare for fixed departures
+    #[bits(13)]
+    pub(crate) frequency: u32,
 }
@@ -1,21 +1,35 @@
 use bitfield_struct::bitfield;
-use rkyv::Archive;
-use zerocopy::{FromBytes, Immutable, KnownLayout};
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    FromBytes,
+    IntoBytes,
+    KnownLayout,
+    Immutable,
+)]
 pub(crate) struct ValhallaTransitRoute {
-    route_color: u32,
-    route_text_color: u32,
+    pub(crate) route_color: u32,
+    pub(crate) route_text_color: u32,
 
-    data1: ValhallaTransitRouteData1,
-    data2: ValhallaTransitRouteData2,
-    data3: ValhallaTransitRouteData3,
-    data4: ValhallaTransitRouteData4,
+    pub(crate) data1: ValhallaTransitRouteData1,
+    pub(crate) data2: ValhallaTransitRouteData2,
+    pub(crate) data3: ValhallaTransitRouteData3,
+    pub(crate) data4: ValhallaTransitRouteData4,
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTransitRouteData1 {
     // uint64_t route_type_ : 8;
     /// Internal route type
@@ -31,7 +45,9 @@ pub(crate) struct ValhallaTransitRouteData1 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTransitRouteData2 {
     // uint64_t op_by_onestop_id_offset_ : 24;
     /// operated by onestop id.
@@ -47,32 +63,36 @@ pub(crate) struct ValhallaTransitRouteData2 {
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTransitRouteData3 {
     // uint64_t op_by_website_offset_ : 24;
     /// operated by website.
     #[bits(24)]
-    op_by_website_offset: usize,
+    pub(crate) op_by_website_offset: usize,
     // uint64_t short_name_offset_ : 24;
     /// Short route name.
     #[bits(24)]
-    short_name_offset: usize,
+    pub(crate) short_name_offset: usize,
     // uint64_t spare3_ : 16;
     #[bits(16)]
     _spare3: u16,
 }
 
 #[bitfield(u64)]
-#[derive(Archive, FromBytes, KnownLayout, Immutable)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
 pub(crate) struct ValhallaTransitRouteData4 {
     // uint64_t long_name_offset_ : 24;
     /// Long route name.
     #[bits(24)]
-    long_name_offset: usize,
+    pub(crate) long_name_offset: usize,
     // uint64_t desc_offset_ : 24;
     /// Route description.
     #[bits(24)]
-    desc_offset: usize,
+    pub(crate) desc_offset: usize,
     // uint64_t spare4_ : 16;
     #[bits(16)]
     _spare4: u16,
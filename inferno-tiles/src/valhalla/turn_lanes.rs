@@ -0,0 +1,21 @@
+use bitfield_struct::bitfield;
+use rkyv::{Archive, Deserialize, Serialize};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+#[bitfield(u64)]
+#[derive(
+    PartialEq, Archive, Serialize, Deserialize, FromBytes, IntoBytes, KnownLayout, Immutable,
+)]
+pub(crate) struct ValhallaTurnLanes {
+    // uint32_t edgeindex_ : 22;
+    /// Directed edge index. Max index is kMaxTileEdgeCount in nodeinfo.h: 22 bits.
+    #[bits(22)]
+    pub(crate) edge_index: usize,
+    // uint32_t spare_ : 10;
+    #[bits(10)]
+    _spare: u16,
+    // uint32_t text_offset_ : 32;
+    /// Offset into the text list of the "|"-separated lane masks
+    #[bits(32)]
+    pub(crate) text_offset: usize,
+}
@@ -0,0 +1,40 @@
+/// Reads an unsigned LEB128-style varint (7 bits per byte, least significant group first) from
+/// `bytes` starting at `*ptr`, advancing `ptr` past it.
+pub(crate) fn read_varint(bytes: &[u8], ptr: &mut usize) -> Result<u64, anyhow::Error> {
+    let mut result = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes
+            .get(*ptr)
+            .ok_or_else(|| anyhow::anyhow!("Truncated varint at offset {}", *ptr))?;
+        *ptr += 1;
+        if shift >= 64 {
+            return Err(anyhow::anyhow!("Varint too long at offset {}", *ptr));
+        }
+        result |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+}
+
+/// Reads a zigzag-encoded signed varint.
+pub(crate) fn read_signed_varint(bytes: &[u8], ptr: &mut usize) -> Result<i64, anyhow::Error> {
+    let value = read_varint(bytes, ptr)?;
+    Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+}
+
+/// Appends `value` to `bytes` as an unsigned varint, the inverse of `read_varint`.
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Appends `value` to `bytes` as a zigzag-encoded signed varint.
+pub(crate) fn write_signed_varint(bytes: &mut Vec<u8>, value: i64) {
+    write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}